@@ -28,13 +28,13 @@ fn get_test_corpus() -> Vec<String> {
     .collect::<Vec<_>>()
 }
 
-fn token_entry_old(tokens: &mut Vec<String>) {
+fn token_entry_old(tokens: &mut [String]) {
     tokens.iter_mut().for_each(|token| {
         let _ = token.to_string();
     });
 }
 
-fn token_entry_new(tokens: &mut Vec<String>) {
+fn token_entry_new(tokens: &mut [String]) {
     tokens.iter_mut().for_each(|token| {
         let _ = std::mem::take(token);
     });
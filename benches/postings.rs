@@ -0,0 +1,98 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hashbrown::HashSet;
+use idx::{
+    core::{RefEntry, TfEntry},
+    posting::PostingList,
+};
+
+const DOCUMENTS: usize = 100_000;
+
+// Dense term in every other document, sparse term in every 97th.
+fn entries(step: usize) -> impl Iterator<Item = TfEntry> {
+    (0..DOCUMENTS)
+        .step_by(step)
+        .map(|index| TfEntry::new(index, index % 7 + 1))
+}
+
+fn hash_set(step: usize) -> HashSet<RefEntry> {
+    entries(step).map(RefEntry::new).collect()
+}
+
+fn posting_list(step: usize) -> PostingList {
+    PostingList::from_sorted(entries(step))
+}
+
+fn hash_set_size(set: &HashSet<RefEntry>) -> usize {
+    // One control byte per bucket, besides the entry itself.
+    set.capacity() * (std::mem::size_of::<RefEntry>() + 1)
+}
+
+fn bench_posting_memory(_: &mut Criterion) {
+    let set = hash_set(2);
+    let list = posting_list(2);
+
+    println!(
+        "postings-memory: {} postings, hash set {} bytes, posting list {} bytes",
+        list.len(),
+        hash_set_size(&set),
+        list.size_in_bytes()
+    );
+}
+
+fn bench_posting_iteration(c: &mut Criterion) {
+    let set = hash_set(2);
+    let list = posting_list(2);
+
+    c.bench_function("postings-iterate-hash-set", |b| {
+        b.iter(|| {
+            set.iter()
+                .map(|entry| *entry.get_frequency())
+                .sum::<usize>()
+        })
+    });
+
+    c.bench_function("postings-iterate-posting-list", |b| {
+        b.iter(|| {
+            list.cursor()
+                .map(|entry| *entry.get_frequency())
+                .sum::<usize>()
+        })
+    });
+}
+
+fn bench_posting_intersection(c: &mut Criterion) {
+    let (dense_set, sparse_set) = (hash_set(2), hash_set(97));
+    let (dense_list, sparse_list) = (posting_list(2), posting_list(97));
+
+    c.bench_function("postings-intersect-hash-set", |b| {
+        b.iter(|| {
+            sparse_set
+                .iter()
+                .filter(|entry| dense_set.contains(black_box(*entry)))
+                .count()
+        })
+    });
+
+    c.bench_function("postings-intersect-posting-list", |b| {
+        b.iter(|| {
+            let mut dense = dense_list.cursor();
+            sparse_list
+                .cursor()
+                .filter(|entry| {
+                    let index = entry.get_index();
+                    dense
+                        .advance_to(black_box(index))
+                        .is_some_and(|entry| entry.get_index() == index)
+                })
+                .count()
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_posting_memory,
+    bench_posting_iteration,
+    bench_posting_intersection
+);
+criterion_main!(benches);
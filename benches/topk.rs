@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use idx::aggregate::{Page, TopK};
+
+const HITS: usize = 100_000;
+
+// Scores of a common term, spread without any order.
+fn hits() -> Vec<(usize, f32)> {
+    (0..HITS)
+        .map(|index| (index, ((index * 7919) % 10_007) as f32 / 10_007.0))
+        .collect()
+}
+
+fn bench_collect(c: &mut Criterion) {
+    let hits = hits();
+    let page = Page::first(10);
+
+    c.bench_function("collect-sort-all", |b| {
+        b.iter(|| {
+            let mut sorted = black_box(&hits).clone();
+            sorted.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            sorted.truncate(page.limit);
+            sorted
+        })
+    });
+
+    c.bench_function("collect-top-k", |b| {
+        b.iter(|| {
+            let mut top = TopK::for_page(page);
+            top.extend(black_box(&hits).iter().copied());
+            top.into_page(page)
+        })
+    });
+}
+
+criterion_group!(benches, bench_collect);
+criterion_main!(benches);
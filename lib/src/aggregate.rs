@@ -1,9 +1,14 @@
-use std::fmt::Debug;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BinaryHeap},
+    fmt::Debug,
+};
 
 use hashbrown::hash_map::{HashMap, Iter};
 
-// TODO:
-// - add limit
+/// Sums the values per key, every key is kept.
+///
+/// See [`TopK`] to keep only the best scored keys.
 pub trait Aggregation: Debug {
     type Key;
     type Value;
@@ -48,7 +53,7 @@ impl<A: Aggregation> Aggregator<A> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct HashAggregator {
     inner: HashMap<usize, f32>,
 }
@@ -95,3 +100,264 @@ impl Aggregation for HashAggregator {
         items
     }
 }
+
+/// Range of ranked hits to return.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Page {
+    #[inline]
+    pub fn new(offset: usize, limit: usize) -> Self {
+        Self { offset, limit }
+    }
+
+    /// The first `limit` hits.
+    #[inline]
+    pub fn first(limit: usize) -> Self {
+        Self::new(0, limit)
+    }
+
+    /// Number of best hits needed to fill the page.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.offset.saturating_add(self.limit)
+    }
+}
+
+impl Default for Page {
+    /// Every hit.
+    fn default() -> Self {
+        Self::new(0, usize::MAX)
+    }
+}
+
+/// Scored resource, ordered by score then by decreasing index,
+/// so that ties rank the lowest index first.
+#[derive(Clone, Copy, Debug)]
+struct Hit {
+    index: usize,
+    score: f32,
+}
+
+impl Ord for Hit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Hit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Hit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Hit {}
+
+/// Collects the `k` best scored resources with a bounded min-heap.
+///
+/// Each resource is inserted once, with its final score. Inserting `n`
+/// resources costs `O(n log k)` and keeps `k` of them in memory, instead of
+/// sorting all of them.
+#[derive(Debug)]
+pub struct TopK {
+    k: usize,
+
+    /// Worst kept hit on top.
+    heap: BinaryHeap<Reverse<Hit>>,
+
+    /// Number of inserted resources.
+    total: usize,
+}
+
+impl TopK {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::new(),
+            total: 0,
+        }
+    }
+
+    /// Collects the hits needed to fill the page.
+    pub fn for_page(page: Page) -> Self {
+        Self::new(page.end())
+    }
+
+    pub fn insert(&mut self, index: usize, score: f32) {
+        self.total += 1;
+        let hit = Hit { index, score };
+
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(hit));
+            return;
+        }
+
+        match self.heap.peek_mut() {
+            Some(mut worst) if hit > worst.0 => *worst = Reverse(hit),
+            _ => {}
+        }
+    }
+
+    /// Lowest score a resource needs to be kept, once `k` resources are kept.
+    pub fn threshold(&self) -> Option<f32> {
+        match self.heap.len() < self.k {
+            true => None,
+            false => self.heap.peek().map(|worst| worst.0.score),
+        }
+    }
+
+    /// Number of inserted resources, kept or not.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Kept resources, by decreasing score.
+    pub fn into_sorted(self) -> Vec<(usize, f32)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(hit)| (hit.index, hit.score))
+            .collect()
+    }
+
+    /// Kept resources within the page, by decreasing score.
+    pub fn into_page(self, page: Page) -> Vec<(usize, f32)> {
+        let mut hits = self.into_sorted();
+        hits.drain(..page.offset.min(hits.len()));
+        hits.truncate(page.limit);
+        hits
+    }
+}
+
+impl Extend<(usize, f32)> for TopK {
+    fn extend<T: IntoIterator<Item = (usize, f32)>>(&mut self, iter: T) {
+        iter.into_iter()
+            .for_each(|(index, score)| self.insert(index, score));
+    }
+}
+
+/// Number of resources per facet value, for every field.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FacetCounts {
+    inner: BTreeMap<String, HashMap<String, usize>>,
+}
+
+impl FacetCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the `(field, value)` pairs of a resource.
+    pub fn insert<'a>(&mut self, facets: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        for (field, value) in facets {
+            *self
+                .inner
+                .entry(field.to_string())
+                .or_default()
+                .entry_ref(value)
+                .or_default() += 1;
+        }
+    }
+
+    /// Values of the field with their count, by decreasing count then value.
+    pub fn get(&self, field: &str) -> Vec<(&str, usize)> {
+        let mut values = self
+            .inner
+            .get(field)
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|(value, &count)| (value.as_str(), count))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        values.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        values
+    }
+
+    /// Counted fields, in order.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.inner.keys().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aggregate::{Page, TopK};
+
+    #[test]
+    fn test_top_k() {
+        let mut top = TopK::new(3);
+        top.extend([(0, 1.0), (1, 5.0), (2, 3.0), (3, 4.0), (4, 2.0), (5, 4.0)]);
+
+        assert_eq!(top.total(), 6);
+        assert_eq!(top.threshold(), Some(4.0));
+
+        // Ties rank the lowest index first.
+        assert_eq!(top.into_sorted(), [(1, 5.0), (3, 4.0), (5, 4.0)]);
+
+        let mut top = TopK::new(0);
+        top.insert(0, 1.0);
+        assert_eq!(top.total(), 1);
+        assert!(top.into_sorted().is_empty());
+    }
+
+    #[test]
+    fn test_top_k_matches_sort() {
+        let scores = (0..500)
+            .map(|index| (index, ((index * 7919) % 263) as f32 / 10.0))
+            .collect::<Vec<_>>();
+
+        let mut sorted = scores.clone();
+        sorted.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        for k in [1, 10, 263, 1000] {
+            let mut top = TopK::new(k);
+            top.extend(scores.iter().copied());
+            let expected = &sorted[..k.min(sorted.len())];
+
+            assert_eq!(top.into_sorted(), expected, "k = {k}");
+        }
+    }
+
+    #[test]
+    fn test_top_k_page() {
+        let hits = (0..10).map(|index| (index, 10.0 - index as f32));
+
+        let page = Page::new(4, 3);
+        let mut top = TopK::for_page(page);
+        top.extend(hits.clone());
+        assert_eq!(top.total(), 10);
+        assert_eq!(top.into_page(page), [(4, 6.0), (5, 5.0), (6, 4.0)]);
+
+        // Past the last hit.
+        let page = Page::new(8, 5);
+        let mut top = TopK::for_page(page);
+        top.extend(hits.clone());
+        assert_eq!(top.into_page(page), [(8, 2.0), (9, 1.0)]);
+
+        let page = Page::new(20, 5);
+        let mut top = TopK::for_page(page);
+        top.extend(hits);
+        assert!(top.into_page(page).is_empty());
+
+        assert_eq!(Page::default().end(), usize::MAX);
+    }
+}
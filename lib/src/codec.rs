@@ -0,0 +1,250 @@
+//! Binary encoding shared by the on-disk formats.
+//!
+//! The [`Codec`] trait converts the resource type `R` to and from bytes,
+//! everything else (terms, postings, counters) is encoded by the index itself
+//! using little-endian integers and length-prefixed byte strings.
+
+use std::fmt::Debug;
+
+use crate::{
+    error::{Error, SegmentError},
+    facet::Facets,
+};
+
+/// Converts a resource to and from its binary representation.
+pub trait Codec<R>: Debug + Send + Sync {
+    fn encode(&self, resource: &R, buffer: &mut Vec<u8>);
+    fn decode(&self, bytes: &[u8]) -> Result<R, Error>;
+}
+
+/// UTF-8 codec for `String` resources, such as file paths or URLs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StringCodec;
+
+impl StringCodec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Codec<String> for StringCodec {
+    fn encode(&self, resource: &String, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(resource.as_bytes());
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<String, Error> {
+        String::from_utf8(bytes.to_vec())
+            .map_err(|error| Error::from(SegmentError::Codec(error.to_string())))
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Encoder {
+    buffer: Vec<u8>,
+}
+
+impl Encoder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    #[inline]
+    pub fn u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    #[inline]
+    pub fn u64(&mut self, value: u64) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    #[inline]
+    pub fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    /// Writes a length-prefixed byte string.
+    #[inline]
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    #[inline]
+    pub fn raw(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Encodes the resource with `codec`, as a length-prefixed byte string.
+    pub fn resource<R>(&mut self, codec: &dyn Codec<R>, resource: &R) {
+        let mut scratch = Vec::new();
+        codec.encode(resource, &mut scratch);
+        self.bytes(&scratch);
+    }
+
+    /// Encodes the number of pairs, then each field and value.
+    pub fn facets(&mut self, facets: &Facets) {
+        self.usize(facets.len());
+        for (field, value) in facets.iter() {
+            self.bytes(field.as_bytes());
+            self.bytes(value.as_bytes());
+        }
+    }
+
+    /// Word count of each named field.
+    pub fn fields(&mut self, fields: &[(String, usize)]) {
+        self.usize(fields.len());
+        for (field, count) in fields {
+            self.bytes(field.as_bytes());
+            self.usize(*count);
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.offset == self.bytes.len()
+    }
+
+    pub fn raw(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or(SegmentError::UnexpectedEof)?;
+
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    #[inline]
+    pub fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.raw(1)?[0])
+    }
+
+    #[inline]
+    pub fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.raw(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    #[inline]
+    pub fn u64(&mut self) -> Result<u64, Error> {
+        let bytes = self.raw(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    #[inline]
+    pub fn usize(&mut self) -> Result<usize, Error> {
+        usize::try_from(self.u64()?)
+            .map_err(|_| Error::from(SegmentError::Corrupted("integer overflow".into())))
+    }
+
+    /// Reads a length-prefixed byte string.
+    #[inline]
+    pub fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.u32()? as usize;
+        self.raw(len)
+    }
+
+    pub fn string(&mut self) -> Result<String, Error> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| Error::from(SegmentError::Corrupted("invalid utf-8 term".into())))
+    }
+
+    pub fn facets(&mut self) -> Result<Facets, Error> {
+        let len = self.usize()?;
+        let mut facets = Facets::new();
+
+        for _ in 0..len {
+            facets.insert(self.string()?, self.string()?);
+        }
+
+        Ok(facets)
+    }
+
+    pub fn fields(&mut self) -> Result<Vec<(String, usize)>, Error> {
+        let len = self.usize()?;
+        (0..len)
+            .map(|_| Ok((self.string()?, self.usize()?)))
+            .collect()
+    }
+
+    pub fn resource<R>(&mut self, codec: &dyn Codec<R>) -> Result<R, Error> {
+        let bytes = self.bytes()?;
+        codec.decode(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        codec::{Codec, Decoder, Encoder, StringCodec},
+        error::{Error, SegmentError},
+    };
+
+    #[test]
+    fn test_codec_roundtrip() {
+        let codec = StringCodec::new();
+
+        let mut encoder = Encoder::new();
+        encoder.u32(7);
+        encoder.usize(42);
+        encoder.bytes(b"term");
+        encoder.resource(&codec, &String::from("tests/data/sample.txt"));
+
+        let buffer = encoder.into_inner();
+        let mut decoder = Decoder::new(&buffer);
+
+        assert_eq!(decoder.u32().unwrap(), 7);
+        assert_eq!(decoder.usize().unwrap(), 42);
+        assert_eq!(decoder.string().unwrap(), "term");
+        assert_eq!(decoder.resource(&codec).unwrap(), "tests/data/sample.txt");
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_codec_truncated() {
+        let mut encoder = Encoder::new();
+        encoder.bytes(b"truncated");
+
+        let buffer = encoder.into_inner();
+        let mut decoder = Decoder::new(&buffer[..6]);
+
+        assert!(matches!(
+            decoder.bytes(),
+            Err(Error::Segment(SegmentError::UnexpectedEof))
+        ));
+    }
+
+    #[test]
+    fn test_codec_invalid_utf8() {
+        let codec = StringCodec::new();
+        assert!(codec.decode(&[0xff, 0xfe]).is_err());
+    }
+}
@@ -1,13 +1,16 @@
 extern crate hashbrown;
 
-use std::{cell::RefCell, fmt::Debug, hash::Hash, num::NonZeroUsize};
+use std::{collections::BTreeMap, fmt::Debug, hash::Hash, num::NonZeroUsize};
 
-use hashbrown::{
-    hash_map::HashMap,
-    hash_set::{HashSet, Iter},
-};
+use hashbrown::{hash_map::HashMap, hash_set::HashSet};
 
-use crate::util::Counter;
+use crate::{
+    dictionary::Dictionary,
+    facet::Facets,
+    layer::{Layer, Layers},
+    posting::{BlockMax, PostingCursor, PostingList},
+    util::Counter,
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct TfIdf {
@@ -35,52 +38,274 @@ impl TfIdf {
     }
 }
 
-// TODO: Ensure that same files are not added more than once, maybe use another data structure.
-#[derive(Debug)]
-pub struct Store<R: Clone + Debug> {
-    inner: Vec<Resource<R>>,
+/// Indexed resources.
+///
+/// A resource is its own external key (e.g. a path or URL),
+/// each key maps to at most one live resource.
+///
+/// Resources, keys and tombstones are split into [`Layers`], see [`Store::freeze`].
+#[derive(Clone, Debug)]
+pub struct Store<R: Clone + Debug + Hash + Eq> {
+    inner: Layers<Vec<Resource<R>>>,
+
+    /// Index of the latest resource for each key.
+    ///
+    /// Keys of deleted resources are only removed from the buffer,
+    /// lookups skip them.
+    keys: Layers<HashMap<R, usize>>,
+
+    /// Indices of deleted resources.
+    ///
+    /// Deleted resources keep their slot, so indices stay stable.
+    tombstones: Layers<HashSet<usize>>,
+
+    /// Number of resources, including deleted ones.
+    len: usize,
+
+    /// Number of deleted resources.
+    deleted: usize,
+
+    /// Word count across all live resources, used for average document length.
+    words: usize,
+
+    /// Word count of each named field across all live resources.
+    field_words: BTreeMap<String, usize>,
+
+    /// Shortest non-empty word count of each field, the default field is
+    /// the empty name.
+    ///
+    /// Deletions are not accounted, so it is a lower bound of the lengths
+    /// of the live resources.
+    min_field_words: BTreeMap<String, usize>,
 }
 
-impl<R: Clone + Debug> Store<R> {
+impl<R: Clone + Debug + Hash + Eq> Store<R> {
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            inner: Vec::with_capacity(capacity),
+            inner: Layers::with_buffer(Vec::with_capacity(capacity)),
+            keys: Layers::with_buffer(HashMap::with_capacity(capacity)),
+            tombstones: Layers::new(),
+            len: 0,
+            deleted: 0,
+            words: 0,
+            field_words: BTreeMap::new(),
+            min_field_words: BTreeMap::new(),
         }
     }
 
+    /// Number of resources, including deleted ones.
     #[inline]
     pub fn len(&self) -> usize {
-        self.inner.len()
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of resources, excluding deleted ones.
+    #[inline]
+    pub fn live(&self) -> usize {
+        self.len - self.deleted
+    }
+
+    /// Moves the resources, keys and tombstones added since the last
+    /// freeze into frozen layers, shared by the clones of the store.
+    pub fn freeze(&mut self) {
+        self.inner.freeze();
+        self.keys.freeze();
+        self.tombstones.freeze();
     }
 
+    /// Appends the resource, returning its index.
+    ///
+    /// The key is re-assigned to the new resource, callers are
+    /// responsible for deleting a previous resource with the same key.
     #[inline]
     pub fn insert(&mut self, value: Resource<R>) -> usize {
-        self.inner.push(value);
-        self.len() - 1
+        let index = self.len();
+        self.words += value.count();
+        for (field, count) in value.fields() {
+            *self.field_words.entry(field.clone()).or_default() += count;
+        }
+
+        let named = value
+            .fields()
+            .iter()
+            .map(|(field, count)| (field.as_str(), *count));
+        for (field, count) in named.chain([("", value.field_count(None))]) {
+            if count == 0 {
+                continue;
+            }
+
+            self.min_field_words
+                .entry(field.to_string())
+                .and_modify(|min| *min = (*min).min(count))
+                .or_insert(count);
+        }
+
+        self.keys.buffer_mut().insert(value.resource(), index);
+        self.inner.buffer_mut().push(value);
+        self.len += 1;
+        index
+    }
+
+    /// Appends an already deleted resource, leaving the keys untouched.
+    #[inline]
+    pub fn insert_deleted(&mut self, value: Resource<R>) -> usize {
+        let index = self.len();
+        self.inner.buffer_mut().push(value);
+        self.tombstones.buffer_mut().insert(index);
+        self.len += 1;
+        self.deleted += 1;
+        index
+    }
+
+    /// Index of the live resource with the given key.
+    ///
+    /// The previous resources of a key are deleted before it is
+    /// re-assigned, so only its latest resource may be live.
+    #[inline]
+    pub fn lookup(&self, key: &R) -> Option<usize> {
+        let index = self.keys.iter().rev().find_map(|keys| keys.get(key))?;
+        (!self.is_deleted(*index)).then_some(*index)
+    }
+
+    /// Marks the resource as deleted.
+    ///
+    /// Returns `false` if the resource does not exist or is already deleted.
+    pub fn delete(&mut self, index: usize) -> bool {
+        if index >= self.len || self.is_deleted(index) {
+            return false;
+        }
+
+        self.tombstones.buffer_mut().insert(index);
+        self.deleted += 1;
+
+        let resource = Self::locate(&self.inner, index).unwrap();
+        let keys = self.keys.buffer_mut();
+        if keys.get(resource.resource_ref()) == Some(&index) {
+            keys.remove(resource.resource_ref());
+        }
+
+        self.words -= resource.count();
+        for (field, count) in resource.fields() {
+            if let Some(words) = self.field_words.get_mut(field) {
+                *words -= count;
+            }
+        }
+        true
+    }
+
+    #[inline]
+    pub fn is_deleted(&self, index: usize) -> bool {
+        self.deleted > 0 && self.tombstones.iter().any(|layer| layer.contains(&index))
+    }
+
+    /// Whether any resource is deleted.
+    #[inline]
+    pub fn has_tombstones(&self) -> bool {
+        self.deleted > 0
+    }
+
+    /// Total word count across all live resources.
+    #[inline]
+    pub fn total_count(&self) -> usize {
+        self.words
+    }
+
+    /// Average word count per live resource, zero if there are none.
+    #[inline]
+    pub fn average_count(&self) -> f32 {
+        if self.live() == 0 {
+            return 0.0;
+        }
+
+        self.words as f32 / self.live() as f32
+    }
+
+    /// Names of the fields of the indexed resources, in order.
+    ///
+    /// Fields of deleted resources are included.
+    #[inline]
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.field_words.keys().map(String::as_str)
+    }
+
+    /// Average word count of the field per live resource, zero if there are none.
+    ///
+    /// `None` is the default field, the words outside of named fields.
+    pub fn average_field_count(&self, field: Option<&str>) -> f32 {
+        if self.live() == 0 {
+            return 0.0;
+        }
+
+        let words = match field {
+            Some(field) => self.field_words.get(field).copied().unwrap_or_default(),
+            None => self.words - self.field_words.values().sum::<usize>(),
+        };
+
+        words as f32 / self.live() as f32
+    }
+
+    /// Lower bound of the word count of the field in the live resources
+    /// containing it, at least one.
+    ///
+    /// `None` is the default field, the words outside of named fields.
+    pub fn min_field_count(&self, field: Option<&str>) -> usize {
+        self.min_field_words
+            .get(field.unwrap_or_default())
+            .copied()
+            .unwrap_or(1)
     }
 
     #[inline]
     pub fn get(&self, index: usize) -> Option<&Resource<R>> {
-        self.inner.get(index)
+        Self::locate(&self.inner, index)
+    }
+
+    /// Resource at `index`, found in the layer holding its range of indices.
+    fn locate(inner: &Layers<Vec<Resource<R>>>, index: usize) -> Option<&Resource<R>> {
+        let mut start = 0;
+
+        for layer in inner.iter() {
+            if index < start + layer.len() {
+                return layer.get(index - start);
+            }
+            start += layer.len();
+        }
+
+        None
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &Resource<R>> {
+        self.inner.iter().flatten()
     }
 
     // temporary
     #[inline]
     pub fn get_path(&self, index: usize) -> Option<R> {
-        self.inner.get(index).map(|entry| entry.resource())
+        self.get(index).map(|entry| entry.resource())
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct Resource<R: Clone + Debug> {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource<R: Clone + Debug + Hash + Eq> {
     inner: R,
 
     // Word count
     count: NonZeroUsize,
+
+    facets: Facets,
+
+    /// Word count of each named field, included in `count`.
+    fields: Vec<(String, usize)>,
 }
 
-impl<R: Clone + Debug> Resource<R> {
+impl<R: Clone + Debug + Hash + Eq> Resource<R> {
     pub fn new(resource: R, word_count: usize) -> Self {
         Self {
             inner: resource,
@@ -88,22 +313,71 @@ impl<R: Clone + Debug> Resource<R> {
             // SAFETY:
             // - The value must not be zero, so empty documents are not indexed.
             count: unsafe { NonZeroUsize::new_unchecked(word_count) },
+            facets: Facets::new(),
+            fields: Vec::new(),
         }
     }
 
+    /// Sets the word count of each named field.
+    pub fn with_fields(mut self, fields: Vec<(String, usize)>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn with_facets(mut self, facets: Facets) -> Self {
+        self.facets = facets;
+        self
+    }
+
     pub fn resource(&self) -> R {
         self.inner.clone()
     }
 
+    pub fn resource_ref(&self) -> &R {
+        &self.inner
+    }
+
     pub fn count(&self) -> usize {
         self.count.get()
     }
+
+    pub fn facets(&self) -> &Facets {
+        &self.facets
+    }
+
+    /// Word count of each named field.
+    pub fn fields(&self) -> &[(String, usize)] {
+        &self.fields
+    }
+
+    /// Word count of the field, `None` is the default field.
+    pub fn field_count(&self, field: Option<&str>) -> usize {
+        match field {
+            Some(field) => self
+                .fields
+                .iter()
+                .find(|(name, _)| name == field)
+                .map_or(0, |(_, count)| *count),
+            None => self.count() - self.fields.iter().map(|(_, count)| count).sum::<usize>(),
+        }
+    }
 }
 
 // TODO: Handle threshold.
-#[derive(Debug)]
+/// Postings of every term, split into [`Layers`], see [`InvertedIndex::freeze`].
+///
+/// The postings of a resource are all added while it is the latest
+/// resource, so the layers hold increasing ranges of resource indices
+/// and the lists of a term in each layer follow each other.
+#[derive(Clone, Debug)]
 pub struct InvertedIndex {
-    inner: HashMap<String, IdfEntry>,
+    inner: Layers<PostingLayer>,
+
+    /// The same terms, sorted.
+    terms: Dictionary,
+
+    /// Whether the postings record term positions.
+    positions: bool,
 }
 
 impl InvertedIndex {
@@ -115,31 +389,149 @@ impl InvertedIndex {
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            inner: HashMap::with_capacity(capacity),
+            inner: Layers::with_buffer(PostingLayer {
+                lists: HashMap::with_capacity(capacity),
+            }),
+            terms: Dictionary::new(),
+            positions: false,
+        }
+    }
+
+    /// Records the positions of the terms added from now on.
+    #[inline]
+    pub fn enable_positions(&mut self) {
+        self.positions = true;
+    }
+
+    #[inline]
+    pub fn has_positions(&self) -> bool {
+        self.positions
+    }
+
+    /// Moves the postings and terms added since the last freeze into
+    /// frozen layers, shared by the clones of the index.
+    pub fn freeze(&mut self) {
+        self.inner.freeze();
+        self.terms.freeze();
+    }
+
+    /// Positions of the term in the resource, in increasing order.
+    ///
+    /// Returns `None` unless positions are recorded for the posting,
+    /// postings merged from an index without positions have none.
+    pub fn get_positions(&self, term: &str, index: usize) -> Option<Vec<u32>> {
+        let entry = self.get_term_entries(term)?;
+        let mut cursor = entry.iter();
+        cursor
+            .advance_to(index)
+            .filter(|entry| entry.get_index() == index)?;
+        Some(cursor.positions()).filter(|positions| !positions.is_empty())
+    }
+
+    /// Number of distinct terms.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Iterates over every term and its `IdfEntry`, in order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, IdfEntry<'_>)> {
+        self.terms
+            .iter()
+            .filter_map(|term| Some((term, self.get_term_entries(term)?)))
+    }
+
+    /// Removes the postings matching `f`, and the terms left without postings.
+    ///
+    /// Returns the number of removed postings.
+    pub fn remove_entries(&mut self, f: impl Fn(&RefEntry) -> bool) -> usize {
+        let mut removed = 0;
+
+        for layer in self.inner.iter_mut() {
+            layer.lists.retain(|_, list| {
+                let count = list.len();
+                list.retain(|entry| !f(entry));
+                removed += count - list.len();
+                !list.is_empty()
+            });
+        }
+
+        let terms = self
+            .terms
+            .iter()
+            .filter(|term| self.get_term_entries(term).is_none())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        for term in terms {
+            self.terms.remove(&term);
         }
+
+        removed
     }
 
     /// Adds a term to the inverted index with its associated `RefEntry`.    
     #[inline]
     pub fn add_term(&mut self, term: String, tf_entry: TfEntry) {
-        // TODO: Track default capacity and threshold.
-        self.inner
-            .entry_ref(&term)
-            .and_modify(|entry| entry.add_entry(RefEntry::new(tf_entry)))
-            .or_insert_with(|| {
-                let mut set = HashSet::new();
-                set.insert(RefEntry::new(tf_entry));
-                IdfEntry { entries: set }
-            });
+        self.entry(term).insert(tf_entry);
     }
 
-    /// Returns an immutable reference to the `IdfEntry` for a given term.
+    /// Adds an occurrence of the term at `position` in the resource,
+    /// the position is ignored unless positions are recorded.
     #[inline]
-    pub fn get_term_entries(&self, term: &str) -> Option<&IdfEntry> {
-        match self.inner.get(term) {
-            Some(entry) => Some(entry),
-            None => None,
+    pub fn add_term_at(&mut self, term: String, index: usize, position: u32) {
+        let entries = self.entry(term);
+        match entries.has_positions() {
+            true => entries.insert_at(index, position),
+            false => entries.insert(TfEntry::new(index, 1)),
+        }
+    }
+
+    /// Adds a whole posting of the term with its positions, e.g. when
+    /// merging or loading an index.
+    #[inline]
+    pub fn push_term(&mut self, term: String, tf_entry: TfEntry, positions: &[u32]) {
+        self.entry(term).push(tf_entry, positions);
+    }
+
+    /// Postings of the term in the buffer, created if missing.
+    ///
+    /// Postings added once positions are enabled carry them.
+    fn entry(&mut self, term: String) -> &mut PostingList {
+        // TODO: Track default capacity and threshold.
+        let lists = &mut self.inner.buffer_mut().lists;
+        if !lists.contains_key(term.as_str()) {
+            self.terms.insert(term.clone());
+        }
+
+        let entry = lists.entry(term).or_default();
+        if self.positions {
+            entry.enable_positions();
         }
+        entry
+    }
+
+    /// Sorted terms, see [`Dictionary`].
+    #[inline]
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.terms
+    }
+
+    /// Returns the `IdfEntry` for a given term.
+    #[inline]
+    pub fn get_term_entries(&self, term: &str) -> Option<IdfEntry<'_>> {
+        let lists = self
+            .inner
+            .iter()
+            .filter_map(|layer| layer.lists.get(term))
+            .collect::<Vec<_>>();
+
+        (!lists.is_empty()).then_some(IdfEntry { lists })
     }
 
     /// Retrieves the `IdfEntry` associated with the specified `term` and applies
@@ -147,68 +539,72 @@ impl InvertedIndex {
     /// If the term is not found, it returns `None`.
     #[inline]
     pub fn get_entry_with<O>(&self, term: &str, f: impl FnOnce(&IdfEntry) -> O) -> Option<O> {
-        self.get_term_entries(term).map(f)
+        self.get_term_entries(term).map(|entry| f(&entry))
     }
 }
 
-pub struct EntriesIter<'a> {
-    inner: Iter<'a, RefEntry>,
+/// Posting lists of the terms in a layer of the [`InvertedIndex`].
+#[derive(Clone, Debug, Default)]
+pub struct PostingLayer {
+    lists: HashMap<String, PostingList>,
 }
 
-impl<'a> Iterator for EntriesIter<'a> {
-    type Item = &'a RefEntry;
+/// Postings of the newer layer are appended to the postings of the same terms.
+impl Layer for PostingLayer {
+    #[inline]
+    fn len(&self) -> usize {
+        self.lists.len()
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+    fn merge(&mut self, newer: Self) {
+        for (term, list) in newer.lists {
+            match self.lists.get_mut(term.as_str()) {
+                Some(older) => older.append(&list),
+                None => _ = self.lists.insert(term, list),
+            }
+        }
     }
 }
 
 // SCANNS
 // IDFIndexEntry keeps track of the token associated with the entry,
 // the number of files that contain the term,
-#[derive(Debug, Default)]
-pub struct IdfEntry {
+/// Postings of a term, one list per layer holding the term.
+#[derive(Clone, Debug)]
+pub struct IdfEntry<'a> {
     // TODO:
-    // - Maybe use better data structure for this use-case.
     // - Track `threshold` for `IdfEntry` somewhere else.
-    entries: HashSet<RefEntry>,
+    lists: Vec<&'a PostingList>,
 }
 
-impl IdfEntry {
-    #[inline]
-    pub fn with_capacity(capacity: usize, threshold: usize) -> Self {
-        Self {
-            entries: HashSet::with_capacity(capacity),
-        }
-    }
-
+impl<'a> IdfEntry<'a> {
     /// Number of documents containing the term.
     #[inline]
     pub fn count(&self) -> usize {
-        self.entries.len()
+        self.lists.iter().map(|list| list.len()).sum()
     }
 
-    /// Adds a `RefEntry` to this `IdfEntry`.
-    pub fn add_entry(&mut self, entry: RefEntry) {
-        if let Some(entry) = self.entries.get(&entry) {
-            entry.increment_frequency();
-            return;
-        }
-
-        self.entries.insert(entry);
+    /// Iterates over the entries, ordered by resource index.
+    #[inline]
+    pub fn iter(&self) -> PostingCursor<'a> {
+        PostingCursor::new(self.lists.clone())
     }
 
-    /// Retrieves all the entries associated with this `IdfEntry`.
+    /// Highest frequency of the postings.
     #[inline]
-    pub fn get_entries(&self) -> &HashSet<RefEntry> {
-        &self.entries
+    pub fn max_frequency(&self) -> usize {
+        self.lists
+            .iter()
+            .map(|list| list.max_frequency())
+            .max()
+            .unwrap_or_default()
     }
 
+    /// Bounds of the block holding the first posting with an index greater
+    /// than or equal to `target`, see [`PostingList::block_max`].
     #[inline]
-    pub fn iter(&self) -> EntriesIter {
-        EntriesIter {
-            inner: self.entries.iter(),
-        }
+    pub fn block_max(&self, target: usize) -> Option<BlockMax> {
+        self.lists.iter().find_map(|list| list.block_max(target))
     }
 
     // #[inline]
@@ -221,18 +617,21 @@ impl IdfEntry {
     //     (self.entries.len() * 100) / self.entries.capacity()
 }
 
-#[derive(Clone, Debug, Eq)]
-pub struct RefEntry(RefCell<TfEntry>);
+/// Posting of a term, hashed and compared by resource index.
+///
+/// Entries are plain values, decoded from the [`PostingList`] of the term.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct RefEntry(TfEntry);
 
 impl RefEntry {
     #[inline]
     pub fn new(entry: TfEntry) -> Self {
-        Self(RefCell::new(entry))
+        Self(entry)
     }
 
     #[inline]
     pub fn tf_entry(&self) -> TfEntry {
-        *self.0.borrow()
+        self.0
     }
 
     #[inline]
@@ -246,20 +645,8 @@ impl RefEntry {
     }
 
     #[inline]
-    pub fn increment_frequency(&self) {
-        self.0.borrow_mut().increment_frequency()
-    }
-}
-
-impl Hash for RefEntry {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.0.borrow().hash(state);
-    }
-}
-
-impl PartialEq for RefEntry {
-    fn eq(&self, other: &Self) -> bool {
-        self.0.borrow().index == other.0.borrow().index
+    pub fn increment_frequency(&mut self) {
+        self.0.increment_frequency()
     }
 }
 
@@ -277,12 +664,12 @@ pub struct TfEntry {
 
 impl TfEntry {
     #[inline]
-    pub fn get_frequency(&mut self) -> Counter<usize> {
+    pub fn get_frequency(&self) -> Counter<usize> {
         self.frequency
     }
 
     #[inline]
-    pub fn get_index(&mut self) -> usize {
+    pub fn get_index(&self) -> usize {
         self.index
     }
 
@@ -290,6 +677,11 @@ impl TfEntry {
     pub fn increment_frequency(&mut self) {
         self.frequency.increment();
     }
+
+    #[inline]
+    pub fn add_frequency(&mut self, frequency: usize) {
+        self.frequency += Counter::new(frequency);
+    }
 }
 
 impl TfEntry {
@@ -313,7 +705,7 @@ impl PartialEq for TfEntry {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 // pub struct TermCounter<K>
 // where
 // K: Debug + Eq + Hash + ToString,
@@ -354,8 +746,9 @@ impl TermCounter {
         self.inner.get(key)
     }
 
-    /// SAFETY: The caller ensures that atleast one term is present in the counter.
-    //
+    /// # Safety
+    /// The caller ensures that atleast one term is present in the counter.
+    ///
     /// # Panics
     /// If the no term exists.
     #[inline]
@@ -0,0 +1,450 @@
+//! Sorted term dictionary.
+//!
+//! The inverted index maps terms through a hash map, which is fast for exact
+//! lookups but cannot enumerate similar terms without scanning every term.
+//! The dictionary keeps the same terms sorted, so the terms sharing a prefix
+//! form a contiguous range that can be skipped or visited as a whole.
+//!
+//! The terms are split into [`Layers`], a term belongs to the layer it was
+//! first inserted in, and the layers are merged when visiting a range.
+
+use std::{collections::BTreeSet, ops::Bound};
+
+use crate::{
+    distance::LevenshteinAutomaton,
+    layer::{Layer, Layers},
+};
+
+/// Terms of an inverted index, in lexicographic order.
+#[derive(Clone, Debug, Default)]
+pub struct Dictionary {
+    terms: Layers<BTreeSet<String>>,
+}
+
+impl Dictionary {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn insert(&mut self, term: String) -> bool {
+        if self.contains(&term) {
+            return false;
+        }
+
+        self.terms.buffer_mut().insert(term)
+    }
+
+    /// Removes the term, copying the frozen layer holding it if it is shared.
+    pub fn remove(&mut self, term: &str) -> bool {
+        if !self.contains(term) {
+            return false;
+        }
+
+        self.terms.iter_mut().any(|terms| terms.remove(term))
+    }
+
+    #[inline]
+    pub fn contains(&self, term: &str) -> bool {
+        self.terms.iter().any(|terms| terms.contains(term))
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.terms.iter().map(Layer::len).sum()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.range("")
+    }
+
+    /// Moves the inserted terms into a frozen layer, see [`Layers::freeze`].
+    #[inline]
+    pub fn freeze(&mut self) {
+        self.terms.freeze()
+    }
+
+    /// Terms starting with `prefix`, in order.
+    pub fn prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.range(prefix)
+            .take_while(move |term| term.starts_with(prefix))
+    }
+
+    /// Terms from `start` included, in order, merged from every layer.
+    fn range<'a>(&'a self, start: &str) -> impl Iterator<Item = &'a str> + 'a {
+        let mut layers = self
+            .terms
+            .iter()
+            .map(|terms| {
+                terms
+                    .range::<str, _>((Bound::Included(start), Bound::Unbounded))
+                    .map(String::as_str)
+                    .peekable()
+            })
+            .collect::<Vec<_>>();
+
+        // Layers are disjoint, the smallest next term is the next one.
+        std::iter::from_fn(move || {
+            let (layer, _) = layers
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(layer, terms)| Some((layer, *terms.peek()?)))
+                .min_by_key(|&(_, term)| term)?;
+            layers[layer].next()
+        })
+    }
+
+    /// Terms matching the glob `pattern`, in order, see [`matches_glob`].
+    ///
+    /// Only the terms starting with the literal prefix of the pattern are visited.
+    pub fn wildcard<'a>(&'a self, pattern: &str) -> impl Iterator<Item = &'a str> + 'a {
+        let literal = pattern.find(['*', '?']).unwrap_or(pattern.len());
+        let prefix = pattern[..literal].to_string();
+        let chars = pattern.chars().collect::<Vec<_>>();
+
+        self.range(&prefix)
+            .take_while(move |term| term.starts_with(prefix.as_str()))
+            .filter(move |term| matches_glob(&chars, term))
+    }
+
+    /// Terms accepted by the automaton, with their edit distance.
+    ///
+    /// Walks the terms in order, sharing the automaton states of common
+    /// prefixes, and skips every term starting with a prefix the automaton
+    /// can no longer accept.
+    pub fn fuzzy(&self, automaton: &LevenshteinAutomaton) -> Vec<(&str, usize)> {
+        self.fuzzy_prefixed("", automaton)
+    }
+
+    /// Terms starting with `prefix` whose remainder is accepted by the
+    /// automaton, with its edit distance, see [`Dictionary::fuzzy`].
+    pub fn fuzzy_prefixed<'a>(
+        &'a self,
+        prefix: &str,
+        automaton: &LevenshteinAutomaton,
+    ) -> Vec<(&'a str, usize)> {
+        self.fuzzy_walk(prefix, automaton, usize::MAX, |_| true)
+    }
+
+    /// The `limit` closest terms of [`Dictionary::fuzzy_prefixed`] kept by
+    /// `keep`, closest first, then in order.
+    ///
+    /// Once `limit` terms are found within a distance, the following terms
+    /// can only replace them if closer, so the walk only accepts closer
+    /// terms from then on, and stops at `limit` exact matches.
+    pub fn fuzzy_closest<'a>(
+        &'a self,
+        prefix: &str,
+        automaton: &LevenshteinAutomaton,
+        limit: usize,
+        keep: impl Fn(&str) -> bool,
+    ) -> Vec<(&'a str, usize)> {
+        if limit == 0 {
+            return Vec::new();
+        }
+
+        let mut matches = self.fuzzy_walk(prefix, automaton, limit, keep);
+        matches.sort_by_key(|&(_, distance)| distance);
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Accepted terms kept by `keep`, in order, lowering the maximum
+    /// distance once `limit` terms are found within it.
+    fn fuzzy_walk<'a>(
+        &'a self,
+        prefix: &str,
+        automaton: &LevenshteinAutomaton,
+        limit: usize,
+        keep: impl Fn(&str) -> bool,
+    ) -> Vec<(&'a str, usize)> {
+        let mut automaton = automaton.clone();
+        let mut matches = Vec::new();
+
+        // Number of matches at each distance.
+        let mut counts = vec![0; automaton.max() + 1];
+
+        // `states[i]` is the state after the first `i` characters of `previous`.
+        let mut states = vec![automaton.start()];
+        let mut previous = Vec::new();
+        let mut terms = self.range(prefix);
+
+        while let Some(term) = terms.next() {
+            let Some(rest) = term.strip_prefix(prefix) else {
+                break;
+            };
+
+            let chars = rest.chars().collect::<Vec<_>>();
+            let common = previous
+                .iter()
+                .zip(&chars)
+                .take_while(|(previous, char)| previous == char)
+                .count();
+            states.truncate(common + 1);
+
+            let rejected = chars[common..].iter().position(|&char| {
+                let state = automaton.step(states.last().unwrap(), char);
+                let rejected = !automaton.can_match(&state);
+                states.push(state);
+                rejected
+            });
+
+            match rejected {
+                Some(offset) => {
+                    let rejected = &chars[..common + offset + 1];
+                    let Some(successor) = successor(rejected) else {
+                        break;
+                    };
+
+                    states.pop();
+                    previous = rejected[..rejected.len() - 1].to_vec();
+                    let successor = format!("{prefix}{successor}");
+                    terms = self.range(&successor);
+                }
+                None => {
+                    previous = chars;
+
+                    let Some(distance) = automaton.distance(states.last().unwrap()) else {
+                        continue;
+                    };
+                    if !keep(term) {
+                        continue;
+                    }
+
+                    matches.push((term, distance));
+                    counts[distance] += 1;
+
+                    let mut within = 0;
+                    let bound = (0..=automaton.max()).find(|&distance| {
+                        within += counts[distance];
+                        within >= limit
+                    });
+
+                    match bound {
+                        Some(0) => break,
+                        Some(bound) => automaton = automaton.with_max(bound - 1),
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+/// Whether `term` matches the glob `pattern`, where `*` matches any
+/// sequence of characters, including none, and `?` a single character.
+pub fn matches_glob(pattern: &[char], term: &str) -> bool {
+    let term = term.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+
+    // Position of the last `*`, and of the term when it was reached.
+    let mut star = None;
+
+    while t < term.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&char) if char == '?' || char == term[t] => {
+                p += 1;
+                t += 1;
+            }
+            // Let the last `*` match one more character.
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&char| char == '*')
+}
+
+/// Smallest string greater than every string starting with `prefix`.
+fn successor(prefix: &[char]) -> Option<String> {
+    let mut prefix = prefix.to_vec();
+
+    while let Some(last) = prefix.pop() {
+        let next = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32);
+
+        if let Some(next) = next {
+            prefix.push(next);
+            return Some(prefix.into_iter().collect());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dictionary::{matches_glob, successor, Dictionary},
+        distance::{damerau_bounded, LevenshteinAutomaton},
+    };
+
+    fn dictionary(terms: &[&str]) -> Dictionary {
+        let mut dictionary = Dictionary::new();
+        terms
+            .iter()
+            .for_each(|term| _ = dictionary.insert(term.to_string()));
+        dictionary
+    }
+
+    #[test]
+    fn test_dictionary_fuzzy() {
+        let terms = [
+            "collar", "color", "colors", "colour", "cooler", "dolor", "olor", "xcolor", "zebra",
+        ];
+        let dictionary = dictionary(&terms);
+
+        let automaton = LevenshteinAutomaton::new("color", 1);
+        assert_eq!(
+            dictionary.fuzzy(&automaton),
+            [
+                ("color", 0),
+                ("colors", 1),
+                ("colour", 1),
+                ("dolor", 1),
+                ("olor", 1),
+                ("xcolor", 1)
+            ]
+        );
+
+        // Same terms as a scan of the whole dictionary.
+        for term in ["color", "clor", "zebar", "", "colllar"] {
+            for max in 0..3 {
+                let automaton = LevenshteinAutomaton::new(term, max).with_transpositions();
+                let expected = terms
+                    .iter()
+                    .filter_map(|&other| Some((other, damerau_bounded(term, other, max)?)))
+                    .collect::<Vec<_>>();
+
+                assert_eq!(dictionary.fuzzy(&automaton), expected, "{term}~{max}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_dictionary_fuzzy_closest() {
+        let dictionary = dictionary(&[
+            "clor", "collar", "color", "colors", "colour", "cooler", "dolor", "olor", "x:color",
+        ]);
+        let automaton = LevenshteinAutomaton::new("color", 2).with_transpositions();
+
+        // Same terms as sorting every accepted term by distance.
+        let mut all = dictionary.fuzzy(&automaton);
+        all.sort_by_key(|&(_, distance)| distance);
+
+        for limit in 0..all.len() + 2 {
+            let closest = dictionary.fuzzy_closest("", &automaton, limit, |_| true);
+            assert_eq!(closest, all[..limit.min(all.len())], "limit {limit}");
+        }
+
+        assert_eq!(
+            dictionary.fuzzy_closest("", &automaton, 3, |term| !term.contains(':')),
+            [("color", 0), ("clor", 1), ("colors", 1)]
+        );
+    }
+
+    #[test]
+    fn test_dictionary_fuzzy_prefixed() {
+        let dictionary = dictionary(&[
+            "body:colour",
+            "color",
+            "title:color",
+            "title:colors",
+            "titles",
+        ]);
+        let automaton = LevenshteinAutomaton::new("color", 1);
+
+        assert_eq!(
+            dictionary.fuzzy_prefixed("title:", &automaton),
+            [("title:color", 0), ("title:colors", 1)]
+        );
+        assert_eq!(
+            dictionary.fuzzy_prefixed("body:", &automaton),
+            [("body:colour", 1)]
+        );
+        assert!(dictionary.fuzzy_prefixed("x:", &automaton).is_empty());
+    }
+
+    #[test]
+    fn test_dictionary_layers() {
+        let mut dictionary = dictionary(&["color", "title:color", "zebra"]);
+        dictionary.freeze();
+        assert!(!dictionary.insert("color".into()));
+        assert!(dictionary.insert("colour".into()));
+        assert!(dictionary.insert("apple".into()));
+
+        // Terms of every layer, in order.
+        assert_eq!(dictionary.len(), 5);
+        let terms = dictionary.iter().collect::<Vec<_>>();
+        assert_eq!(terms, ["apple", "color", "colour", "title:color", "zebra"]);
+
+        let automaton = LevenshteinAutomaton::new("color", 1);
+        assert_eq!(dictionary.fuzzy(&automaton), [("color", 0), ("colour", 1)]);
+        assert_eq!(dictionary.prefix("col").count(), 2);
+
+        assert!(dictionary.remove("color"));
+        assert!(!dictionary.contains("color"));
+        assert_eq!(dictionary.wildcard("col*").collect::<Vec<_>>(), ["colour"]);
+    }
+
+    #[test]
+    fn test_dictionary_prefix() {
+        let dictionary = dictionary(&["token", "tokenize", "tokenizer", "tokio", "tom"]);
+
+        let terms = dictionary.prefix("tokeni").collect::<Vec<_>>();
+        assert_eq!(terms, ["tokenize", "tokenizer"]);
+        assert_eq!(dictionary.prefix("").count(), 5);
+        assert_eq!(dictionary.prefix("x").count(), 0);
+    }
+
+    #[test]
+    fn test_dictionary_wildcard() {
+        let dictionary = dictionary(&["hall", "hello", "hell", "help", "hull", "shell", "hallo"]);
+
+        let terms = dictionary.wildcard("h?ll*").collect::<Vec<_>>();
+        assert_eq!(terms, ["hall", "hallo", "hell", "hello", "hull"]);
+
+        let terms = dictionary.wildcard("*ell*").collect::<Vec<_>>();
+        assert_eq!(terms, ["hell", "hello", "shell"]);
+        assert_eq!(dictionary.wildcard("help").collect::<Vec<_>>(), ["help"]);
+    }
+
+    #[test]
+    fn test_matches_glob() {
+        let glob = |pattern: &str, term| matches_glob(&pattern.chars().collect::<Vec<_>>(), term);
+
+        assert!(glob("*", ""));
+        assert!(glob("a*b*c", "aXXbYYc"));
+        assert!(glob("a*b", "abab"));
+        assert!(glob("??", "éa"));
+        assert!(!glob("??", "a"));
+        assert!(!glob("a*b", "abac"));
+        assert!(glob("**a", "ba"));
+    }
+
+    #[test]
+    fn test_dictionary_successor() {
+        assert_eq!(successor(&['a', 'b']), Some("ac".into()));
+        assert_eq!(successor(&['a', char::MAX]), Some("b".into()));
+        assert_eq!(successor(&['\u{d7ff}']), Some("\u{e000}".into()));
+        assert_eq!(successor(&[char::MAX]), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Levenshtein automaton.
+//!
+//! The automaton accepts the strings within a maximum edit distance of a
+//! term. Its state after reading a prefix is the matching row of the
+//! Wagner-Fischer matrix, so it can tell when no continuation of the prefix
+//! is accepted anymore. This allows pruning whole ranges of a sorted
+//! dictionary, see [`Dictionary::fuzzy`](crate::dictionary::Dictionary::fuzzy).
+
+/// Automaton accepting the strings within `max` edits of a term.
+#[derive(Clone, Debug)]
+pub struct LevenshteinAutomaton {
+    term: Vec<char>,
+    max: usize,
+    transpositions: bool,
+}
+
+/// State of a [`LevenshteinAutomaton`] after reading a prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct State {
+    /// Distance from every prefix of the term to the read prefix.
+    row: Vec<usize>,
+
+    /// Row before the last character, for transpositions.
+    previous: Vec<usize>,
+
+    last: Option<char>,
+}
+
+impl LevenshteinAutomaton {
+    pub fn new(term: &str, max: usize) -> Self {
+        Self {
+            term: term.chars().collect(),
+            max,
+            transpositions: false,
+        }
+    }
+
+    /// Counts swapping two adjacent characters as a single edit,
+    /// see [`damerau`](crate::distance::damerau).
+    pub fn with_transpositions(mut self) -> Self {
+        self.transpositions = true;
+        self
+    }
+
+    #[inline]
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Same automaton with another maximum distance, states of either
+    /// automaton can be stepped by the other.
+    pub fn with_max(mut self, max: usize) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// State before reading any character.
+    pub fn start(&self) -> State {
+        State {
+            row: (0..=self.term.len()).collect(),
+            previous: Vec::new(),
+            last: None,
+        }
+    }
+
+    /// State after reading `char` from `state`.
+    pub fn step(&self, state: &State, char: char) -> State {
+        let mut row = Vec::with_capacity(state.row.len());
+        row.push(state.row[0] + 1);
+
+        for (i, &expected) in self.term.iter().enumerate() {
+            let substitution = state.row[i] + (expected != char) as usize;
+            let mut distance = substitution.min(state.row[i + 1] + 1).min(row[i] + 1);
+
+            let transposed = i > 0 && state.last == Some(expected) && self.term[i - 1] == char;
+            if self.transpositions && transposed {
+                distance = distance.min(state.previous[i - 1] + 1);
+            }
+
+            row.push(distance);
+        }
+
+        State {
+            previous: state.row.clone(),
+            row,
+            last: Some(char),
+        }
+    }
+
+    /// Distance of the read string, if accepted.
+    #[inline]
+    pub fn distance(&self, state: &State) -> Option<usize> {
+        let distance = *state.row.last().unwrap();
+        (distance <= self.max).then_some(distance)
+    }
+
+    /// Whether a continuation of the read string can be accepted.
+    #[inline]
+    pub fn can_match(&self, state: &State) -> bool {
+        state.row.iter().any(|&distance| distance <= self.max)
+    }
+
+    /// Distance to `other`, if accepted.
+    pub fn distance_to(&self, other: &str) -> Option<usize> {
+        let mut state = self.start();
+
+        for char in other.chars() {
+            state = self.step(&state, char);
+            if !self.can_match(&state) {
+                return None;
+            }
+        }
+
+        self.distance(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distance::automaton::LevenshteinAutomaton;
+
+    #[test]
+    fn test_automaton_pruning() {
+        let automaton = LevenshteinAutomaton::new("color", 1);
+
+        let state = "col".chars().fold(automaton.start(), |state, char| {
+            automaton.step(&state, char)
+        });
+        assert!(automaton.can_match(&state));
+        assert_eq!(automaton.distance(&state), None);
+
+        // Two substitutions in, no continuation is within one edit.
+        let state = "cxx".chars().fold(automaton.start(), |state, char| {
+            automaton.step(&state, char)
+        });
+        assert!(!automaton.can_match(&state));
+
+        assert_eq!(automaton.distance_to("colour"), Some(1));
+        assert_eq!(automaton.distance_to("colro"), None);
+        assert_eq!(
+            automaton.with_transpositions().distance_to("colro"),
+            Some(1)
+        );
+    }
+}
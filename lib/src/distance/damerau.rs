@@ -0,0 +1,43 @@
+//! Damerau-Levenshtein distance, where swapping two adjacent characters
+//! also counts as a single edit.
+//!
+//! This is the optimal string alignment variant, a substring is never
+//! edited again after a transposition, so `ca` to `abc` is 3, not 2.
+
+use crate::distance::automaton::LevenshteinAutomaton;
+
+/// Damerau-Levenshtein distance between `a` and `b`, in characters.
+pub fn damerau(a: &str, b: &str) -> usize {
+    LevenshteinAutomaton::new(a, usize::MAX)
+        .with_transpositions()
+        .distance_to(b)
+        .unwrap()
+}
+
+/// Damerau-Levenshtein distance between `a` and `b`,
+/// or `None` if greater than `max`.
+pub fn damerau_bounded(a: &str, b: &str, max: usize) -> Option<usize> {
+    LevenshteinAutomaton::new(a, max)
+        .with_transpositions()
+        .distance_to(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distance::damerau::{damerau, damerau_bounded};
+
+    #[test]
+    fn test_damerau() {
+        assert_eq!(damerau("ab", "ba"), 1);
+        assert_eq!(damerau("recieve", "receive"), 1);
+        assert_eq!(damerau("kitten", "sitting"), 3);
+        assert_eq!(damerau("ca", "abc"), 3);
+        assert_eq!(damerau("", "ab"), 2);
+    }
+
+    #[test]
+    fn test_damerau_bounded() {
+        assert_eq!(damerau_bounded("teh", "the", 1), Some(1));
+        assert_eq!(damerau_bounded("teh", "then", 1), None);
+    }
+}
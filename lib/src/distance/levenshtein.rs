@@ -1,2 +1,45 @@
-// Recursive: Vladimir Levenshtein
-// DP: Wagner Fischer
+//! Levenshtein distance, the minimum number of single character insertions,
+//! deletions and substitutions turning one string into another.
+//!
+//! Computed with the Wagner-Fischer dynamic programming algorithm, one row
+//! per character, see [`LevenshteinAutomaton`].
+
+use crate::distance::automaton::LevenshteinAutomaton;
+
+/// Levenshtein distance between `a` and `b`, in characters.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    LevenshteinAutomaton::new(a, usize::MAX)
+        .distance_to(b)
+        .unwrap()
+}
+
+/// Levenshtein distance between `a` and `b`, or `None` if greater than `max`.
+///
+/// Gives up as soon as the distance is known to exceed `max`.
+pub fn levenshtein_bounded(a: &str, b: &str, max: usize) -> Option<usize> {
+    LevenshteinAutomaton::new(a, max).distance_to(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distance::levenshtein::{levenshtein, levenshtein_bounded};
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+        assert_eq!(levenshtein("color", "colour"), 1);
+        assert_eq!(levenshtein("ab", "ba"), 2);
+        assert_eq!(levenshtein("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn test_levenshtein_bounded() {
+        assert_eq!(levenshtein_bounded("kitten", "sitting", 3), Some(3));
+        assert_eq!(levenshtein_bounded("kitten", "sitting", 2), None);
+        assert_eq!(levenshtein_bounded("a", "abcdef", 1), None);
+        assert_eq!(levenshtein_bounded("same", "same", 0), Some(0));
+    }
+}
@@ -1 +1,9 @@
+//! Edit distances between terms.
+
+pub mod automaton;
+pub mod damerau;
 pub mod levenshtein;
+
+pub use automaton::LevenshteinAutomaton;
+pub use damerau::{damerau, damerau_bounded};
+pub use levenshtein::{levenshtein, levenshtein_bounded};
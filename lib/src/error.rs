@@ -12,6 +12,18 @@ pub enum Error {
 
     #[error("{0}")]
     Io(#[from] IoError),
+
+    #[error("{0}")]
+    Segment(#[from] SegmentError),
+
+    #[error("{0}")]
+    Query(#[from] QueryError),
+
+    #[error("Duplicate Resource: already indexed at {0}")]
+    Duplicate(usize),
+
+    #[error("Resource Not Found: no indexed resource at {0}")]
+    NotFound(usize),
 }
 
 /// Configuration error.
@@ -26,6 +38,12 @@ pub enum ConfigError {
     #[error("Tokenizer Error: {0}")]
     Tokenizer(String),
 
+    #[error("Normalizer Error: {0}")]
+    Normalizer(String),
+
+    #[error("Filter Error: {0}")]
+    Filter(String),
+
     #[error("Serialization Error: {0}")]
     Serialization(String),
 
@@ -48,3 +66,56 @@ pub enum IoError {
     #[error("Reader Error: {0}")]
     Reader(io::ErrorKind),
 }
+
+/// Query syntax errors.
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum QueryError {
+    #[error("Empty Query")]
+    Empty,
+
+    #[error("Unexpected Token At {0}: {1}")]
+    UnexpectedToken(usize, String),
+
+    #[error("Unexpected End Of Query")]
+    UnexpectedEnd,
+
+    #[error("Unbalanced Parenthesis At {0}")]
+    UnbalancedParenthesis(usize),
+
+    #[error("Unterminated Phrase At {0}")]
+    UnterminatedPhrase(usize),
+
+    #[error("Fuzzy Distance Too Large At {0}: {1}")]
+    DistanceTooLarge(usize, u32),
+}
+
+/// On-disk segment errors.
+#[derive(Debug, Error, PartialEq)]
+pub enum SegmentError {
+    #[error("Segment I/O Error: {0}")]
+    Io(io::ErrorKind),
+
+    #[error("Invalid Segment Header")]
+    InvalidMagic,
+
+    #[error("Unsupported Segment Version: {0}, the index must be rebuilt")]
+    UnsupportedVersion(u32),
+
+    #[error("Segment Checksum Mismatch")]
+    Checksum,
+
+    #[error("Unexpected End Of Segment")]
+    UnexpectedEof,
+
+    #[error("Corrupted Segment: {0}")]
+    Corrupted(String),
+
+    #[error("Codec Error: {0}")]
+    Codec(String),
+}
+
+impl From<io::Error> for SegmentError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error.kind())
+    }
+}
@@ -0,0 +1,105 @@
+//! Explanations of scores.
+//!
+//! An [`Explanation`] is the tree of contributions to a score: each node
+//! holds a value, what the value is, and the values it is computed from.
+//! With the `serde` feature, explanations serialize, e.g. to JSON.
+
+use std::fmt::{self, Display};
+
+/// Value of a score, or of a contribution to a score, with the
+/// contributions it is computed from.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Explanation {
+    pub value: f32,
+    pub description: String,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub details: Vec<Explanation>,
+}
+
+impl Explanation {
+    #[inline]
+    pub fn new(value: f32, description: impl Into<String>) -> Self {
+        Self {
+            value,
+            description: description.into(),
+            details: Vec::new(),
+        }
+    }
+
+    /// Sets the contributions the value is computed from.
+    #[inline]
+    pub fn with_details(mut self, details: Vec<Explanation>) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Value of a count, e.g. a frequency or a number of resources.
+    #[inline]
+    pub fn count(value: usize, description: impl Into<String>) -> Self {
+        Self::new(value as f32, description)
+    }
+
+    /// Sum of the contributions.
+    pub fn sum(description: impl Into<String>, details: Vec<Explanation>) -> Self {
+        let value = details.iter().map(|detail| detail.value).sum();
+        Self::new(value, description).with_details(details)
+    }
+
+    /// First node with the description, depth first.
+    pub fn find(&self, description: &str) -> Option<&Explanation> {
+        if self.description == description {
+            return Some(self);
+        }
+
+        self.details
+            .iter()
+            .find_map(|detail| detail.find(description))
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{:indent$}{} = {}",
+            "",
+            self.value,
+            self.description,
+            indent = depth * 2
+        )?;
+        self.details
+            .iter()
+            .try_for_each(|detail| detail.fmt_indented(f, depth + 1))
+    }
+}
+
+impl Display for Explanation {
+    /// One line per node, details indented under their node.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::explain::Explanation;
+
+    #[test]
+    fn test_explanation() {
+        let explanation = Explanation::sum(
+            "sum of:",
+            vec![
+                Explanation::new(1.5, "a"),
+                Explanation::new(2.0, "b").with_details(vec![Explanation::count(4, "c")]),
+            ],
+        );
+
+        assert_eq!(explanation.value, 3.5);
+        assert_eq!(explanation.find("c").unwrap().value, 4.0);
+        assert!(explanation.find("d").is_none());
+        assert_eq!(
+            explanation.to_string(),
+            "3.5 = sum of:\n  1.5 = a\n  2 = b\n    4 = c\n"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Facets, values attached to a resource at insert time, such as its file
+//! extension, directory or author.
+//!
+//! Facets do not affect scoring. They narrow the hits of a query with
+//! [`FacetFilter`]s, and are counted over the hits, see [`FacetCounts`].
+
+use std::{fmt::Debug, hash::Hash};
+
+use crate::{aggregate::FacetCounts, query::eval::Matches, reader::ReaderContext};
+
+/// Facet values of a resource, as `(field, value)` pairs.
+///
+/// A field may have several values, pairs are kept sorted and unique.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Facets(Vec<(String, String)>);
+
+impl Facets {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a value to the field, returning `false` if already present.
+    pub fn insert(&mut self, field: impl Into<String>, value: impl Into<String>) -> bool {
+        let pair = (field.into(), value.into());
+
+        match self.0.binary_search(&pair) {
+            Ok(_) => false,
+            Err(position) => {
+                self.0.insert(position, pair);
+                true
+            }
+        }
+    }
+
+    /// Adds a value to the field.
+    #[inline]
+    pub fn with(mut self, field: impl Into<String>, value: impl Into<String>) -> Self {
+        self.insert(field, value);
+        self
+    }
+
+    /// Values of the field, in order.
+    pub fn get<'a>(&'a self, field: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.iter()
+            .filter(move |(name, _)| *name == field)
+            .map(|(_, value)| value)
+    }
+
+    #[inline]
+    pub fn contains(&self, field: &str, value: &str) -> bool {
+        self.iter().any(|pair| pair == (field, value))
+    }
+
+    /// Every `(field, value)` pair, ordered by field then value.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(field, value)| (field.as_str(), value.as_str()))
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<F: Into<String>, V: Into<String>> FromIterator<(F, V)> for Facets {
+    fn from_iter<I: IntoIterator<Item = (F, V)>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |facets, (field, value)| {
+                facets.with(field, value)
+            })
+    }
+}
+
+/// Keeps the resources having any of the values for the field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FacetFilter {
+    field: String,
+    values: Vec<String>,
+}
+
+impl FacetFilter {
+    pub fn new(field: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            values: vec![value.into()],
+        }
+    }
+
+    /// Also accepts `value`.
+    pub fn or(mut self, value: impl Into<String>) -> Self {
+        self.values.push(value.into());
+        self
+    }
+
+    #[inline]
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    pub fn matches(&self, facets: &Facets) -> bool {
+        facets
+            .get(&self.field)
+            .any(|value| self.values.iter().any(|expected| expected == value))
+    }
+}
+
+/// Removes the matches rejected by any filter, and counts the facet values
+/// of the remaining ones.
+///
+/// The values of a field are counted ignoring the filters on that field, so
+/// the counts still show the alternatives to a selected value. A match only
+/// rejected by the filters on `extension` is counted for `extension` only.
+pub fn filter<R>(
+    reader: &ReaderContext<'_, R>,
+    matches: &mut Matches,
+    filters: &[FacetFilter],
+) -> FacetCounts
+where
+    R: Clone + Debug + Hash + Eq,
+{
+    let mut counts = FacetCounts::new();
+
+    matches.retain(|&index, _| {
+        let Some(facets) = reader.facets(index) else {
+            return false;
+        };
+
+        let mut rejected = filters.iter().filter(|filter| !filter.matches(facets));
+        let Some(first) = rejected.next() else {
+            counts.insert(facets.iter());
+            return true;
+        };
+
+        if rejected.all(|filter| filter.field() == first.field()) {
+            counts.insert(facets.iter().filter(|(field, _)| *field == first.field()));
+        }
+
+        false
+    });
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        facet::{filter, FacetFilter, Facets},
+        index::{Index, Indexer},
+        query::Query,
+        score::{Score, Scorer, TfIdfScorer},
+        tokens,
+    };
+
+    fn test_index() -> Index<String> {
+        let mut index = Index::new(10, 8);
+        let documents = [
+            ("a.rs", "rust", "src", tokens!["parser", "error"]),
+            ("b.rs", "rust", "tests", tokens!["parser"]),
+            ("c.md", "markdown", "src", tokens!["parser", "docs"]),
+            ("d.md", "markdown", "docs", tokens!["install"]),
+        ];
+
+        for (resource, language, directory, mut tokens) in documents {
+            let facets = Facets::new()
+                .with("language", language)
+                .with("directory", directory);
+            let word_count = tokens.count();
+            index
+                .insert_with_facets(resource.into(), word_count, &mut tokens, facets)
+                .unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_facets() {
+        let facets = Facets::new()
+            .with("tag", "rust")
+            .with("extension", "md")
+            .with("tag", "async")
+            .with("tag", "rust");
+
+        assert_eq!(facets.len(), 3);
+        assert_eq!(facets.get("tag").collect::<Vec<_>>(), ["async", "rust"]);
+        assert!(facets.contains("extension", "md"));
+        assert!(!facets.contains("extension", "rust"));
+        assert_eq!(
+            facets,
+            [("extension", "md"), ("tag", "async"), ("tag", "rust")]
+                .into_iter()
+                .collect()
+        );
+
+        assert!(FacetFilter::new("tag", "go").or("rust").matches(&facets));
+        assert!(!FacetFilter::new("author", "rust").matches(&facets));
+    }
+
+    #[test]
+    fn test_facet_filter_and_counts() {
+        let mut index = test_index();
+        index.delete(1).unwrap();
+        index
+            .insert_with_facets(
+                "e.rs".into(),
+                1,
+                &mut tokens!["parser"],
+                Facets::new().with("language", "rust"),
+            )
+            .unwrap();
+
+        let reader = index.reader();
+        let scorer = Scorer::new(TfIdfScorer::new(&reader));
+        let expr = Query::new("parser").parse().unwrap();
+
+        // Counted over every match, deleted resources excluded.
+        let mut matches = scorer.evaluate(&expr);
+        let counts = filter(&reader, &mut matches, &[]);
+        assert_eq!(matches.len(), 3);
+        assert_eq!(counts.get("language"), [("rust", 2), ("markdown", 1)]);
+        assert_eq!(counts.get("directory"), [("src", 2)]);
+        assert_eq!(
+            counts.fields().collect::<Vec<_>>(),
+            ["directory", "language"]
+        );
+
+        // Counts of a filtered field ignore its own filter.
+        let mut matches = scorer.evaluate(&expr);
+        let filters = [
+            FacetFilter::new("language", "rust"),
+            FacetFilter::new("directory", "src"),
+        ];
+        let counts = filter(&reader, &mut matches, &filters);
+        assert_eq!(matches.keys().collect::<Vec<_>>(), [&0]);
+        assert_eq!(counts.get("language"), [("markdown", 1), ("rust", 1)]);
+        assert_eq!(counts.get("directory"), [("src", 1)]);
+    }
+}
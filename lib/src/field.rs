@@ -0,0 +1,183 @@
+//! Named fields of a document.
+//!
+//! Terms of a named field are indexed under a key qualified with the field
+//! name, see [`qualify`], so every field has its own postings in the same
+//! inverted index. Terms of the default, unnamed field keep their plain key,
+//! single-field documents are indexed as before.
+
+use crate::{normalizer::NormalizerPipeline, token::Tokens, tokenizer::Tokenizer};
+
+/// Separates the field name from the term in a qualified key.
+pub const SEPARATOR: char = '\u{1f}';
+
+/// Key of the term in the field, the term itself for the default field.
+#[inline]
+pub fn qualify(field: &str, term: &str) -> String {
+    match field.is_empty() {
+        true => term.to_string(),
+        false => format!("{field}{SEPARATOR}{term}"),
+    }
+}
+
+/// Splits a key into its field, `None` for the default field, and its term.
+#[inline]
+pub fn split(key: &str) -> (Option<&str>, &str) {
+    match key.split_once(SEPARATOR) {
+        Some((field, term)) => (Some(field), term),
+        None => (None, key),
+    }
+}
+
+/// Key in the query syntax, `field:term` for a named field.
+pub fn display(key: &str) -> String {
+    match split(key) {
+        (Some(field), term) => format!("{field}:{term}"),
+        (None, term) => term.to_string(),
+    }
+}
+
+/// Whether the key belongs to a named field.
+#[inline]
+pub fn is_qualified(key: &str) -> bool {
+    key.contains(SEPARATOR)
+}
+
+/// Normalized tokens of a field, with the word count of the field before
+/// normalization.
+#[derive(Debug)]
+pub struct FieldTokens {
+    name: String,
+    word_count: usize,
+    tokens: Tokens,
+}
+
+impl FieldTokens {
+    #[inline]
+    pub fn new(name: impl Into<String>, word_count: usize, tokens: Tokens) -> Self {
+        Self {
+            name: name.into(),
+            word_count,
+            tokens,
+        }
+    }
+
+    /// Tokens of the default, unnamed field.
+    #[inline]
+    pub fn unnamed(word_count: usize, tokens: Tokens) -> Self {
+        Self::new(String::new(), word_count, tokens)
+    }
+
+    /// Name of the field, empty for the default field.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn word_count(&self) -> usize {
+        self.word_count
+    }
+
+    #[inline]
+    pub fn tokens(&self) -> &Tokens {
+        &self.tokens
+    }
+
+    #[inline]
+    pub fn tokens_mut(&mut self) -> &mut Tokens {
+        &mut self.tokens
+    }
+
+    #[inline]
+    pub fn into_tokens(self) -> Tokens {
+        self.tokens
+    }
+}
+
+/// Analysis and weight of a named field.
+#[derive(Clone, Debug)]
+pub struct Field {
+    name: String,
+    tokenizer: Tokenizer,
+    pipeline: NormalizerPipeline,
+    boost: f32,
+}
+
+impl Field {
+    pub fn new(
+        name: impl Into<String>,
+        tokenizer: Tokenizer,
+        pipeline: NormalizerPipeline,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            tokenizer,
+            pipeline,
+            boost: 1.0,
+        }
+    }
+
+    /// Weight of the field in scores, applied at query time.
+    pub fn with_boost(mut self, boost: f32) -> Self {
+        self.boost = boost;
+        self
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn boost(&self) -> f32 {
+        self.boost
+    }
+
+    /// Tokenizes and normalizes the text of the field.
+    pub fn analyze(&mut self, text: &str) -> FieldTokens {
+        let mut tokens = self.tokenizer.tokenize(text);
+        let word_count = tokens.count();
+
+        if !self.pipeline.is_empty() {
+            self.pipeline.run(&mut tokens);
+        }
+
+        FieldTokens::new(self.name.clone(), word_count, tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        field::{display, is_qualified, qualify, split, Field},
+        normalizer::{case::Lowercase, NormalizerPipeline},
+        tokenizer::{Tokenizer, Whitespace},
+        tokens,
+    };
+
+    #[test]
+    fn test_field_keys() {
+        let key = qualify("title", "rust");
+
+        assert!(is_qualified(&key));
+        assert_eq!(split(&key), (Some("title"), "rust"));
+        assert_eq!(qualify("", "rust"), "rust");
+        assert_eq!(split("rust"), (None, "rust"));
+        assert_eq!(display(&key), "title:rust");
+    }
+
+    #[test]
+    fn test_field_analyze() {
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+
+        let tokenizer = Tokenizer::Whitespace(Whitespace::new());
+        let mut field = Field::new("title", tokenizer, pipeline).with_boost(2.0);
+        let tokens = field.analyze("Rust Guide");
+
+        assert_eq!(tokens.name(), "title");
+        assert_eq!(tokens.word_count(), 2);
+        assert_eq!(tokens.tokens(), &tokens!["rust", "guide"]);
+        assert_eq!(field.boost(), 2.0);
+    }
+}
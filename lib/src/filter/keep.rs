@@ -0,0 +1,83 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use hashbrown::HashSet;
+
+use crate::{
+    error::{ConfigError, Error},
+    filter::TextFilter,
+    token::Tokens,
+};
+
+/// Keeps only the tokens in the allowlist.
+#[derive(Clone, Debug)]
+pub struct KeepWords(Arc<HashSet<String>>);
+
+impl KeepWords {
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self(Arc::new(words.into_iter().map(Into::into).collect()))
+    }
+
+    /// Loads the allowlist from a file, a word per line.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|error| ConfigError::File(error.kind()))?;
+        let mut words = Vec::new();
+
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|error| ConfigError::Reader(error.kind()))?;
+            let word = line.trim();
+
+            match word.split_whitespace().count() {
+                0 => continue,
+                1 => words.push(word.to_string()),
+                _ => {
+                    return Err(ConfigError::Filter(format!(
+                        "Error in txt file. Invalid word: '{line}'"
+                    ))
+                    .into())
+                }
+            }
+        }
+
+        Ok(Self::new(words))
+    }
+}
+
+impl TextFilter for KeepWords {
+    fn filter(&mut self, tokens: &mut Tokens) {
+        tokens.retain_mut(|token| self.0.contains(token.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter::{KeepWords, TextFilter},
+        tokens,
+    };
+
+    #[test]
+    fn test_filter_keep_words() {
+        let mut tokens = tokens!["rust", "is", "fast", "and", "safe"];
+        KeepWords::new(["rust", "safe", "memory"]).filter(&mut tokens);
+        assert_eq!(tokens, tokens!["rust", "safe"]);
+    }
+
+    #[test]
+    fn test_filter_keep_words_load() {
+        let words = KeepWords::load("../assets/stopwords/en.txt").unwrap();
+        let mut tokens = tokens!["the", "fox", "and", "the", "dog"];
+        words.clone().filter(&mut tokens);
+        assert_eq!(tokens, tokens!["the", "and", "the"]);
+
+        assert!(KeepWords::load("../assets/missing.txt").is_err());
+    }
+}
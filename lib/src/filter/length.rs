@@ -0,0 +1,58 @@
+use crate::{filter::TextFilter, token::Tokens};
+
+/// Keeps the tokens with a length, in characters, within the bounds.
+#[derive(Clone, Debug)]
+pub struct Length {
+    min: usize,
+    max: usize,
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Self::new(0, usize::MAX)
+    }
+}
+
+impl Length {
+    /// Keeps the tokens of `min` to `max` characters, both included.
+    pub fn new(min: usize, max: usize) -> Self {
+        Self { min, max }
+    }
+
+    #[inline]
+    pub fn with_min(mut self, min: usize) -> Self {
+        self.min = min;
+        self
+    }
+
+    #[inline]
+    pub fn with_max(mut self, max: usize) -> Self {
+        self.max = max;
+        self
+    }
+}
+
+impl TextFilter for Length {
+    fn filter(&mut self, tokens: &mut Tokens) {
+        tokens.retain_mut(|token| (self.min..=self.max).contains(&token.chars().count()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter::{Length, TextFilter},
+        tokens,
+    };
+
+    #[test]
+    fn test_filter_length() {
+        let mut tokens = tokens!["a", "an", "été", "quick", "extraordinary"];
+        Length::new(2, 5).filter(&mut tokens);
+        assert_eq!(tokens, tokens!["an", "été", "quick"]);
+
+        let mut tokens = tokens!["a", "an", "extraordinary"];
+        Length::default().with_min(2).filter(&mut tokens);
+        assert_eq!(tokens, tokens!["an", "extraordinary"]);
+    }
+}
@@ -1,14 +1,46 @@
+//! Token filters, dropping tokens from the analyzed tokens.
+//!
+//! Unlike normalizers, filters never rewrite a token, they only keep or
+//! drop it. They run after the normalizers, on the normalized tokens.
+
+pub mod keep;
+pub mod length;
+pub mod numeric;
+pub mod pattern;
+
+pub use {keep::KeepWords, length::Length, numeric::Numeric, pattern::Pattern};
+
 use crate::token::Tokens;
 
-pub trait TextFilter {
+pub trait TextFilterClone {
+    fn clone_box(&self) -> Box<dyn TextFilter>;
+}
+
+impl<T> TextFilterClone for T
+where
+    T: 'static + TextFilter + Clone,
+{
+    fn clone_box(&self) -> Box<dyn TextFilter> {
+        Box::new(self.clone())
+    }
+}
+
+pub trait TextFilter: TextFilterClone + std::fmt::Debug + Send + Sync {
     fn filter(&mut self, tokens: &mut Tokens);
 }
 
-pub struct Filter<T: TextFilter>(T);
+impl Clone for Box<dyn TextFilter> {
+    fn clone(&self) -> Box<dyn TextFilter> {
+        self.clone_box()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Filter(Box<dyn TextFilter>);
 
-impl<T: TextFilter> Filter<T> {
-    pub fn new(kind: T) -> Self {
-        Self(kind)
+impl Filter {
+    pub fn new<T: TextFilter + 'static>(filter: T) -> Self {
+        Self(Box::new(filter))
     }
 
     pub fn filter(&mut self, tokens: &mut Tokens) {
@@ -16,31 +48,79 @@ impl<T: TextFilter> Filter<T> {
     }
 }
 
-pub struct FilterPipeline<T: TextFilter>(Vec<T>);
+#[derive(Clone, Debug)]
+pub struct FilterPipeline(Vec<Box<dyn TextFilter>>);
 
-impl<T: TextFilter> Default for FilterPipeline<T> {
+impl Default for FilterPipeline {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: TextFilter> FilterPipeline<T> {
+impl FilterPipeline {
     pub fn new() -> Self {
         Self(Vec::new())
     }
 
-    pub fn insert(&mut self, filter: T) -> &mut Self {
+    pub fn insert(&mut self, filter: Box<dyn TextFilter>) -> &mut Self {
         self.0.push(filter);
         self
     }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn run(&mut self, tokens: &mut Tokens) {
+        self.0.iter_mut().for_each(|filter| {
+            filter.filter(tokens);
+        })
+    }
 }
 
-// #[cfg(test)]
-// mod tests {
-// use super::{Filter, FilterPipeline};
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter::{FilterPipeline, KeepWords, Length, Numeric, Pattern},
+        tokenizer::{Standard, TextTokenizer},
+        tokens,
+    };
+
+    #[test]
+    fn test_filter_pipeline() {
+        let mut tokens = Standard::new().tokenize("a 2024 release of idx_internal and idx docs");
+
+        let mut pipeline = FilterPipeline::new();
+        pipeline.insert(Box::new(Length::new(2, 10)));
+        pipeline.insert(Box::new(Numeric::new()));
+        pipeline.insert(Box::new(Pattern::new("_internal$").unwrap()));
+        pipeline.run(&mut tokens);
+
+        assert_eq!(pipeline.len(), 3);
+        assert_eq!(tokens, tokens!["release", "of", "and", "idx", "docs"]);
 
-// #[test]
-// fn test_filter_pipeline() {
-// let mut pipeline = FilterPipeline::new();
-// }
-// }
+        // Remaining tokens keep their offsets and positions.
+        let token = tokens.iter().nth(3).unwrap();
+        assert_eq!((token.offsets(), token.position()), (35..38, 6));
+
+        pipeline.insert(Box::new(KeepWords::new(["idx", "docs"])));
+        pipeline.run(&mut tokens);
+        assert_eq!(tokens, tokens!["idx", "docs"]);
+    }
+
+    #[test]
+    fn test_filter_pipeline_empty() {
+        let mut tokens = tokens!["one", "2"];
+        let mut pipeline = FilterPipeline::new();
+
+        assert!(pipeline.is_empty());
+        pipeline.run(&mut tokens);
+        assert_eq!(tokens, tokens!["one", "2"]);
+    }
+}
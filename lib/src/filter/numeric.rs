@@ -0,0 +1,42 @@
+use crate::{filter::TextFilter, token::Tokens};
+
+/// Drops the purely numeric tokens, e.g. "2024", "3.14" or "١٢".
+///
+/// A numeric token has digits and possibly decimal or group separators,
+/// "." and ",", tokens mixing digits and letters, e.g. "h264", are kept.
+#[derive(Clone, Debug, Default)]
+pub struct Numeric;
+
+impl Numeric {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TextFilter for Numeric {
+    fn filter(&mut self, tokens: &mut Tokens) {
+        tokens.retain_mut(|token| !is_numeric(token));
+    }
+}
+
+fn is_numeric(token: &str) -> bool {
+    token.chars().any(char::is_numeric)
+        && token
+            .chars()
+            .all(|char| char.is_numeric() || matches!(char, '.' | ','))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter::{Numeric, TextFilter},
+        tokens,
+    };
+
+    #[test]
+    fn test_filter_numeric() {
+        let mut tokens = tokens!["2024", "3.14", "1,000", "h264", "v2", "fox", "١٢", ".", ""];
+        Numeric::new().filter(&mut tokens);
+        assert_eq!(tokens, tokens!["h264", "v2", "fox", ".", ""]);
+    }
+}
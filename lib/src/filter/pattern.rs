@@ -0,0 +1,45 @@
+use regex::Regex;
+
+use crate::{
+    error::{ConfigError, Error},
+    filter::TextFilter,
+    token::Tokens,
+};
+
+/// Drops the tokens matching the regular expression.
+///
+/// A token matches when the expression matches any part of it, anchor the
+/// expression with `^` and `$` to match whole tokens.
+#[derive(Clone, Debug)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        let regex = Regex::new(pattern)
+            .map_err(|error| ConfigError::Filter(format!("Invalid pattern: {error}")))?;
+        Ok(Self(regex))
+    }
+}
+
+impl TextFilter for Pattern {
+    fn filter(&mut self, tokens: &mut Tokens) {
+        tokens.retain_mut(|token| !self.0.is_match(token));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter::{Pattern, TextFilter},
+        tokens,
+    };
+
+    #[test]
+    fn test_filter_pattern() {
+        let mut tokens = tokens!["http", "https", "www", "example", "com"];
+        Pattern::new("^(https?|www)$").unwrap().filter(&mut tokens);
+        assert_eq!(tokens, tokens!["example", "com"]);
+
+        assert!(Pattern::new("(unclosed").is_err());
+    }
+}
@@ -0,0 +1,269 @@
+//! Snippets of the text of a hit, with the matched terms highlighted.
+//!
+//! The text is tokenized and normalized like the indexed terms. A token
+//! matches when its normalized form is one of the query terms, see
+//! [`Expr::terms`](crate::query::Expr::terms). Markers are inserted at the
+//! byte offsets of the tokens in the original text, so highlights cover the
+//! text as written, before normalization.
+//!
+//! The text is cut into fragments of consecutive tokens. A fragment scores
+//! a point per distinct matched term, and [`REPEAT_WEIGHT`] per repeated
+//! match, the best ones are returned.
+
+use std::ops::Range;
+
+use hashbrown::HashMap;
+
+use crate::{normalizer::NormalizerPipeline, token::Tokens, tokenizer::Tokenizer};
+
+/// Number of tokens per fragment, by default.
+pub const DEFAULT_FRAGMENT_SIZE: usize = 20;
+
+/// Number of fragments returned, by default.
+pub const DEFAULT_FRAGMENTS: usize = 3;
+
+/// Score of a match of a term already matched in the fragment.
+pub const REPEAT_WEIGHT: f32 = 0.1;
+
+/// Part of the text around matched terms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fragment {
+    /// Text of the fragment, matched tokens wrapped in the markers.
+    pub text: String,
+
+    /// Byte range of the fragment in the original text.
+    pub range: Range<usize>,
+
+    pub score: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Highlighter {
+    tokenizer: Tokenizer,
+    pipeline: NormalizerPipeline,
+    pre: String,
+    post: String,
+    fragment_size: usize,
+    fragments: usize,
+}
+
+impl Highlighter {
+    /// Highlighter for text analyzed with the tokenizer and the pipeline,
+    /// wrapping matches in `<em>` and `</em>`.
+    pub fn new(tokenizer: Tokenizer, pipeline: NormalizerPipeline) -> Self {
+        Self {
+            tokenizer,
+            pipeline,
+            pre: "<em>".to_string(),
+            post: "</em>".to_string(),
+            fragment_size: DEFAULT_FRAGMENT_SIZE,
+            fragments: DEFAULT_FRAGMENTS,
+        }
+    }
+
+    /// Wraps matched tokens in `pre` and `post`.
+    pub fn with_markers(mut self, pre: impl Into<String>, post: impl Into<String>) -> Self {
+        self.pre = pre.into();
+        self.post = post.into();
+        self
+    }
+
+    /// Number of tokens per fragment, at least one.
+    pub fn with_fragment_size(mut self, fragment_size: usize) -> Self {
+        self.fragment_size = fragment_size.max(1);
+        self
+    }
+
+    /// Number of fragments to return.
+    pub fn with_fragments(mut self, fragments: usize) -> Self {
+        self.fragments = fragments;
+        self
+    }
+
+    /// Best fragments of the text for the normalized terms, by decreasing
+    /// score, empty if no term matches.
+    ///
+    /// Fragments do not overlap, ties go to the fragment centered on its
+    /// matches, then to the first one.
+    pub fn highlight(&mut self, text: &str, terms: &[impl AsRef<str>]) -> Vec<Fragment> {
+        let mut tokens = self.tokenizer.tokenize(text);
+        let spans = tokens
+            .iter()
+            .map(|token| token.offsets())
+            .collect::<Vec<_>>();
+        let matches = self.matches(&mut tokens, spans.len(), terms);
+
+        let size = self.fragment_size.min(spans.len());
+        let mut candidates = (0..=spans.len() - size)
+            .filter_map(|start| {
+                let window = start..start + size;
+                let score = score(&matches[window.clone()])?;
+
+                // Distance of the matches from the center of the fragment.
+                let first = matches[window.clone()].iter().position(Option::is_some)?;
+                let last = matches[window].iter().rposition(Option::is_some)?;
+                let off_center = (first + last).abs_diff(size - 1);
+
+                Some((start, score, off_center))
+            })
+            .collect::<Vec<_>>();
+
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.2.cmp(&b.2)).then(a.0.cmp(&b.0)));
+
+        let mut selected = Vec::<(usize, f32)>::new();
+        for (start, score, _) in candidates {
+            if selected.len() == self.fragments {
+                break;
+            }
+
+            let overlaps = selected
+                .iter()
+                .any(|&(other, _)| start < other + size && other < start + size);
+            if !overlaps {
+                selected.push((start, score));
+            }
+        }
+
+        selected
+            .into_iter()
+            .map(|(start, score)| {
+                let window = start..start + size;
+                self.fragment(text, &spans[window.clone()], &matches[window], score)
+            })
+            .collect()
+    }
+
+    /// Term matched by each of the `count` tokens, as an index in `terms`.
+    ///
+    /// Normalized tokens are matched back to the tokens of the text by
+    /// position, tokens removed by the pipeline do not match.
+    fn matches(
+        &mut self,
+        tokens: &mut Tokens,
+        count: usize,
+        terms: &[impl AsRef<str>],
+    ) -> Vec<Option<usize>> {
+        let terms = terms
+            .iter()
+            .enumerate()
+            .map(|(position, term)| (term.as_ref(), position))
+            .collect::<HashMap<_, _>>();
+
+        if !self.pipeline.is_empty() {
+            self.pipeline.run(tokens);
+        }
+
+        let mut matches = vec![None; count];
+        for token in tokens.iter() {
+            let matched = &mut matches[token.position()];
+            *matched = matched.or(terms.get(token.as_str()).copied());
+        }
+        matches
+    }
+
+    fn fragment(
+        &self,
+        text: &str,
+        spans: &[Range<usize>],
+        matches: &[Option<usize>],
+        score: f32,
+    ) -> Fragment {
+        let range = spans[0].start..spans[spans.len() - 1].end;
+        let mut highlighted = String::with_capacity(range.len());
+        let mut cursor = range.start;
+
+        let matched = spans
+            .iter()
+            .zip(matches)
+            .filter_map(|(span, term)| term.map(|_| span));
+
+        for span in matched {
+            highlighted.push_str(&text[cursor..span.start]);
+            highlighted.push_str(&self.pre);
+            highlighted.push_str(&text[span.clone()]);
+            highlighted.push_str(&self.post);
+            cursor = span.end;
+        }
+        highlighted.push_str(&text[cursor..range.end]);
+
+        Fragment {
+            text: highlighted,
+            range,
+            score,
+        }
+    }
+}
+
+/// Score of the matches of a fragment, `None` without matches.
+fn score(matches: &[Option<usize>]) -> Option<f32> {
+    let mut terms = matches.iter().flatten().collect::<Vec<_>>();
+    let total = terms.len();
+
+    terms.sort_unstable();
+    terms.dedup();
+
+    let repeats = total - terms.len();
+    (total > 0).then_some(terms.len() as f32 + repeats as f32 * REPEAT_WEIGHT)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        highlight::Highlighter,
+        normalizer::{case::Lowercase, punctuation::Punctuation, NormalizerPipeline},
+        tokenizer::{Standard, Tokenizer, Whitespace},
+    };
+
+    fn pipeline() -> NormalizerPipeline {
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+        pipeline.insert(Box::new(Punctuation::new()));
+        pipeline
+    }
+
+    #[test]
+    fn test_highlight_original_text() {
+        let tokenizer = Tokenizer::Whitespace(Whitespace::new());
+        let mut highlighter = Highlighter::new(tokenizer, pipeline()).with_markers("[", "]");
+
+        // Tokens are matched once normalized, and highlighted as written.
+        let fragments = highlighter.highlight("The Quick, brown FOX jumps.", &["quick", "fox"]);
+
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(fragments[0].text, "The [Quick,] brown [FOX] jumps.");
+        assert_eq!(fragments[0].range, 0..27);
+        assert_eq!(fragments[0].score, 2.0);
+        assert!(highlighter.highlight("The lazy dog", &["fox"]).is_empty());
+    }
+
+    #[test]
+    fn test_highlight_fragments() {
+        let text = "Rust is fast. Memory safety without a garbage collector. \
+                    Many teams pick Rust for safety, and Rust for speed. \
+                    The end of the text mentions nothing.";
+
+        let tokenizer = Tokenizer::Standard(Standard::new());
+        let mut highlighter = Highlighter::new(tokenizer, pipeline())
+            .with_fragment_size(5)
+            .with_fragments(2);
+        let fragments = highlighter.highlight(text, &["rust", "safety"]);
+
+        // Both terms first, then the single match at the start.
+        let texts = fragments
+            .iter()
+            .map(|fragment| fragment.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [
+                "<em>Rust</em> for <em>safety</em>, and <em>Rust</em>",
+                "<em>Rust</em> is fast. Memory <em>safety</em>",
+            ]
+        );
+        assert!((fragments[0].score - 2.1).abs() < 1e-6);
+        assert_eq!(
+            &text[fragments[1].range.clone()],
+            "Rust is fast. Memory safety"
+        );
+    }
+}
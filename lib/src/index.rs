@@ -4,21 +4,116 @@
 //! Inverted Index
 //! The [`InvertedIndex`] handles the core inverted index data structure and
 //! exposes methods to perform operations.
+//!
+//! Persistence
+//! A [`CoreIndex`] can be written to and loaded from an immutable on-disk
+//! segment, see [`crate::segment`] for the format. Inserts made after the
+//! last segment are recovered from the optional [`WriteAheadLog`].
+//!
+//! Concurrency
+//! Searches run on immutable snapshots published by the [`Index`],
+//! see [`crate::snapshot`].
 
-use std::fmt::Debug;
+use std::{cmp::Ordering, fmt::Debug, hash::Hash, path::Path, sync::Arc};
 
 use crate::{
-    core::{InvertedIndex, Store, TermCounter},
+    codec::Codec,
+    core::{InvertedIndex, Resource, Store, TermCounter, TfEntry},
+    error::{Error, SegmentError},
+    facet::Facets,
+    field::{self, FieldTokens},
     reader::{IndexReader, ReaderContext},
-    token::Tokens,
+    segment::{SegmentReader, SegmentWriter},
+    snapshot::{Published, Snapshot, SnapshotReader},
+    token::{Token, Tokens},
+    wal::{Record, WriteAheadLog},
     writer::{IndexWriter, ResourceState, WriterContext},
 };
 
 pub trait Indexer {
-    type R: Clone + Debug;
+    type R: Clone + Debug + Hash + Eq;
 
     fn new(capacity: usize, threshold: usize) -> Self;
-    fn insert(&mut self, resource: Self::R, word_count: usize, tokens: &mut Tokens);
+
+    /// Indexes the resource, returning its index.
+    fn insert(
+        &mut self,
+        resource: Self::R,
+        word_count: usize,
+        tokens: &mut Tokens,
+    ) -> Result<usize, Error> {
+        self.insert_with_facets(resource, word_count, tokens, Facets::new())
+    }
+
+    /// Indexes the resource with its facets, returning its index.
+    fn insert_with_facets(
+        &mut self,
+        resource: Self::R,
+        word_count: usize,
+        tokens: &mut Tokens,
+        facets: Facets,
+    ) -> Result<usize, Error> {
+        let tokens = std::mem::replace(tokens, Vec::new().into());
+        let fields = vec![FieldTokens::unnamed(word_count, tokens)];
+        self.insert_fields(resource, fields, facets)
+    }
+
+    /// Indexes the resource made of several fields, with its facets,
+    /// returning its index.
+    ///
+    /// Terms of named fields are indexed under qualified keys,
+    /// see [`crate::field`].
+    fn insert_fields(
+        &mut self,
+        resource: Self::R,
+        fields: Vec<FieldTokens>,
+        facets: Facets,
+    ) -> Result<usize, Error>;
+
+    /// Deletes the resource, returning `false` if it does not exist
+    /// or is already deleted.
+    fn delete(&mut self, index: usize) -> Result<bool, Error>;
+
+    /// Replaces the resource at `index` with a new version,
+    /// returning the index of the new version.
+    ///
+    /// Fails with [`Error::NotFound`] if the resource does not exist or
+    /// is already deleted.
+    fn update(
+        &mut self,
+        index: usize,
+        resource: Self::R,
+        word_count: usize,
+        tokens: &mut Tokens,
+    ) -> Result<usize, Error> {
+        self.update_with_facets(index, resource, word_count, tokens, Facets::new())
+    }
+
+    /// Replaces the resource at `index` with a new version and its facets,
+    /// returning the index of the new version.
+    fn update_with_facets(
+        &mut self,
+        index: usize,
+        resource: Self::R,
+        word_count: usize,
+        tokens: &mut Tokens,
+        facets: Facets,
+    ) -> Result<usize, Error> {
+        let tokens = std::mem::replace(tokens, Vec::new().into());
+        let fields = vec![FieldTokens::unnamed(word_count, tokens)];
+        self.update_fields(index, resource, fields, facets)
+    }
+
+    /// Replaces the resource at `index` with a new version made of several
+    /// fields, returning the index of the new version.
+    fn update_fields(
+        &mut self,
+        index: usize,
+        resource: Self::R,
+        fields: Vec<FieldTokens>,
+        facets: Facets,
+    ) -> Result<usize, Error>;
+
     fn get(&self, index: usize) -> Option<Self::R>;
     fn reader(&self) -> ReaderContext<'_, Self::R>;
 }
@@ -30,51 +125,129 @@ pub trait Indexer {
 /// optimizing throughput and tail latency.
 ///
 /// The current strategy utilizes a single-threaded, thread-local indexer
-/// and perform a merge operation to generate a global index view.
+/// and perform a merge operation to generate a global index view,
+/// see [`CoreIndex::merge`].
 #[derive(Debug)]
-pub struct Index<R: Clone + Debug> {
+pub struct Index<R: Clone + Debug + Hash + Eq> {
     pub core: CoreIndex<R>,
     pub capacity: usize,
     pub threshold: usize,
+    pub policy: DuplicatePolicy,
+    wal: Option<WriteAheadLog<R>>,
+    generation: u64,
+    published: Arc<Published<R>>,
 }
 
-impl<R: Clone + Debug> Indexer for Index<R> {
+/// Behavior when inserting a resource that is already indexed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Fail with [`Error::Duplicate`].
+    #[default]
+    Reject,
+
+    /// Replace the indexed resource, see [`Indexer::update`].
+    Update,
+}
+
+impl<R: Clone + Debug + Hash + Eq> Indexer for Index<R> {
     type R = R;
 
     /// Creates a new instance of `Index`
     fn new(capacity: usize, threshold: usize) -> Self {
         // TODO: Ensure threshold is less than capacity.
 
-        Self {
-            core: CoreIndex::with_capacity(capacity),
-            capacity,
-            threshold,
+        Self::from_core(CoreIndex::with_capacity(capacity), capacity, threshold)
+    }
+
+    // The resource is stored before its terms, a system outage in between
+    // leaves the store and the inverted index out of sync.
+    //
+    // With a write-ahead log attached, the insert is durable before it is
+    // applied, and the in-memory state is re-constructed from the last
+    // segment and the log on startup.
+    fn insert_fields(
+        &mut self,
+        resource: R,
+        fields: Vec<FieldTokens>,
+        facets: Facets,
+    ) -> Result<usize, Error> {
+        if let Some(existing) = self.core.store.lookup(&resource) {
+            return match self.policy {
+                DuplicatePolicy::Reject => Err(Error::Duplicate(existing)),
+                DuplicatePolicy::Update => self.update_fields(existing, resource, fields, facets),
+            };
         }
+
+        let (word_count, fields, mut tokens) = flatten(fields);
+
+        if let Some(wal) = self.wal.as_mut() {
+            let index = self.core.store.len();
+            let terms = tokens
+                .iter()
+                .map(|token| (token.as_str(), token.position()));
+            wal.append_insert(index, None, &resource, word_count, terms, &facets, &fields)?;
+        }
+
+        Ok(self.apply(resource, word_count, &mut tokens, facets, fields))
     }
 
-    // TODO:
-    // File entry is stored.
-    // What if system outage happens at this stage.
-    // The file store and inverted index won't sync,
-    // ends up with potentially corrupted state.
+    // Deleted resources are tombstoned, their postings are skipped
+    // by readers until the index is compacted.
+    fn delete(&mut self, index: usize) -> Result<bool, Error> {
+        if index >= self.core.store.len() || self.core.store.is_deleted(index) {
+            return Ok(false);
+        }
 
-    // Approach 1 (runtime overhead):
-    // Maintain write-ahead logs to re-construct the
-    // core index in correct state.
+        if let Some(wal) = self.wal.as_mut() {
+            wal.append_delete(index)?;
+        }
 
-    // Approach 2:
-    // On startup, validate for such scenarios and handle,
-    // before re-constructing the in-memory structure.
-    fn insert(&mut self, resource: R, word_count: usize, tokens: &mut Tokens) {
-        let writer = self.core.writer();
-        let resource_entry = WriterContext::<ResourceState, R>::new(writer);
-        let mut term_entry = resource_entry.entry(resource, word_count);
+        Ok(self.core.writer().delete_resource(index))
+    }
+
+    fn update_fields(
+        &mut self,
+        index: usize,
+        resource: R,
+        fields: Vec<FieldTokens>,
+        facets: Facets,
+    ) -> Result<usize, Error> {
+        let store = &self.core.store;
+
+        // A stale index would otherwise insert a second version.
+        if index >= store.len() || store.is_deleted(index) {
+            return Err(Error::NotFound(index));
+        }
+
+        // The key belongs to another resource.
+        if let Some(existing) = store
+            .lookup(&resource)
+            .filter(|&existing| existing != index)
+        {
+            return Err(Error::Duplicate(existing));
+        }
+
+        let (word_count, fields, mut tokens) = flatten(fields);
+
+        if let Some(wal) = self.wal.as_mut() {
+            let next = self.core.store.len();
+            let terms = tokens
+                .iter()
+                .map(|token| (token.as_str(), token.position()));
+            wal.append_insert(
+                next,
+                Some(index),
+                &resource,
+                word_count,
+                terms,
+                &facets,
+                &fields,
+            )?;
+        }
 
-        tokens.for_each_mut(|token| {
-            term_entry.insert_term_with(|| std::mem::take(token));
-        });
+        self.core.writer().delete_resource(index);
 
-        term_entry.reset_counter()
+        Ok(self.apply(resource, word_count, &mut tokens, facets, fields))
     }
 
     fn get(&self, index: usize) -> Option<R> {
@@ -88,14 +261,231 @@ impl<R: Clone + Debug> Indexer for Index<R> {
     }
 }
 
-#[derive(Debug)]
-pub struct CoreIndex<R: Clone + Debug> {
+impl<R: Clone + Debug + Hash + Eq> Index<R> {
+    /// Creates an `Index` on top of an existing [`CoreIndex`].
+    pub fn from_core(core: CoreIndex<R>, capacity: usize, threshold: usize) -> Self {
+        Self {
+            core,
+            capacity,
+            threshold,
+            policy: DuplicatePolicy::default(),
+            wal: None,
+            generation: 0,
+            published: Arc::new(Published::new(Snapshot::new(
+                0,
+                CoreIndex::with_capacity(0),
+            ))),
+        }
+    }
+
+    /// Publishes a snapshot of the current state, returning its generation.
+    ///
+    /// The writes since the last publish are frozen into layers shared
+    /// with the snapshot, see [`CoreIndex::freeze`], so publishing only
+    /// copies the writes of the batch. Nothing is visible to readers
+    /// before the first publish.
+    pub fn publish(&mut self) -> u64
+    where
+        R: Send + Sync + 'static,
+    {
+        self.generation += 1;
+        self.core.freeze();
+        let snapshot = Snapshot::new(self.generation, self.core.clone());
+        self.published.store(snapshot);
+        self.generation
+    }
+
+    /// Handle to the published snapshots, to be shared with searching threads.
+    pub fn snapshots(&self) -> SnapshotReader<R> {
+        SnapshotReader::new(Arc::clone(&self.published))
+    }
+
+    /// Records term positions for the resources indexed from now on.
+    ///
+    /// Positions are persisted in segments, an index loaded from a
+    /// segment written with positions keeps recording them.
+    pub fn with_positions(mut self) -> Self {
+        self.core.index.enable_positions();
+        self
+    }
+
+    /// Sets the behavior for resources that are already indexed.
+    pub fn with_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Recovers the index from the segment at `path`, if it exists,
+    /// and the inserts logged after it.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        wal: WriteAheadLog<R>,
+        capacity: usize,
+        threshold: usize,
+    ) -> Result<Self, Error> {
+        Self::load(path, wal.codec(), capacity, threshold)?.with_wal(wal)
+    }
+
+    /// Loads the index from the segment at `path`, if it exists,
+    /// otherwise creates an empty index.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        codec: &dyn Codec<R>,
+        capacity: usize,
+        threshold: usize,
+    ) -> Result<Self, Error> {
+        let core = match path.as_ref().exists() {
+            true => CoreIndex::load(path, codec)?,
+            false => CoreIndex::with_capacity(capacity),
+        };
+
+        Ok(Self::from_core(core, capacity, threshold))
+    }
+
+    /// Attaches a write-ahead log, replaying the operations missing from the index.
+    pub fn with_wal(mut self, mut wal: WriteAheadLog<R>) -> Result<Self, Error> {
+        for record in wal.replay()? {
+            let expected = self.core.store.len();
+
+            match record {
+                Record::Insert {
+                    index,
+                    replaces,
+                    resource,
+                    word_count,
+                    terms,
+                    facets,
+                    fields,
+                } => match index.cmp(&expected) {
+                    // Already persisted in the segment.
+                    Ordering::Less => continue,
+                    Ordering::Equal => {
+                        if let Some(index) = replaces {
+                            self.core.writer().delete_resource(index);
+                        }
+
+                        let mut tokens = terms
+                            .into_iter()
+                            .map(|(term, position)| Token::from(term).with_position(position))
+                            .collect();
+                        self.apply(resource, word_count, &mut tokens, facets, fields);
+                    }
+                    Ordering::Greater => {
+                        let message = format!("missing log record for resource {expected}");
+                        return Err(SegmentError::Corrupted(message).into());
+                    }
+                },
+                Record::Delete { index } if index < expected => {
+                    self.core.writer().delete_resource(index);
+                }
+                Record::Delete { index } => {
+                    let message = format!("delete of unknown resource {index}");
+                    return Err(SegmentError::Corrupted(message).into());
+                }
+            }
+        }
+
+        self.wal = Some(wal);
+        Ok(self)
+    }
+
+    /// Size in bytes of the operations logged since the last checkpoint,
+    /// zero without a write-ahead log.
+    ///
+    /// Checkpointing rewrites the whole segment, so it is meant to be done
+    /// once the log grows past a threshold, the log keeps the operations
+    /// durable in between.
+    pub fn wal_size(&self) -> u64 {
+        self.wal.as_ref().map_or(0, WriteAheadLog::size)
+    }
+
+    /// Persists the index as a segment at `path`,
+    /// then truncates the write-ahead log.
+    pub fn checkpoint<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        codec: &dyn Codec<R>,
+    ) -> Result<(), Error> {
+        self.core.save(path, codec)?;
+
+        match self.wal.as_mut() {
+            Some(wal) => wal.truncate(),
+            None => Ok(()),
+        }
+    }
+
+    fn apply(
+        &mut self,
+        resource: R,
+        word_count: usize,
+        tokens: &mut Tokens,
+        facets: Facets,
+        fields: Vec<(String, usize)>,
+    ) -> usize {
+        let writer = self.core.writer();
+        let resource_entry = WriterContext::<ResourceState, R>::new(writer);
+        let entry = Resource::new(resource, word_count)
+            .with_facets(facets)
+            .with_fields(fields);
+        let mut term_entry = resource_entry.resource_entry(entry);
+
+        // Positions are kept from tokenization, so the terms dropped by
+        // normalization still count in the distances between terms.
+        for token in tokens.iter_mut() {
+            let position = token.position() as u32;
+            term_entry.insert_term_at(std::mem::take(token).into(), position);
+        }
+
+        term_entry.reset_counter();
+        term_entry.index()
+    }
+}
+
+/// Concatenates the tokens of the fields, with the terms of named fields
+/// qualified, returning the total word count and the word count of each
+/// named field.
+///
+/// Positions keep counting across fields, offset by the word count of the
+/// previous fields. A qualified term only occurs in its own field, so the
+/// distances between its positions are unchanged.
+fn flatten(fields: Vec<FieldTokens>) -> (usize, Vec<(String, usize)>, Tokens) {
+    let word_count = fields.iter().map(FieldTokens::word_count).sum();
+    let mut counts = Vec::<(String, usize)>::new();
+    let mut tokens = Vec::new();
+    let mut base = 0;
+
+    for field in fields {
+        let name = field.name().to_string();
+        let offset = base;
+        base += field.word_count();
+
+        if !name.is_empty() {
+            match counts.iter_mut().find(|(existing, _)| *existing == name) {
+                Some((_, count)) => *count += field.word_count(),
+                None => counts.push((name.clone(), field.word_count())),
+            }
+        }
+
+        tokens.extend(field.into_tokens().into_iter().map(|mut token| {
+            if !name.is_empty() {
+                *token.inner_mut() = field::qualify(&name, &token);
+            }
+            let position = offset + token.position();
+            token.with_position(position)
+        }));
+    }
+
+    (word_count, counts, tokens.into())
+}
+
+#[derive(Clone, Debug)]
+pub struct CoreIndex<R: Clone + Debug + Hash + Eq> {
     store: Store<R>,
     index: InvertedIndex,
     count: TermCounter,
 }
 
-impl<R: Clone + Debug> CoreIndex<R> {
+impl<R: Clone + Debug + Hash + Eq> CoreIndex<R> {
     /// Creates a new instance of [`CoreIndex`]
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
@@ -106,6 +496,19 @@ impl<R: Clone + Debug> CoreIndex<R> {
         }
     }
 
+    /// Creates a new instance of [`CoreIndex`] recording term positions.
+    #[inline]
+    pub fn with_positions(capacity: usize) -> Self {
+        let mut core = Self::with_capacity(capacity);
+        core.index.enable_positions();
+        core
+    }
+
+    #[inline]
+    pub fn has_positions(&self) -> bool {
+        self.index.has_positions()
+    }
+
     /// The `CoreIndex` never interacts with external environment
     /// directly for READ operations.
     ///
@@ -114,7 +517,7 @@ impl<R: Clone + Debug> CoreIndex<R> {
     /// # See Also
     ///
     /// - [`IndexWriter`]: Provides WRITE access to the index.
-    pub fn reader(&self) -> IndexReader<R> {
+    pub fn reader(&self) -> IndexReader<'_, R> {
         IndexReader::new(&self.store, &self.index, &self.count)
     }
 
@@ -126,7 +529,699 @@ impl<R: Clone + Debug> CoreIndex<R> {
     /// # See Also
     ///
     /// - [`IndexReader`]: Provides READ access to the index.
-    pub fn writer(&mut self) -> IndexWriter<R> {
+    pub fn writer(&mut self) -> IndexWriter<'_, R> {
         IndexWriter::new(&mut self.store, &mut self.index, &mut self.count)
     }
+
+    /// Moves the writes since the last freeze into immutable layers, so
+    /// clones of the index share them instead of copying them.
+    ///
+    /// Small layers are merged as they accumulate, see [`crate::layer`].
+    pub fn freeze(&mut self) {
+        self.store.freeze();
+        self.index.freeze();
+    }
+
+    /// Writes the index to `path` as an immutable segment.
+    pub fn save<P: AsRef<Path>>(&self, path: P, codec: &dyn Codec<R>) -> Result<(), Error> {
+        SegmentWriter::new(codec).write(path, self)
+    }
+
+    /// Loads an index previously written with [`CoreIndex::save`].
+    pub fn load<P: AsRef<Path>>(path: P, codec: &dyn Codec<R>) -> Result<Self, Error> {
+        SegmentReader::new(codec).read(path)
+    }
+
+    /// Physically removes the postings of deleted resources,
+    /// returning the number of removed postings.
+    ///
+    /// Deleted resources keep their slot in the store, so indices stay stable.
+    pub fn compact(&mut self) -> usize {
+        if !self.store.has_tombstones() {
+            return 0;
+        }
+
+        let store = &self.store;
+        self.index
+            .remove_entries(|entry| store.is_deleted(entry.get_index()))
+    }
+
+    /// Merges several indexes into a single global index.
+    ///
+    /// Resources keep their relative order, the resources of each index
+    /// are placed after those of the previous ones.
+    pub fn merge<'a, I>(indexes: I) -> Self
+    where
+        I: IntoIterator<Item = &'a CoreIndex<R>>,
+        R: 'a,
+    {
+        let indexes = indexes.into_iter().collect::<Vec<_>>();
+        let capacity = indexes.iter().map(|core| core.store.len()).sum();
+
+        indexes
+            .into_iter()
+            .fold(Self::with_capacity(capacity), |mut global, core| {
+                global.merge_from(core);
+                global
+            })
+    }
+
+    /// Appends the resources and postings of `other` to this index,
+    /// returning the offset added to the resource indices of `other`.
+    ///
+    /// Postings of a term present in both indexes are combined, so the
+    /// document frequency is the sum of both document frequencies.
+    /// A resource already present in this index is replaced.
+    pub fn merge_from(&mut self, other: &CoreIndex<R>) -> usize {
+        let offset = self.store.len();
+        if other.has_positions() {
+            self.index.enable_positions();
+        }
+
+        let source = other.reader();
+        let mut writer = self.writer();
+
+        for (index, resource) in source.resources().enumerate() {
+            let entry = resource.clone();
+
+            if source.is_deleted(index) {
+                writer.insert_deleted_resource(entry);
+                continue;
+            }
+
+            // The most recently merged resource wins.
+            if let Some(previous) = writer.lookup(resource.resource_ref()) {
+                writer.delete_resource(previous);
+            }
+
+            writer.insert_resource(entry);
+        }
+
+        for (term, idf_entry) in source.terms() {
+            let mut cursor = idf_entry.iter();
+            while let Some(entry) = cursor.next() {
+                // Dead postings are dropped, the tombstone is carried over.
+                if source.is_deleted(entry.get_index()) {
+                    continue;
+                }
+
+                let tf_entry = TfEntry::new(offset + entry.get_index(), *entry.get_frequency());
+                writer.push_term(term.to_string(), tf_entry, &cursor.positions());
+            }
+        }
+
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use crate::{
+        codec::StringCodec,
+        error::Error,
+        facet::Facets,
+        field::{self, FieldTokens},
+        index::{CoreIndex, DuplicatePolicy, Index, Indexer},
+        segment::SegmentWriter,
+        token::Tokens,
+        tokens,
+        wal::WriteAheadLog,
+    };
+
+    fn documents() -> Vec<(String, Tokens)> {
+        vec![
+            ("a.txt".into(), tokens!["quick", "brown", "fox"]),
+            ("b.txt".into(), tokens!["lazy", "dog", "dog"]),
+            ("c.txt".into(), tokens!["quick", "dog"]),
+        ]
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("idx-index-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn encode(core: &CoreIndex<String>) -> Vec<u8> {
+        SegmentWriter::new(&StringCodec).encode(core)
+    }
+
+    /// In-memory index of the first `n` documents, without a log.
+    fn expected(n: usize) -> Vec<u8> {
+        let mut index = Index::new(10, 8);
+        for (resource, mut tokens) in documents().into_iter().take(n) {
+            let word_count = tokens.count();
+            index.insert(resource, word_count, &mut tokens).unwrap();
+        }
+        encode(&index.core)
+    }
+
+    fn recover(segment: &Path, wal: &Path) -> Index<String> {
+        let wal = WriteAheadLog::open(wal, StringCodec).unwrap();
+        Index::open(segment, wal, 10, 8).unwrap()
+    }
+
+    #[test]
+    fn test_index_merge() {
+        let mut left = Index::new(10, 8);
+        let mut right = Index::new(10, 8);
+
+        for (position, (resource, mut tokens)) in documents().into_iter().enumerate() {
+            let index = if position == 0 { &mut left } else { &mut right };
+            let word_count = tokens.count();
+            index.insert(resource, word_count, &mut tokens).unwrap();
+        }
+
+        let global = CoreIndex::merge([&left.core, &right.core]);
+        let reader = global.reader();
+
+        assert_eq!(encode(&global), expected(3));
+        assert_eq!(reader.total_documents(), 3);
+        assert_eq!(reader.document_frequency("quick"), Some(2));
+        assert_eq!(reader.document_frequency("dog"), Some(2));
+        assert_eq!(reader.get_index(2).unwrap().resource(), "c.txt");
+
+        let remapped = reader
+            .get_term_entries("dog")
+            .unwrap()
+            .iter()
+            .map(|entry| (entry.get_index(), *entry.get_frequency()))
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(remapped, [(1, 2), (2, 1)].into());
+    }
+
+    #[test]
+    fn test_index_merge_empty() {
+        let left: Index<String> = Index::new(10, 8);
+        let global = CoreIndex::merge([&left.core]);
+        assert_eq!(global.reader().total_documents(), 0);
+
+        let global = CoreIndex::<String>::merge([]);
+        assert_eq!(global.reader().total_documents(), 0);
+    }
+
+    #[test]
+    fn test_index_wal_crash_during_append() {
+        let directory = temp_dir("append");
+        let segment = directory.join("segment.idx");
+        let wal = directory.join("wal.log");
+
+        let mut index = recover(&segment, &wal);
+        let mut boundaries = vec![0];
+
+        for (resource, mut tokens) in documents() {
+            let word_count = tokens.count();
+            index.insert(resource, word_count, &mut tokens).unwrap();
+            boundaries.push(fs::metadata(&wal).unwrap().len() as usize);
+        }
+
+        drop(index);
+        let log = fs::read(&wal).unwrap();
+
+        // A crash may tear the log at any byte.
+        for len in 0..=log.len() {
+            fs::write(&wal, &log[..len]).unwrap();
+
+            let complete = boundaries.iter().filter(|&&end| end <= len).count() - 1;
+            let index = recover(&segment, &wal);
+
+            assert_eq!(encode(&index.core), expected(complete), "torn at {len}");
+            assert_eq!(
+                fs::metadata(&wal).unwrap().len() as usize,
+                boundaries[complete]
+            );
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_index_wal_crash_before_apply() {
+        let directory = temp_dir("apply");
+        let segment = directory.join("segment.idx");
+        let wal = directory.join("wal.log");
+
+        // The record is durable, but the process dies before the
+        // resource and its terms reach the in-memory index.
+        let mut log = WriteAheadLog::open(&wal, StringCodec).unwrap();
+        for (index, (resource, tokens)) in documents().into_iter().enumerate() {
+            let terms = tokens
+                .iter()
+                .map(|token| (token.as_str(), token.position()));
+            log.append_insert(
+                index,
+                None,
+                &resource,
+                tokens.count(),
+                terms,
+                &Facets::new(),
+                &[],
+            )
+            .unwrap();
+        }
+        drop(log);
+
+        let index = recover(&segment, &wal);
+        assert_eq!(encode(&index.core), expected(3));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_index_wal_crash_during_checkpoint() {
+        let directory = temp_dir("checkpoint");
+        let segment = directory.join("segment.idx");
+        let wal = directory.join("wal.log");
+
+        let mut index = recover(&segment, &wal);
+        for (resource, mut tokens) in documents().into_iter().take(2) {
+            let word_count = tokens.count();
+            index.insert(resource, word_count, &mut tokens).unwrap();
+        }
+
+        // Crash before the segment is renamed into place.
+        fs::write(directory.join("segment.idx.tmp"), b"partial").unwrap();
+        assert_eq!(encode(&recover(&segment, &wal).core), expected(2));
+
+        // Crash after the segment is written, before the log is truncated.
+        index.core.save(&segment, &StringCodec).unwrap();
+        assert_eq!(encode(&recover(&segment, &wal).core), expected(2));
+
+        // Complete checkpoint, followed by more inserts.
+        let mut index = recover(&segment, &wal);
+        assert_eq!(index.wal_size(), fs::metadata(&wal).unwrap().len());
+        index.checkpoint(&segment, &StringCodec).unwrap();
+        assert_eq!(fs::metadata(&wal).unwrap().len(), 0);
+        assert_eq!(index.wal_size(), 0);
+
+        let (resource, mut tokens) = documents().pop().unwrap();
+        let word_count = tokens.count();
+        index.insert(resource, word_count, &mut tokens).unwrap();
+        drop(index);
+
+        assert_eq!(encode(&recover(&segment, &wal).core), expected(3));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_index_facets() {
+        let directory = temp_dir("facets");
+        let segment = directory.join("segment.idx");
+        let wal = directory.join("wal.log");
+        let facets = |extension: &str| Facets::new().with("extension", extension);
+
+        let mut index = recover(&segment, &wal);
+        index
+            .insert_with_facets("a.md".into(), 1, &mut tokens!["fox"], facets("md"))
+            .unwrap();
+        index.checkpoint(&segment, &StringCodec).unwrap();
+
+        // Logged after the checkpoint, then replaced.
+        index
+            .insert_with_facets("b.rs".into(), 1, &mut tokens!["dog"], facets("rs"))
+            .unwrap();
+        index
+            .update_with_facets(1, "b.rs".into(), 1, &mut tokens!["dog"], facets("txt"))
+            .unwrap();
+        drop(index);
+
+        let index = recover(&segment, &wal);
+        let reader = index.reader();
+        assert_eq!(reader.facets(0), Some(&facets("md")));
+        assert_eq!(reader.facets(1), None);
+        assert_eq!(reader.facets(2), Some(&facets("txt")));
+
+        // Facets follow their resource when merging.
+        let global = CoreIndex::merge([&populated().core, &index.core]);
+        let reader = global.reader();
+        assert_eq!(reader.get_index(3).unwrap().facets(), &facets("md"));
+        assert!(reader.get_index(0).unwrap().facets().is_empty());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    fn populated() -> Index<String> {
+        let mut index = Index::new(10, 8);
+        for (resource, mut tokens) in documents() {
+            let word_count = tokens.count();
+            index.insert(resource, word_count, &mut tokens).unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_index_fields() {
+        let directory = temp_dir("fields");
+        let segment = directory.join("segment.idx");
+        let wal = directory.join("wal.log");
+        let fields = |title: Tokens, body: Tokens| {
+            vec![
+                FieldTokens::new("title", title.count(), title),
+                FieldTokens::unnamed(body.count(), body),
+            ]
+        };
+
+        let mut index = recover(&segment, &wal);
+        index
+            .insert_fields(
+                "a.md".into(),
+                fields(tokens!["fox"], tokens!["quick", "fox"]),
+                Facets::new(),
+            )
+            .unwrap();
+        index.checkpoint(&segment, &StringCodec).unwrap();
+
+        // Logged after the checkpoint, then replaced.
+        index
+            .insert_fields(
+                "b.md".into(),
+                fields(tokens!["lazy", "dog"], tokens!["dog"]),
+                Facets::new(),
+            )
+            .unwrap();
+        index
+            .update_fields(
+                1,
+                "b.md".into(),
+                fields(tokens!["dog"], tokens!["lazy", "dog", "sleeps"]),
+                Facets::new(),
+            )
+            .unwrap();
+        drop(index);
+
+        let index = recover(&segment, &wal);
+        let reader = index.reader();
+        assert_eq!(reader.count(2), 4);
+        assert_eq!(reader.field_count(2, Some("title")), 1);
+        assert_eq!(reader.field_count(2, None), 3);
+        assert_eq!(reader.average_field_count(Some("title")), 1.0);
+        assert_eq!(reader.average_field_count(None), 2.5);
+
+        // Terms of named fields have their own postings.
+        assert_eq!(reader.document_frequency("fox"), Some(1));
+        assert_eq!(
+            reader.document_frequency(&field::qualify("title", "fox")),
+            Some(1)
+        );
+        assert_eq!(
+            reader.document_frequency(&field::qualify("title", "lazy")),
+            None
+        );
+        assert_eq!(reader.document_frequency("lazy"), Some(1));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_index_delete() {
+        let mut index = populated();
+
+        assert!(index.delete(1).unwrap());
+        assert!(!index.delete(1).unwrap());
+        assert!(!index.delete(10).unwrap());
+
+        let reader = index.core.reader();
+        assert_eq!(reader.total_documents(), 2);
+        assert_eq!(reader.average_count(), 2.5);
+        assert_eq!(reader.document_frequency("dog"), Some(1));
+        assert_eq!(reader.document_frequency("lazy"), None);
+        assert_eq!(index.get(1), None);
+
+        let ctx = index.reader();
+        let postings = ctx
+            .postings("dog")
+            .unwrap()
+            .map(|entry| entry.get_index())
+            .collect::<Vec<_>>();
+        assert_eq!(postings, vec![2]);
+    }
+
+    #[test]
+    fn test_index_update() {
+        let mut index = populated();
+
+        let updated = index
+            .update(0, "a.txt".into(), 2, &mut tokens!["slow", "fox"])
+            .unwrap();
+
+        assert_eq!(updated, 3);
+        assert_eq!(index.get(0), None);
+        assert_eq!(index.get(3), Some("a.txt".into()));
+
+        let reader = index.core.reader();
+        assert_eq!(reader.total_documents(), 3);
+        assert_eq!(reader.document_frequency("quick"), Some(1));
+        assert_eq!(reader.document_frequency("slow"), Some(1));
+
+        // Stale and unknown indexes are not inserted again.
+        assert!(matches!(
+            index.update(0, "a.txt".into(), 1, &mut tokens!["fox"]),
+            Err(Error::NotFound(0))
+        ));
+        assert!(matches!(
+            index.update(9, "d.txt".into(), 1, &mut tokens!["fox"]),
+            Err(Error::NotFound(9))
+        ));
+        assert_eq!(index.core.reader().total_documents(), 3);
+    }
+
+    #[test]
+    fn test_index_compact() {
+        let mut index = populated();
+        index.delete(1).unwrap();
+
+        let before = encode(&index.core);
+        assert_eq!(index.core.compact(), 2);
+        assert_eq!(index.core.compact(), 0);
+
+        let reader = index.core.reader();
+        assert!(reader.get_term_entries("lazy").is_none());
+        assert!(!reader.dictionary().contains("lazy"));
+        assert!(reader.dictionary().contains("dog"));
+        assert_eq!(reader.get_term_entries("dog").unwrap().count(), 1);
+        assert_eq!(reader.total_documents(), 2);
+        assert_ne!(encode(&index.core), before);
+    }
+
+    #[test]
+    fn test_index_persist_tombstones() {
+        let directory = temp_dir("tombstones");
+        let segment = directory.join("segment.idx");
+        let wal = directory.join("wal.log");
+
+        let mut index = recover(&segment, &wal);
+        for (resource, mut tokens) in documents().into_iter().take(2) {
+            let word_count = tokens.count();
+            index.insert(resource, word_count, &mut tokens).unwrap();
+        }
+        index.delete(0).unwrap();
+        index.checkpoint(&segment, &StringCodec).unwrap();
+
+        // Logged after the checkpoint.
+        let (resource, mut tokens) = documents().pop().unwrap();
+        let word_count = tokens.count();
+        index.update(1, resource, word_count, &mut tokens).unwrap();
+        let state = encode(&index.core);
+        drop(index);
+
+        let index = recover(&segment, &wal);
+        assert_eq!(encode(&index.core), state);
+        assert_eq!(index.core.reader().total_documents(), 1);
+        assert_eq!(index.get(2), Some("c.txt".into()));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_index_merge_tombstones() {
+        let mut left = populated();
+        let mut right = Index::new(10, 8);
+        left.delete(0).unwrap();
+        right
+            .insert("d.txt".into(), 2, &mut tokens!["brown", "bear"])
+            .unwrap();
+
+        let global = CoreIndex::merge([&left.core, &right.core]);
+        let reader = global.reader();
+
+        assert_eq!(reader.total_documents(), 3);
+        assert!(reader.is_deleted(0));
+        assert!(!reader.is_deleted(3));
+        assert_eq!(reader.document_frequency("quick"), Some(1));
+        assert_eq!(reader.get_term_entries("brown").unwrap().count(), 1);
+        assert_eq!(reader.lookup(&"a.txt".into()), None);
+    }
+
+    #[test]
+    fn test_index_duplicate_reject() {
+        let mut index = populated();
+        let (resource, mut tokens) = documents().remove(1);
+        let word_count = tokens.count();
+
+        assert!(matches!(
+            index.insert(resource, word_count, &mut tokens),
+            Err(Error::Duplicate(1))
+        ));
+
+        let reader = index.core.reader();
+        assert_eq!(reader.total_documents(), 3);
+        assert_eq!(reader.document_frequency("dog"), Some(2));
+        assert_eq!(index.reader().lookup(&"b.txt".into()), Some(1));
+    }
+
+    #[test]
+    fn test_index_duplicate_update() {
+        let mut index = populated().with_policy(DuplicatePolicy::Update);
+
+        let updated = index
+            .insert("b.txt".into(), 2, &mut tokens!["lazy", "cat"])
+            .unwrap();
+
+        assert_eq!(updated, 3);
+        assert_eq!(index.reader().lookup(&"b.txt".into()), Some(3));
+
+        let reader = index.core.reader();
+        assert_eq!(reader.total_documents(), 3);
+        assert_eq!(reader.document_frequency("dog"), Some(1));
+        assert_eq!(reader.document_frequency("cat"), Some(1));
+
+        // The key of a deleted resource can be indexed again.
+        index.delete(3).unwrap();
+        assert_eq!(index.reader().lookup(&"b.txt".into()), None);
+        assert_eq!(
+            index
+                .insert("b.txt".into(), 1, &mut tokens!["dog"])
+                .unwrap(),
+            4
+        );
+
+        // Another resource cannot take over an indexed key.
+        assert!(matches!(
+            index.update(4, "a.txt".into(), 1, &mut tokens!["fox"]),
+            Err(Error::Duplicate(0))
+        ));
+    }
+
+    #[test]
+    fn test_index_merge_duplicates() {
+        let left = populated();
+        let right = populated();
+
+        let global = CoreIndex::merge([&left.core, &right.core]);
+        let reader = global.reader();
+
+        assert_eq!(reader.total_documents(), 3);
+        assert_eq!(reader.document_frequency("dog"), Some(2));
+        assert_eq!(reader.lookup(&"a.txt".into()), Some(3));
+        assert!((0..3).all(|index| reader.is_deleted(index)));
+    }
+
+    #[test]
+    fn test_index_positions() {
+        let mut index = Index::new(10, 8).with_positions();
+        index
+            .insert("a.txt".into(), 4, &mut tokens!["dog", "eat", "dog", "food"])
+            .unwrap();
+        index
+            .insert("b.txt".into(), 1, &mut tokens!["dog"])
+            .unwrap();
+
+        let ctx = index.reader();
+        assert!(ctx.has_positions());
+        assert_eq!(ctx.positions("dog", 0).unwrap().collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(ctx.positions("food", 0).unwrap().collect::<Vec<_>>(), [3]);
+        assert!(ctx.positions("food", 1).is_none());
+
+        // Positions follow the postings when merging and compacting.
+        let mut global = CoreIndex::merge([&populated().core, &index.core]);
+        let reader = global.reader();
+        assert!(reader.has_positions());
+        assert_eq!(reader.get_positions("dog", 3), Some(vec![0, 2]));
+        assert_eq!(reader.get_positions("dog", 1), None);
+
+        global.writer().delete_resource(3);
+        global.compact();
+        assert_eq!(global.reader().get_positions("dog", 3), None);
+        assert_eq!(global.reader().get_positions("dog", 4), Some(vec![0]));
+    }
+
+    #[test]
+    fn test_index_without_positions() {
+        let index = populated();
+        let ctx = index.reader();
+
+        assert!(!ctx.has_positions());
+        assert!(ctx.positions("quick", 0).is_none());
+    }
+
+    #[test]
+    fn test_index_publish_layers() {
+        let build = |publish: bool| {
+            let mut index = Index::new(10, 8)
+                .with_positions()
+                .with_policy(DuplicatePolicy::Update);
+            let mut snapshots = Vec::new();
+            let mut publish = |index: &mut Index<String>| {
+                if publish {
+                    index.publish();
+                    snapshots.push(index.snapshots().load());
+                }
+            };
+
+            let colors = ["red", "green", "blue", "cyan", "pink"];
+            for document in 0..100 {
+                let mut tokens = tokens!["quick", colors[document % 5], "fox"];
+                index
+                    .insert(format!("{document}.txt"), 3, &mut tokens)
+                    .unwrap();
+                publish(&mut index);
+            }
+
+            for document in (0..100).step_by(10) {
+                index.delete(document).unwrap();
+                publish(&mut index);
+            }
+
+            index
+                .insert("5.txt".into(), 2, &mut tokens!["lazy", "fox"])
+                .unwrap();
+            publish(&mut index);
+
+            index.core.compact();
+            (index, snapshots)
+        };
+
+        // Layers are invisible to readers, the index is the same as one
+        // built without publishing.
+        let (layered, snapshots) = build(true);
+        let (flat, _) = build(false);
+        let codec = StringCodec::new();
+        let writer = SegmentWriter::new(&codec);
+        assert_eq!(writer.encode(&layered.core), writer.encode(&flat.core));
+
+        let reader = layered.reader();
+        assert_eq!(reader.lookup(&"5.txt".into()), Some(100));
+        assert_eq!(reader.lookup(&"10.txt".into()), None);
+        assert_eq!(reader.lookup(&"11.txt".into()), Some(11));
+        assert_eq!(reader.document_frequency("fox"), Some(100 - 10));
+        assert_eq!(
+            reader.positions("fox", 100).unwrap().collect::<Vec<_>>(),
+            [1]
+        );
+
+        // Earlier snapshots are unaffected by later writes and compaction.
+        let snapshot = &snapshots[9];
+        let reader = snapshot.reader();
+        assert_eq!(reader.total_documents(), 10);
+        assert_eq!(reader.document_frequency("blue"), Some(2));
+        assert_eq!(reader.lookup(&"0.txt".into()), Some(0));
+        assert_eq!(reader.positions("fox", 0).unwrap().collect::<Vec<_>>(), [2]);
+    }
 }
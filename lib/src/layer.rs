@@ -0,0 +1,184 @@
+//! Structurally shared layers of the index.
+//!
+//! Each structure of a [`CoreIndex`](crate::index::CoreIndex) is split into
+//! immutable frozen layers, shared behind an [`Arc`], and a mutable buffer
+//! receiving the writes. Freezing moves the buffer into a new frozen layer,
+//! so publishing a snapshot only copies the handles of the frozen layers,
+//! see [`Index::publish`](crate::index::Index::publish).
+//!
+//! The two newest frozen layers are merged while the older one is at most
+//! twice as large, so layers get geometrically smaller from oldest to newest.
+//! A structure is thus split into a logarithmic number of layers, and each
+//! write is merged a logarithmic number of times.
+
+use std::{collections::BTreeSet, hash::Hash, sync::Arc};
+
+use hashbrown::{HashMap, HashSet};
+
+/// Contents of a layer, merged with a newer layer when frozen layers are merged.
+pub trait Layer: Clone + Default {
+    /// Size of the layer, in entries.
+    fn len(&self) -> usize;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Merges the newer layer into this one.
+    fn merge(&mut self, newer: Self);
+}
+
+/// Frozen layers and the buffer of a structure, from oldest to newest.
+#[derive(Clone, Debug, Default)]
+pub struct Layers<T: Layer> {
+    frozen: Vec<Arc<T>>,
+    buffer: T,
+}
+
+impl<T: Layer> Layers<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_buffer(buffer: T) -> Self {
+        Self {
+            frozen: Vec::new(),
+            buffer,
+        }
+    }
+
+    #[inline]
+    pub fn buffer_mut(&mut self) -> &mut T {
+        &mut self.buffer
+    }
+
+    /// Number of frozen layers.
+    #[inline]
+    pub fn frozen(&self) -> usize {
+        self.frozen.len()
+    }
+
+    /// Layers from oldest to newest, the buffer last.
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.frozen.iter().map(Arc::as_ref).chain([&self.buffer])
+    }
+
+    /// Mutable layers from oldest to newest, the buffer last.
+    ///
+    /// Frozen layers shared with a snapshot are copied first.
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.frozen
+            .iter_mut()
+            .map(Arc::make_mut)
+            .chain([&mut self.buffer])
+    }
+
+    /// Moves the buffer into a new frozen layer, then merges the newest
+    /// layers while the older one is at most twice as large.
+    pub fn freeze(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let buffer = std::mem::take(&mut self.buffer);
+        self.frozen.push(Arc::new(buffer));
+
+        while let [.., older, newer] = &self.frozen[..] {
+            if older.len() > newer.len() * 2 {
+                break;
+            }
+
+            let newer = self.frozen.pop().unwrap();
+            let older = self.frozen.last_mut().unwrap();
+            Arc::make_mut(older).merge(Arc::unwrap_or_clone(newer));
+        }
+    }
+}
+
+impl<T: Clone> Layer for Vec<T> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn merge(&mut self, newer: Self) {
+        self.extend(newer);
+    }
+}
+
+impl<T: Clone + Ord> Layer for BTreeSet<T> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn merge(&mut self, mut newer: Self) {
+        self.append(&mut newer);
+    }
+}
+
+impl<T: Clone + Hash + Eq> Layer for HashSet<T> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn merge(&mut self, newer: Self) {
+        self.extend(newer);
+    }
+}
+
+/// Values of the newer layer replace the values of the same keys.
+impl<K: Clone + Hash + Eq, V: Clone> Layer for HashMap<K, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn merge(&mut self, newer: Self) {
+        self.extend(newer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::layer::Layers;
+
+    #[test]
+    fn test_layers_freeze() {
+        let mut layers = Layers::<Vec<usize>>::new();
+        let mut snapshots = Vec::new();
+
+        for batch in 0..64 {
+            layers.buffer_mut().extend(batch * 4..batch * 4 + 4);
+            layers.freeze();
+            snapshots.push(layers.clone());
+        }
+
+        // Layers get smaller from oldest to newest, and stay few.
+        let sizes = layers.iter().map(Vec::len).collect::<Vec<_>>();
+        assert!(sizes.windows(2).all(|pair| pair[0] >= pair[1]), "{sizes:?}");
+        assert!(layers.frozen() <= 7, "{sizes:?}");
+        assert!(layers.iter().flatten().copied().eq(0..256));
+
+        // Clones share the frozen layers, and keep them while merged.
+        let last = snapshots.last().unwrap();
+        assert!(Arc::ptr_eq(&last.frozen[0], &layers.frozen[0]));
+        assert!(snapshots[2].iter().flatten().copied().eq(0..12));
+
+        // Nothing to freeze.
+        layers.freeze();
+        assert_eq!(layers.iter().map(Vec::len).collect::<Vec<_>>(), sizes);
+    }
+}
@@ -1,16 +1,27 @@
 pub mod aggregate;
+pub mod codec;
 pub mod core;
+pub mod dictionary;
 pub mod distance;
 pub mod document;
 pub mod error;
+pub mod explain;
+pub mod facet;
+pub mod field;
 pub mod filter;
+pub mod highlight;
 pub mod index;
+pub mod layer;
 pub mod normalizer;
+pub mod posting;
 pub mod query;
 pub mod rank;
 pub mod reader;
 pub mod score;
+pub mod segment;
+pub mod snapshot;
 pub mod token;
 pub mod tokenizer;
 pub mod util;
+pub mod wal;
 pub mod writer;
@@ -1,3 +1,6 @@
+//! Case normalizers, Unicode aware: "École" and "ÉCOLE" both lowercase to
+//! "école".
+
 use crate::{normalizer::TextNormalizer, token::Tokens};
 
 #[derive(Clone, Debug, Default)]
@@ -12,7 +15,7 @@ impl Lowercase {
 impl TextNormalizer for Lowercase {
     fn normalize(&mut self, tokens: &mut Tokens) {
         tokens.iter_mut().for_each(|token| {
-            token.as_mut().make_ascii_lowercase();
+            lowercase(token.inner_mut());
         })
     }
 }
@@ -28,17 +31,55 @@ impl Uppercase {
 
 impl TextNormalizer for Uppercase {
     fn normalize(&mut self, tokens: &mut Tokens) {
-        tokens.iter_mut().for_each(|token| {
-            token.as_mut().make_ascii_uppercase();
+        tokens.iter_mut().for_each(|token| match token.is_ascii() {
+            true => token.make_ascii_uppercase(),
+            false => *token.inner_mut() = token.to_uppercase(),
         })
     }
 }
 
+/// Case folding, for caseless matching: unlike [`Lowercase`], folds the
+/// characters whose lowercase differs across forms, e.g. "Straße" and
+/// "STRASSE" both fold to "strasse", and a final "ς" to "σ".
+///
+/// Folds to the lowercase of the uppercase of the text, with the final
+/// sigma folded too. This matches the full case folding of Unicode but for
+/// a few characters, e.g. "İ".
+#[derive(Clone, Debug, Default)]
+pub struct CaseFold;
+
+impl CaseFold {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TextNormalizer for CaseFold {
+    fn normalize(&mut self, tokens: &mut Tokens) {
+        tokens.iter_mut().for_each(|token| match token.is_ascii() {
+            true => token.make_ascii_lowercase(),
+            false => *token.inner_mut() = token.to_uppercase().to_lowercase().replace('ς', "σ"),
+        })
+    }
+}
+
+/// Lowercases the text in place, without allocating if it is ASCII.
+pub(crate) fn lowercase(text: &mut String) {
+    match text.is_ascii() {
+        true => text.make_ascii_lowercase(),
+        false => *text = text.to_lowercase(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Lowercase;
     use crate::{
-        normalizer::{case::Uppercase, TextNormalizer},
+        normalizer::{
+            case::{CaseFold, Uppercase},
+            TextNormalizer,
+        },
+        tokenizer::{Standard, TextTokenizer},
         tokens,
     };
 
@@ -73,4 +114,28 @@ mod tests {
         normalizer.normalize(&mut tokens);
         assert_eq!(tokens, tokens!["THE", "TOKENIZED", "STRING"])
     }
+
+    #[test]
+    fn test_normalizer_unicode_case() {
+        let mut tokens = Standard::new().tokenize("École ÉCOLE ΣΟΦΊΑ");
+        Lowercase::new().normalize(&mut tokens);
+        assert_eq!(tokens, tokens!["école", "école", "σοφία"]);
+
+        // Offsets still point to the token as written.
+        let token = tokens.iter().nth(1).unwrap();
+        assert_eq!((token.offsets(), token.position()), (7..13, 1));
+
+        Uppercase::new().normalize(&mut tokens);
+        assert_eq!(tokens, tokens!["ÉCOLE", "ÉCOLE", "ΣΟΦΊΑ"]);
+    }
+
+    #[test]
+    fn test_normalizer_case_fold() {
+        let mut tokens = tokens!["Straße", "STRASSE", "ὈΔΥΣΣΕΎΣ", "ὀδυσσεύς", "Fox"];
+        CaseFold::new().normalize(&mut tokens);
+        assert_eq!(
+            tokens,
+            tokens!["strasse", "strasse", "ὀδυσσεύσ", "ὀδυσσεύσ", "fox"]
+        );
+    }
 }
@@ -1,24 +1,317 @@
 // https://github.com/explosion/spaCy/blob/master/spacy/pipeline/lemmatizer.py
 
-use hashbrown::HashMap;
+//! Lookup and rules lemmatizer, replacing the words with their lemma:
+//! "mice" becomes "mouse" and "running" becomes "run".
+//!
+//! Like the rule lemmatizer of spaCy, each part of speech has exceptions,
+//! an index of known lemmas, and suffix rules. Without a tagger, the parts
+//! of speech are tried in turn, see [`Lemmatizer::lemma`].
+//!
+//! Tables are loaded from a directory, see [`Lemmatizer::load`].
 
-enum Pos {
-    Adjective,
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    ops::RangeInclusive,
+    path::Path,
+    sync::Arc,
+};
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    error::{ConfigError, Error},
+    normalizer::TextNormalizer,
+    token::Tokens,
+};
+
+/// Part of speech, in the order the tables are looked up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pos {
     Noun,
-    Punctuation,
     Verb,
+    Adjective,
+    Punctuation,
 }
 
-pub struct Lemmatizer {
-    lookup: Lookup<Vec<(String, String)>>,
+impl Pos {
+    pub const ALL: [Pos; 4] = [Pos::Noun, Pos::Verb, Pos::Adjective, Pos::Punctuation];
+
+    /// Name of the part of speech in the names of the table files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pos::Noun => "noun",
+            Pos::Verb => "verb",
+            Pos::Adjective => "adj",
+            Pos::Punctuation => "punct",
+        }
+    }
 }
 
 type Map<V> = HashMap<Pos, V>;
 
-struct Lookup<R: IntoIterator<Item = (String, String)>> {
+#[derive(Clone, Debug, Default)]
+struct Lookup {
     exceptions: Map<HashMap<String, String>>,
-    index: Map<HashMap<String, String>>,
-    rules: Map<R>,
+    index: Map<HashSet<String>>,
+    rules: Map<Vec<(String, String)>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Lemmatizer {
+    lookup: Arc<Lookup>,
 }
 
-impl<R: IntoIterator<Item = (String, String)>> Lookup<R> {}
+impl Lemmatizer {
+    /// Lemmatizer without tables, leaving the words unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the tables of each part of speech from the directory.
+    ///
+    /// Files are named after the part of speech, see [`Pos::name`], e.g.
+    /// for nouns:
+    /// - `noun_exc.txt`, a word and its lemma per line,
+    /// - `noun_index.txt`, a lemma per line,
+    /// - `noun_rules.txt`, a suffix and its replacement per line, no
+    ///   replacement removes the suffix.
+    ///
+    /// Missing files are empty tables, blank lines and lines starting with
+    /// `#` are skipped.
+    pub fn load<P: AsRef<Path>>(directory: P) -> Result<Self, Error> {
+        let directory = directory.as_ref();
+        if !directory.is_dir() {
+            return Err(ConfigError::File(std::io::ErrorKind::NotFound).into());
+        }
+
+        let mut lemmatizer = Self::new();
+
+        for pos in Pos::ALL {
+            let table = |kind: &str| directory.join(format!("{}_{kind}.txt", pos.name()));
+
+            let exceptions = read_table(&table("exc"), 2..=2)?;
+            let index = read_table(&table("index"), 1..=1)?;
+            let rules = read_table(&table("rules"), 1..=2)?;
+
+            lemmatizer = lemmatizer
+                .with_exceptions(pos, exceptions.into_iter().map(pair))
+                .with_index(pos, index.into_iter().flatten())
+                .with_rules(pos, rules.into_iter().map(pair));
+        }
+
+        Ok(lemmatizer)
+    }
+
+    /// Adds irregular forms of the part of speech, with their lemma.
+    pub fn with_exceptions<I, S>(mut self, pos: Pos, exceptions: I) -> Self
+    where
+        I: IntoIterator<Item = (S, S)>,
+        S: Into<String>,
+    {
+        Arc::make_mut(&mut self.lookup)
+            .exceptions
+            .entry(pos)
+            .or_default()
+            .extend(
+                exceptions
+                    .into_iter()
+                    .map(|(form, lemma)| (form.into(), lemma.into())),
+            );
+        self
+    }
+
+    /// Adds known lemmas of the part of speech.
+    pub fn with_index<I, S>(mut self, pos: Pos, lemmas: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Arc::make_mut(&mut self.lookup)
+            .index
+            .entry(pos)
+            .or_default()
+            .extend(lemmas.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds suffix rules of the part of speech, tried in order.
+    pub fn with_rules<I, S>(mut self, pos: Pos, rules: I) -> Self
+    where
+        I: IntoIterator<Item = (S, S)>,
+        S: Into<String>,
+    {
+        Arc::make_mut(&mut self.lookup)
+            .rules
+            .entry(pos)
+            .or_default()
+            .extend(
+                rules
+                    .into_iter()
+                    .map(|(suffix, replacement)| (suffix.into(), replacement.into())),
+            );
+        self
+    }
+
+    /// Lemma of the lowercase word, `None` if the word is its own lemma or
+    /// has no known lemma.
+    ///
+    /// An exception of any part of speech wins, then a word of an index is
+    /// its own lemma. Otherwise, the first form produced by the rules that
+    /// is in the index of their part of speech is the lemma. Without an
+    /// index, the first form produced by its rules is.
+    pub fn lemma(&self, word: &str) -> Option<String> {
+        let lookup = &*self.lookup;
+
+        if let Some(lemma) = Pos::ALL
+            .iter()
+            .find_map(|pos| lookup.exceptions.get(pos)?.get(word))
+        {
+            return Some(lemma.clone());
+        }
+
+        if lookup.index.values().any(|index| index.contains(word)) {
+            return None;
+        }
+
+        Pos::ALL.iter().find_map(|pos| {
+            let index = lookup.index.get(pos).filter(|index| !index.is_empty());
+
+            lookup
+                .rules
+                .get(pos)?
+                .iter()
+                .find_map(|(suffix, replacement)| {
+                    let stem = word.strip_suffix(suffix.as_str())?;
+                    let form = format!("{stem}{replacement}");
+
+                    let known = index.is_none_or(|index| index.contains(&form));
+                    (!stem.is_empty() && known).then_some(form)
+                })
+        })
+    }
+}
+
+impl TextNormalizer for Lemmatizer {
+    fn normalize(&mut self, tokens: &mut Tokens) {
+        tokens.iter_mut().for_each(|token| {
+            if let Some(lemma) = self.lemma(token.as_str()) {
+                *token.inner_mut() = lemma;
+            }
+        });
+    }
+}
+
+/// First two columns of a row, the second one empty if missing.
+fn pair(row: Vec<String>) -> (String, String) {
+    let mut columns = row.into_iter();
+    let first = columns.next().unwrap_or_default();
+    (first, columns.next().unwrap_or_default())
+}
+
+/// Columns of the lines of the table file, empty if the file is missing.
+fn read_table(path: &Path, columns: RangeInclusive<usize>) -> Result<Vec<Vec<String>>, Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(ConfigError::File(error.kind()).into()),
+    };
+
+    let mut rows = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|error| ConfigError::Reader(error.kind()))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let row = line
+            .split_whitespace()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if !columns.contains(&row.len()) {
+            return Err(ConfigError::Normalizer(format!(
+                "Error in {}. Invalid line: '{line}'",
+                path.display()
+            ))
+            .into());
+        }
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        normalizer::{
+            lemmatize::{Lemmatizer, Pos},
+            TextNormalizer,
+        },
+        tokenizer::{Standard, TextTokenizer},
+        tokens,
+    };
+
+    fn lemmatizer() -> Lemmatizer {
+        Lemmatizer::new()
+            .with_exceptions(Pos::Noun, [("mice", "mouse")])
+            .with_exceptions(Pos::Verb, [("ran", "run")])
+            .with_index(Pos::Noun, ["mouse", "fox", "news", "city"])
+            .with_index(Pos::Verb, ["run", "jump", "make"])
+            .with_rules(Pos::Noun, [("s", ""), ("es", ""), ("ies", "y")])
+            .with_rules(
+                Pos::Verb,
+                [("s", ""), ("ing", ""), ("ing", "e"), ("ning", "")],
+            )
+    }
+
+    #[test]
+    fn test_lemmatizer_lemma() {
+        let lemmatizer = lemmatizer();
+
+        assert_eq!(lemmatizer.lemma("mice").as_deref(), Some("mouse"));
+        assert_eq!(lemmatizer.lemma("ran").as_deref(), Some("run"));
+        assert_eq!(lemmatizer.lemma("foxes").as_deref(), Some("fox"));
+        assert_eq!(lemmatizer.lemma("cities").as_deref(), Some("city"));
+        assert_eq!(lemmatizer.lemma("making").as_deref(), Some("make"));
+        assert_eq!(lemmatizer.lemma("running").as_deref(), Some("run"));
+
+        // Known lemmas and unknown forms are left unchanged.
+        assert_eq!(lemmatizer.lemma("news"), None);
+        assert_eq!(lemmatizer.lemma("this"), None);
+    }
+
+    #[test]
+    fn test_lemmatizer_without_index() {
+        let lemmatizer = Lemmatizer::new().with_rules(Pos::Adjective, [("est", ""), ("er", "")]);
+
+        assert_eq!(lemmatizer.lemma("smallest").as_deref(), Some("small"));
+        assert_eq!(lemmatizer.lemma("er"), None);
+    }
+
+    #[test]
+    fn test_normalizer_lemmatizer() {
+        let mut tokens = Standard::new().tokenize("mice ran making foxes");
+        lemmatizer().normalize(&mut tokens);
+
+        assert_eq!(tokens, tokens!["mouse", "run", "make", "fox"]);
+        let token = tokens.iter().nth(3).unwrap();
+        assert_eq!((token.offsets(), token.position()), (16..21, 3));
+    }
+
+    #[test]
+    fn test_lemmatizer_load() {
+        let lemmatizer = Lemmatizer::load("../assets/lemmatizer/en").unwrap();
+
+        let mut tokens = Standard::new().tokenize("children were running and jumping over boxes");
+        lemmatizer.clone().normalize(&mut tokens);
+        assert_eq!(
+            tokens,
+            tokens!["child", "be", "run", "and", "jump", "over", "box"]
+        );
+
+        assert!(Lemmatizer::load("../assets/lemmatizer/missing").is_err());
+    }
+}
@@ -1,8 +1,10 @@
 pub mod case;
+pub mod lemmatize;
 pub mod punctuation;
 pub mod replace;
+pub mod stemmer;
 pub mod stopwords;
-pub mod lemmatize;
+pub mod unicode;
 
 pub use stopwords::Stopwords;
 
@@ -4,10 +4,7 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use crate::{
-    normalizer::TextNormalizer,
-    token::{Token, Tokens},
-};
+use crate::{normalizer::TextNormalizer, token::Tokens};
 
 #[derive(Clone, Debug)]
 pub struct TokenReplacer<V>
@@ -44,8 +41,9 @@ where
 {
     fn normalize(&mut self, tokens: &mut Tokens) {
         tokens.iter_mut().for_each(|token| {
+            // The text is replaced in place, the token keeps its offsets.
             if let Some(replacement) = self.pairs.read().unwrap().get(token.inner_ref()) {
-                *token = Token::from(replacement.to_string());
+                *token.inner_mut() = replacement.to_string();
             }
         });
     }
@@ -57,6 +55,7 @@ mod tests {
 
     use crate::{
         normalizer::{replace::TokenReplacer, TextNormalizer},
+        tokenizer::{Standard, TextTokenizer},
         tokens,
     };
 
@@ -79,4 +78,19 @@ mod tests {
             tokens!["The", "quick", "brown", "fox", "jumps", "over", "the", "lazy", "dog"]
         ]
     }
+
+    #[test]
+    fn test_normalizer_replace_keeps_offsets() {
+        let mut tokens = Standard::new().tokenize("the lazy dog");
+
+        let mut pairs = HashMap::new();
+        pairs.insert("lazy".into(), "quick");
+
+        let mut normalizer = TokenReplacer::new(pairs);
+        normalizer.normalize(&mut tokens);
+
+        let token = tokens.iter().nth(1).unwrap();
+        assert_eq!(token.as_str(), "quick");
+        assert_eq!((token.offsets(), token.position()), (4..8, 1));
+    }
 }
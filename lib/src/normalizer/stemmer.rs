@@ -1 +1,522 @@
+//! Stemmers, reducing the words to their stem: "jumps" and "jumping" both
+//! become "jump".
+//!
+//! Algorithms follow the Snowball design, suffixes are removed in steps,
+//! each one only when it lies in a region of the word, see [`Word`]. A
+//! language is added with an [`Algorithm`] and a variant of [`Language`].
 
+use std::{borrow::Cow, fmt::Debug};
+
+use crate::{normalizer::TextNormalizer, token::Tokens};
+
+/// Stemming algorithm of a language.
+pub trait Algorithm: Debug + Send + Sync {
+    /// Stem of the lowercase word, the word itself if it is left unchanged.
+    fn stem<'w>(&self, word: &'w str) -> Cow<'w, str>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    English,
+}
+
+impl Language {
+    pub fn algorithm(&self) -> &'static dyn Algorithm {
+        match self {
+            Language::English => &English,
+        }
+    }
+}
+
+/// Replaces each token with its stem, in the language.
+///
+/// Stems are computed from lowercase words, the stemmer is expected to run
+/// after [`Lowercase`](crate::normalizer::case::Lowercase).
+#[derive(Clone, Debug, Default)]
+pub struct Stemmer {
+    language: Language,
+}
+
+impl Stemmer {
+    pub fn new(language: Language) -> Self {
+        Self { language }
+    }
+
+    #[inline]
+    pub fn language(&self) -> Language {
+        self.language
+    }
+}
+
+impl TextNormalizer for Stemmer {
+    fn normalize(&mut self, tokens: &mut Tokens) {
+        let algorithm = self.language.algorithm();
+
+        tokens.iter_mut().for_each(|token| {
+            if let Cow::Owned(stem) = algorithm.stem(token.as_str()) {
+                *token.inner_mut() = stem;
+            }
+        });
+    }
+}
+
+/// Word being stemmed, with its Snowball regions.
+///
+/// R1 is the region after the first non-vowel following a vowel, R2 the
+/// region after the first non-vowel following a vowel in R1. Both are
+/// empty when there is no such non-vowel.
+#[derive(Debug)]
+pub struct Word {
+    chars: Vec<u8>,
+    vowels: &'static [u8],
+    r1: usize,
+    r2: usize,
+}
+
+impl Word {
+    /// Word with the regions computed from the vowels of the language, R1
+    /// starting after the first of the prefixes the word starts with.
+    pub fn new(word: &str, vowels: &'static [u8], prefixes: &[&str]) -> Self {
+        let mut word = Self {
+            chars: word.as_bytes().to_vec(),
+            vowels,
+            r1: 0,
+            r2: 0,
+        };
+
+        word.r1 = prefixes
+            .iter()
+            .find(|prefix| word.chars.starts_with(prefix.as_bytes()))
+            .map_or_else(|| word.region_after(0), |prefix| prefix.len());
+        word.r2 = word.region_after(word.r1);
+        word
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    #[inline]
+    pub fn is_vowel(&self, char: u8) -> bool {
+        self.vowels.contains(&char)
+    }
+
+    #[inline]
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        self.chars.ends_with(suffix.as_bytes())
+    }
+
+    /// Longest of the suffixes the word ends with.
+    pub fn longest<'s>(&self, suffixes: &[&'s str]) -> Option<&'s str> {
+        suffixes
+            .iter()
+            .filter(|suffix| self.ends_with(suffix))
+            .max_by_key(|suffix| suffix.len())
+            .copied()
+    }
+
+    /// Whether the suffix lies in R1.
+    #[inline]
+    pub fn in_r1(&self, suffix: &str) -> bool {
+        self.len() - suffix.len() >= self.r1
+    }
+
+    /// Whether the suffix lies in R2.
+    #[inline]
+    pub fn in_r2(&self, suffix: &str) -> bool {
+        self.len() - suffix.len() >= self.r2
+    }
+
+    /// Part of the word before the suffix, which the word ends with.
+    #[inline]
+    pub fn before(&self, suffix: &str) -> &[u8] {
+        &self.chars[..self.len() - suffix.len()]
+    }
+
+    /// Replaces the suffix, which the word ends with.
+    pub fn replace(&mut self, suffix: &str, replacement: &str) {
+        self.chars.truncate(self.len() - suffix.len());
+        self.chars.extend_from_slice(replacement.as_bytes());
+    }
+
+    fn region_after(&self, start: usize) -> usize {
+        (start + 1..self.len())
+            .find(|&index| {
+                self.is_vowel(self.chars[index - 1]) && !self.is_vowel(self.chars[index])
+            })
+            .map_or(self.len(), |index| index + 1)
+    }
+
+    fn into_string(self) -> String {
+        // Only ASCII characters are replaced, the word stays valid UTF-8.
+        String::from_utf8(self.chars).expect("stem is not valid UTF-8")
+    }
+}
+
+const ENGLISH_VOWELS: &[u8] = b"aeiouy";
+
+/// Prefixes after which R1 starts, instead of its usual start.
+const ENGLISH_PREFIXES: [&str; 3] = ["gener", "commun", "arsen"];
+
+/// Words with a stem the steps do not produce.
+const ENGLISH_EXCEPTIONS: [(&str, &str); 18] = [
+    ("skis", "ski"),
+    ("skies", "sky"),
+    ("dying", "die"),
+    ("lying", "lie"),
+    ("tying", "tie"),
+    ("idly", "idl"),
+    ("gently", "gentl"),
+    ("ugly", "ugli"),
+    ("early", "earli"),
+    ("only", "onli"),
+    ("singly", "singl"),
+    ("sky", "sky"),
+    ("news", "news"),
+    ("howe", "howe"),
+    ("atlas", "atlas"),
+    ("cosmos", "cosmos"),
+    ("bias", "bias"),
+    ("andes", "andes"),
+];
+
+/// Words left as they are once their plural is removed.
+const ENGLISH_INVARIANTS: [&str; 8] = [
+    "inning", "outing", "canning", "herring", "earring", "proceed", "exceed", "succeed",
+];
+
+const STEP_2: [(&str, &str); 24] = [
+    ("tional", "tion"),
+    ("enci", "ence"),
+    ("anci", "ance"),
+    ("abli", "able"),
+    ("entli", "ent"),
+    ("izer", "ize"),
+    ("ization", "ize"),
+    ("ational", "ate"),
+    ("ation", "ate"),
+    ("ator", "ate"),
+    ("alism", "al"),
+    ("aliti", "al"),
+    ("alli", "al"),
+    ("fulness", "ful"),
+    ("ousli", "ous"),
+    ("ousness", "ous"),
+    ("iveness", "ive"),
+    ("iviti", "ive"),
+    ("biliti", "ble"),
+    ("bli", "ble"),
+    ("ogi", "og"),
+    ("fulli", "ful"),
+    ("lessli", "less"),
+    ("li", ""),
+];
+
+const STEP_3: [(&str, &str); 9] = [
+    ("tional", "tion"),
+    ("ational", "ate"),
+    ("alize", "al"),
+    ("icate", "ic"),
+    ("iciti", "ic"),
+    ("ical", "ic"),
+    ("ful", ""),
+    ("ness", ""),
+    ("ative", ""),
+];
+
+const STEP_4: [&str; 18] = [
+    "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ism", "ate",
+    "iti", "ous", "ive", "ize", "ion",
+];
+
+/// English (Porter2) stemmer.
+///
+/// Words with characters outside of ASCII are left unchanged.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct English;
+
+impl Algorithm for English {
+    fn stem<'w>(&self, word: &'w str) -> Cow<'w, str> {
+        if let Some((_, stem)) = ENGLISH_EXCEPTIONS.iter().find(|(form, _)| *form == word) {
+            return Cow::Borrowed(stem);
+        }
+
+        if word.len() <= 2 || !word.is_ascii() {
+            return Cow::Borrowed(word);
+        }
+
+        let word = word.strip_prefix('\'').unwrap_or(word);
+        let mut stem = Word::new(&mark_consonant_y(word), ENGLISH_VOWELS, &ENGLISH_PREFIXES);
+
+        English::step_0(&mut stem);
+        English::step_1a(&mut stem);
+
+        if !ENGLISH_INVARIANTS
+            .iter()
+            .any(|&invariant| stem.chars == invariant.as_bytes())
+        {
+            English::step_1b(&mut stem);
+            English::step_1c(&mut stem);
+            English::step_2(&mut stem);
+            English::step_3(&mut stem);
+            English::step_4(&mut stem);
+            English::step_5(&mut stem);
+        }
+
+        Cow::Owned(unmark_consonant_y(stem.into_string(), word))
+    }
+}
+
+impl English {
+    /// Removes the possessive.
+    fn step_0(word: &mut Word) {
+        if let Some(suffix) = word.longest(&["'s'", "'s", "'"]) {
+            word.replace(suffix, "");
+        }
+    }
+
+    /// Removes the plural.
+    fn step_1a(word: &mut Word) {
+        match word.longest(&["sses", "ied", "ies", "s", "us", "ss"]) {
+            Some("sses") => word.replace("sses", "ss"),
+            Some(suffix @ ("ied" | "ies")) => match word.len() > 4 {
+                true => word.replace(suffix, "i"),
+                false => word.replace(suffix, "ie"),
+            },
+            // A vowel before the letter preceding the "s", so "gas" stays.
+            Some("s") => {
+                let preceding = &word.chars[..word.len().saturating_sub(2)];
+                if preceding.iter().any(|&char| word.is_vowel(char)) {
+                    word.replace("s", "");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Removes the past tense and the progressive.
+    fn step_1b(word: &mut Word) {
+        match word.longest(&["eed", "eedly", "ed", "edly", "ing", "ingly"]) {
+            Some(suffix @ ("eed" | "eedly")) if word.in_r1(suffix) => word.replace(suffix, "ee"),
+            Some("eed" | "eedly") | None => {}
+            Some(suffix) => {
+                if !word.before(suffix).iter().any(|&char| word.is_vowel(char)) {
+                    return;
+                }
+                word.replace(suffix, "");
+
+                if word.ends_with("at") || word.ends_with("bl") || word.ends_with("iz") {
+                    word.replace("", "e");
+                } else if ends_with_double(&word.chars) {
+                    word.chars.pop();
+                } else if English::is_short(word) {
+                    word.replace("", "e");
+                }
+            }
+        }
+    }
+
+    /// Replaces a final "y" after a consonant, not the first letter, by "i".
+    fn step_1c(word: &mut Word) {
+        let len = word.len();
+        if len > 2
+            && matches!(word.chars[len - 1], b'y' | b'Y')
+            && !word.is_vowel(word.chars[len - 2])
+        {
+            word.chars[len - 1] = b'i';
+        }
+    }
+
+    fn step_2(word: &mut Word) {
+        let suffixes = STEP_2.map(|(suffix, _)| suffix);
+        let Some(suffix) = word.longest(&suffixes) else {
+            return;
+        };
+        if !word.in_r1(suffix) {
+            return;
+        }
+
+        let preceding = word.before(suffix).last().copied();
+        let replacement = STEP_2[suffixes.iter().position(|&s| s == suffix).unwrap()].1;
+
+        match suffix {
+            "ogi" if preceding != Some(b'l') => {}
+            "li" if !preceding.is_some_and(|char| b"cdeghkmnrt".contains(&char)) => {}
+            _ => word.replace(suffix, replacement),
+        }
+    }
+
+    fn step_3(word: &mut Word) {
+        let suffixes = STEP_3.map(|(suffix, _)| suffix);
+        let Some(suffix) = word.longest(&suffixes) else {
+            return;
+        };
+        if !word.in_r1(suffix) || (suffix == "ative" && !word.in_r2(suffix)) {
+            return;
+        }
+
+        let replacement = STEP_3[suffixes.iter().position(|&s| s == suffix).unwrap()].1;
+        word.replace(suffix, replacement);
+    }
+
+    fn step_4(word: &mut Word) {
+        let Some(suffix) = word.longest(&STEP_4) else {
+            return;
+        };
+        if !word.in_r2(suffix) {
+            return;
+        }
+
+        let preceding = word.before(suffix).last().copied();
+        if suffix != "ion" || matches!(preceding, Some(b's' | b't')) {
+            word.replace(suffix, "");
+        }
+    }
+
+    fn step_5(word: &mut Word) {
+        if word.ends_with("e") {
+            let short = ends_with_short_syllable(word.before("e"), word.vowels);
+            if word.in_r2("e") || (word.in_r1("e") && !short) {
+                word.replace("e", "");
+            }
+        } else if word.ends_with("l") && word.in_r2("l") && word.before("l").ends_with(b"l") {
+            word.replace("l", "");
+        }
+    }
+
+    /// Whether the word ends with a short syllable, and R1 is empty.
+    fn is_short(word: &Word) -> bool {
+        word.r1 >= word.len() && ends_with_short_syllable(&word.chars, word.vowels)
+    }
+}
+
+/// Marks the initial "y", and every "y" after a vowel, as a consonant "Y".
+fn mark_consonant_y(word: &str) -> String {
+    let mut marked = word.as_bytes().to_vec();
+
+    for index in 0..marked.len() {
+        let after_vowel = index > 0 && ENGLISH_VOWELS.contains(&marked[index - 1]);
+        if marked[index] == b'y' && (index == 0 || after_vowel) {
+            marked[index] = b'Y';
+        }
+    }
+
+    String::from_utf8(marked).expect("word is not valid UTF-8")
+}
+
+/// Restores the "y"s marked by [`mark_consonant_y`], keeping the "Y"s of
+/// the word. Steps only rewrite the end of the word, so marks keep their
+/// index.
+fn unmark_consonant_y(stem: String, word: &str) -> String {
+    let mut chars = stem.into_bytes();
+
+    for (char, &original) in chars.iter_mut().zip(word.as_bytes()) {
+        if *char == b'Y' && original == b'y' {
+            *char = b'y';
+        }
+    }
+
+    String::from_utf8(chars).expect("word is not valid UTF-8")
+}
+
+fn ends_with_double(chars: &[u8]) -> bool {
+    const DOUBLES: [&[u8]; 9] = [
+        b"bb", b"dd", b"ff", b"gg", b"mm", b"nn", b"pp", b"rr", b"tt",
+    ];
+    DOUBLES.iter().any(|double| chars.ends_with(double))
+}
+
+/// A vowel and a consonant other than "w", "x" and "Y" after a consonant,
+/// or a vowel and a consonant starting the word.
+fn ends_with_short_syllable(chars: &[u8], vowels: &[u8]) -> bool {
+    let is_vowel = |char: &u8| vowels.contains(char);
+
+    match chars {
+        [first, second] => is_vowel(first) && !is_vowel(second),
+        [.., first, second, third] => {
+            !is_vowel(first)
+                && is_vowel(second)
+                && !is_vowel(third)
+                && !matches!(third, b'w' | b'x' | b'Y')
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        normalizer::{
+            stemmer::{Algorithm, English, Language, Stemmer},
+            TextNormalizer,
+        },
+        tokenizer::{Standard, TextTokenizer},
+        tokens,
+    };
+
+    #[test]
+    fn test_stemmer_english() {
+        let words = [
+            ("jumps", "jump"),
+            ("jumping", "jump"),
+            ("jumped", "jump"),
+            ("foxes", "fox"),
+            ("dogs", "dog"),
+            ("lazy", "lazi"),
+            ("quickly", "quick"),
+            ("caresses", "caress"),
+            ("cries", "cri"),
+            ("ties", "tie"),
+            ("gas", "gas"),
+            ("gaps", "gap"),
+            ("kiwis", "kiwi"),
+            ("hopping", "hop"),
+            ("hoped", "hope"),
+            ("filing", "file"),
+            ("luxuriating", "luxuri"),
+            ("consignment", "consign"),
+            ("consistently", "consist"),
+            ("knackeries", "knackeri"),
+            ("generously", "generous"),
+            ("generation", "generat"),
+            ("happiness", "happi"),
+            ("relational", "relat"),
+            ("sensibility", "sensibl"),
+            ("controlling", "control"),
+            ("toy's", "toy"),
+            ("yelling", "yell"),
+            ("sayings", "say"),
+            ("YES", "YES"),
+            ("skies", "sky"),
+            ("news", "news"),
+            ("exceeds", "exceed"),
+            ("agreed", "agre"),
+            ("feed", "feed"),
+            ("by", "by"),
+            ("café", "café"),
+        ];
+
+        for (word, stem) in words {
+            assert_eq!(English.stem(word), stem, "stem of {word}");
+        }
+    }
+
+    #[test]
+    fn test_normalizer_stemmer() {
+        let mut tokens = Standard::new().tokenize("foxes jumping over dogs");
+        let mut normalizer = Stemmer::new(Language::English);
+
+        normalizer.normalize(&mut tokens);
+
+        assert_eq!(tokens, tokens!["fox", "jump", "over", "dog"]);
+        let token = tokens.iter().nth(1).unwrap();
+        assert_eq!((token.offsets(), token.position()), (6..13, 1));
+    }
+}
@@ -8,7 +8,7 @@ use std::{
 
 use crate::{
     error::{ConfigError, Error},
-    normalizer::TextNormalizer,
+    normalizer::{case::lowercase, TextNormalizer},
     token::Tokens,
 };
 
@@ -43,7 +43,7 @@ impl Stopwords {
                     stopwords.push(line);
                 }
                 Err(error) => {
-                    eprintln!("Error reading the line: {}", error.to_string());
+                    eprintln!("Error reading the line: {}", error);
                     return Err(Error::from(ConfigError::Reader(
                         std::io::ErrorKind::InvalidInput,
                     )));
@@ -64,9 +64,8 @@ impl TextNormalizer for Stopwords {
     fn normalize(&mut self, tokens: &mut Tokens) {
         let stopwords = self.0.read().unwrap();
         tokens.retain_mut(|token| {
-            let token = token.as_mut();
-            token.make_ascii_lowercase();
-            !stopwords.contains(token)
+            lowercase(token.inner_mut());
+            !stopwords.contains(token.as_str())
         })
     }
 }
@@ -75,6 +74,7 @@ impl TextNormalizer for Stopwords {
 mod tests {
     use crate::{
         normalizer::{Stopwords, TextNormalizer},
+        tokenizer::{Standard, TextTokenizer},
         tokens,
     };
 
@@ -126,6 +126,16 @@ mod tests {
         assert_eq!(tokens, tokens!["école"]);
     }
 
+    #[test]
+    fn test_normalizer_stopwords_unicode_case() {
+        let mut tokens = tokens!["NAÏVE", "Élève", "École"];
+        let mut normalizer = Stopwords::new(["naïve", "élève"]);
+
+        normalizer.normalize(&mut tokens);
+
+        assert_eq!(tokens, tokens!["école"]);
+    }
+
     #[test]
     fn test_normalizer_stopwords_dynamic() {
         let mut stopwords = vec![
@@ -152,4 +162,19 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_normalizer_stopwords_keeps_offsets() {
+        let mut tokens = Standard::new().tokenize("The cat in the hat");
+        let mut normalizer = Stopwords::new(["the", "in"]);
+
+        normalizer.normalize(&mut tokens);
+
+        // Removed tokens leave gaps in the positions of the remaining ones.
+        let tokens = tokens
+            .iter()
+            .map(|token| (token.as_str(), token.offsets(), token.position()))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, [("cat", 4..7, 1), ("hat", 15..18, 4)]);
+    }
 }
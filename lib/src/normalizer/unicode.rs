@@ -0,0 +1,117 @@
+//! Unicode normalization forms and diacritic folding.
+//!
+//! The same text can be written with precomposed characters, "é", or with
+//! a base character and combining marks, "e\u{301}". Normalizing to a form
+//! indexes both the same way.
+
+use unicode_normalization::{char::is_combining_mark, is_nfc, is_nfkc, UnicodeNormalization};
+
+use crate::{normalizer::TextNormalizer, token::Tokens};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Form {
+    /// Canonical composition, "e\u{301}" becomes "é".
+    #[default]
+    Nfc,
+
+    /// Compatibility composition, also folding compatibility characters,
+    /// "ﬁ" becomes "fi" and "Ｆ" becomes "F".
+    Nfkc,
+}
+
+/// Normalizes the tokens to the Unicode form.
+#[derive(Clone, Debug, Default)]
+pub struct UnicodeNormalizer {
+    form: Form,
+}
+
+impl UnicodeNormalizer {
+    pub fn new(form: Form) -> Self {
+        Self { form }
+    }
+
+    #[inline]
+    pub fn form(&self) -> Form {
+        self.form
+    }
+}
+
+impl TextNormalizer for UnicodeNormalizer {
+    fn normalize(&mut self, tokens: &mut Tokens) {
+        tokens.iter_mut().for_each(|token| match self.form {
+            Form::Nfc if !is_nfc(token) => *token.inner_mut() = token.nfc().collect(),
+            Form::Nfkc if !is_nfkc(token) => *token.inner_mut() = token.nfkc().collect(),
+            _ => {}
+        })
+    }
+}
+
+/// Removes the diacritics, "café" becomes "cafe" and "Ångström" becomes
+/// "Angstrom".
+///
+/// Characters are decomposed, their combining marks removed, then
+/// recomposed. Letters without a decomposition, e.g. "ø" or "ł", are kept.
+#[derive(Clone, Debug, Default)]
+pub struct Diacritics;
+
+impl Diacritics {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TextNormalizer for Diacritics {
+    fn normalize(&mut self, tokens: &mut Tokens) {
+        tokens.iter_mut().for_each(|token| {
+            if !token.is_ascii() {
+                *token.inner_mut() = token
+                    .nfd()
+                    .filter(|&char| !is_combining_mark(char))
+                    .nfc()
+                    .collect();
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        normalizer::{
+            unicode::{Diacritics, Form, UnicodeNormalizer},
+            TextNormalizer,
+        },
+        tokenizer::{TextTokenizer, Whitespace},
+        tokens,
+    };
+
+    #[test]
+    fn test_normalizer_nfc() {
+        let mut tokens = tokens!["caf\u{e9}", "cafe\u{301}", "ﬁle"];
+        UnicodeNormalizer::new(Form::Nfc).normalize(&mut tokens);
+
+        // Compatibility characters are kept.
+        assert_eq!(tokens, tokens!["café", "café", "ﬁle"]);
+        assert_eq!(tokens.iter().nth(1).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_normalizer_nfkc() {
+        let mut tokens = tokens!["cafe\u{301}", "ﬁle", "Ｆｏｘ", "x²"];
+        UnicodeNormalizer::new(Form::Nfkc).normalize(&mut tokens);
+        assert_eq!(tokens, tokens!["café", "file", "Fox", "x2"]);
+    }
+
+    #[test]
+    fn test_normalizer_diacritics() {
+        let mut tokens = Whitespace::new().tokenize("café Ångström naïve cafe\u{301} ø fox");
+        Diacritics::new().normalize(&mut tokens);
+
+        assert_eq!(
+            tokens,
+            tokens!["cafe", "Angstrom", "naive", "cafe", "ø", "fox"]
+        );
+        let token = tokens.iter().nth(1).unwrap();
+        assert_eq!((token.offsets(), token.position()), (6..16, 1));
+    }
+}
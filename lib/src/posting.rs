@@ -0,0 +1,725 @@
+//! Compressed posting lists.
+//!
+//! Postings are sorted by resource index and stored in blocks of
+//! [`BLOCK_SIZE`] postings. Within a block, each posting is encoded as the
+//! variable-byte delta from the previous index followed by the variable-byte
+//! frequency. A skip entry per block stores the last index of the block and
+//! its byte offset, so a [`PostingCursor`] can skip whole blocks. It also
+//! stores the highest frequency of the block, see [`PostingList::block_max`].
+//!
+//! Lists of an index recording positions also store, after the frequency of
+//! each posting, the byte length of its positions followed by the
+//! variable-byte deltas of the positions. Cursors skip over them unless
+//! [`PostingCursor::positions`] is called. Lists without positions store
+//! nothing more per posting.
+//!
+//! The last posting is kept decoded, since its frequency keeps changing
+//! while the terms of the latest resource are inserted.
+
+use std::{cmp::Ordering, ops::Range};
+
+use crate::core::{RefEntry, TfEntry};
+
+/// Number of postings per block.
+pub const BLOCK_SIZE: usize = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Skip {
+    /// Last resource index in the block.
+    last: usize,
+
+    /// Byte offset of the block.
+    offset: usize,
+
+    /// Highest frequency in the block.
+    max_frequency: usize,
+}
+
+/// Last index and highest frequency of a block of postings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockMax {
+    pub last: usize,
+    pub max_frequency: usize,
+}
+
+/// Postings of a term, sorted by resource index.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PostingList {
+    data: Vec<u8>,
+    skips: Vec<Skip>,
+    last: Option<TfEntry>,
+
+    /// Positions of the last posting, kept decoded with it.
+    last_positions: Vec<u32>,
+    len: usize,
+
+    /// Whether the postings carry the positions of the term.
+    positions: bool,
+}
+
+impl PostingList {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty list whose postings carry the positions of the term.
+    #[inline]
+    pub fn with_positions() -> Self {
+        Self {
+            positions: true,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn has_positions(&self) -> bool {
+        self.positions
+    }
+
+    /// Carries the positions of the postings added from now on, the
+    /// postings already in the list have none.
+    pub fn enable_positions(&mut self) {
+        if !self.positions {
+            *self = Self::from_entries(true, self.entries());
+        }
+    }
+
+    /// Builds a list from postings sorted by index, without duplicates.
+    pub fn from_sorted(entries: impl IntoIterator<Item = TfEntry>) -> Self {
+        let mut list = Self::new();
+        entries.into_iter().for_each(|entry| list.insert(entry));
+        list
+    }
+
+    /// Number of postings.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Heap memory used by the list, in bytes.
+    #[inline]
+    pub fn size_in_bytes(&self) -> usize {
+        self.data.capacity()
+            + self.skips.capacity() * std::mem::size_of::<Skip>()
+            + self.last_positions.capacity() * std::mem::size_of::<u32>()
+    }
+
+    /// Highest frequency of the postings, zero for an empty list.
+    pub fn max_frequency(&self) -> usize {
+        self.skips
+            .iter()
+            .map(|skip| skip.max_frequency)
+            .chain(self.last.map(|last| *last.get_frequency()))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Bounds of the block holding the first posting with an index greater
+    /// than or equal to `target`, without decoding it.
+    ///
+    /// The last posting, kept decoded, is a block of its own.
+    pub fn block_max(&self, target: usize) -> Option<BlockMax> {
+        let block = self.skips.partition_point(|skip| skip.last < target);
+
+        match self.skips.get(block) {
+            Some(skip) => Some(BlockMax {
+                last: skip.last,
+                max_frequency: skip.max_frequency,
+            }),
+            None => self
+                .last
+                .filter(|last| last.get_index() >= target)
+                .map(|last| BlockMax {
+                    last: last.get_index(),
+                    max_frequency: *last.get_frequency(),
+                }),
+        }
+    }
+
+    /// Adds a posting.
+    ///
+    /// If a posting with the same index exists, its frequency is incremented.
+    /// Appending is cheap, inserting before the last posting re-encodes the list.
+    #[inline]
+    pub fn insert(&mut self, entry: TfEntry) {
+        self.add(entry, &[], 1);
+    }
+
+    /// Adds an occurrence of the term at `position` in the resource.
+    ///
+    /// The position is only recorded if the list has positions.
+    #[inline]
+    pub fn insert_at(&mut self, index: usize, position: u32) {
+        self.add(TfEntry::new(index, 1), &[position], 1);
+    }
+
+    /// Adds a whole posting with its positions, e.g. when merging lists.
+    ///
+    /// If a posting with the same index exists, the frequencies are summed.
+    #[inline]
+    pub fn push(&mut self, entry: TfEntry, positions: &[u32]) {
+        self.add(entry, positions, *entry.get_frequency());
+    }
+
+    /// Keeps the postings matching `f`.
+    pub fn retain(&mut self, mut f: impl FnMut(&RefEntry) -> bool) {
+        let entries = self
+            .entries()
+            .into_iter()
+            .filter(|(entry, _)| f(&RefEntry::new(*entry)))
+            .collect::<Vec<_>>();
+
+        if entries.len() != self.len {
+            *self = Self::from_entries(self.positions, entries);
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool {
+        ListCursor::new(self)
+            .advance_to(index)
+            .is_some_and(|entry| entry.get_index() == index)
+    }
+
+    #[inline]
+    pub fn cursor(&self) -> PostingCursor<'_> {
+        PostingCursor::new(vec![self])
+    }
+
+    /// Appends the postings of `other`, all with greater indices.
+    pub fn append(&mut self, other: &PostingList) {
+        if other.positions {
+            self.enable_positions();
+        }
+
+        let mut cursor = ListCursor::new(other);
+        while let Some(entry) = cursor.next() {
+            self.push(entry.tf_entry(), &cursor.positions());
+        }
+    }
+
+    /// Adds the posting, or `increment` to the frequency of the posting
+    /// with the same index.
+    fn add(&mut self, entry: TfEntry, positions: &[u32], increment: usize) {
+        let positions = if self.positions { positions } else { &[] };
+
+        let Some(last) = self.last.as_mut() else {
+            self.last = Some(entry);
+            self.last_positions = positions.to_vec();
+            self.len += 1;
+            return;
+        };
+
+        match last.get_index().cmp(&entry.get_index()) {
+            Ordering::Equal => {
+                last.add_frequency(increment);
+                merge_positions(&mut self.last_positions, positions);
+            }
+            Ordering::Less => {
+                let last = std::mem::replace(last, entry);
+                let last_positions =
+                    std::mem::replace(&mut self.last_positions, positions.to_vec());
+                self.encode(last, &last_positions);
+                self.len += 1;
+            }
+            Ordering::Greater => self.insert_unordered(entry, positions, increment),
+        }
+    }
+
+    fn insert_unordered(&mut self, entry: TfEntry, positions: &[u32], increment: usize) {
+        let mut entries = self.entries();
+
+        match entries.binary_search_by_key(&entry.get_index(), |(entry, _)| entry.get_index()) {
+            Ok(position) => {
+                let (existing, existing_positions) = &mut entries[position];
+                existing.add_frequency(increment);
+                merge_positions(existing_positions, positions);
+            }
+            Err(position) => entries.insert(position, (entry, positions.to_vec())),
+        }
+
+        *self = Self::from_entries(self.positions, entries);
+    }
+
+    /// Decoded postings, with their positions.
+    fn entries(&self) -> Vec<(TfEntry, Vec<u32>)> {
+        let mut entries = Vec::with_capacity(self.len);
+        let mut cursor = ListCursor::new(self);
+
+        while let Some(entry) = cursor.next() {
+            entries.push((entry.tf_entry(), cursor.positions()));
+        }
+
+        entries
+    }
+
+    fn from_entries(positions: bool, entries: Vec<(TfEntry, Vec<u32>)>) -> Self {
+        let mut list = Self {
+            positions,
+            ..Self::default()
+        };
+        entries
+            .into_iter()
+            .for_each(|(entry, positions)| list.push(entry, &positions));
+        list
+    }
+
+    fn encode(&mut self, entry: TfEntry, positions: &[u32]) {
+        let encoded = self.len - 1;
+        let index = entry.get_index();
+        let frequency = *entry.get_frequency();
+
+        let base = match encoded % BLOCK_SIZE {
+            0 => {
+                let base = self.skips.last().map_or(0, |skip| skip.last);
+                self.skips.push(Skip {
+                    last: index,
+                    offset: self.data.len(),
+                    max_frequency: frequency,
+                });
+                base
+            }
+            _ => {
+                let skip = self.skips.last_mut().unwrap();
+                skip.max_frequency = skip.max_frequency.max(frequency);
+                std::mem::replace(&mut skip.last, index)
+            }
+        };
+
+        write_varint(&mut self.data, index - base);
+        write_varint(&mut self.data, frequency);
+
+        if self.positions {
+            let mut deltas = Vec::new();
+            let mut previous = 0;
+            for &position in positions {
+                write_varint(&mut deltas, (position - previous) as usize);
+                previous = position;
+            }
+
+            write_varint(&mut self.data, deltas.len());
+            self.data.extend_from_slice(&deltas);
+        }
+    }
+
+    /// Number of encoded postings in the block.
+    #[inline]
+    fn block_len(&self, block: usize) -> usize {
+        let encoded = self.len - self.last.is_some() as usize;
+        (encoded - block * BLOCK_SIZE).min(BLOCK_SIZE)
+    }
+
+    /// Index the deltas of the block are relative to.
+    #[inline]
+    fn block_base(&self, block: usize) -> usize {
+        match block {
+            0 => 0,
+            block => self.skips[block - 1].last,
+        }
+    }
+}
+
+/// Forward-only cursor over postings, in increasing index order.
+///
+/// Chains the lists of a term in several layers of the index, whose
+/// postings follow each other, see [`crate::layer`].
+#[derive(Clone, Debug)]
+pub struct PostingCursor<'a> {
+    lists: Vec<&'a PostingList>,
+
+    /// List the cursor is in.
+    list: usize,
+
+    cursor: Option<ListCursor<'a>>,
+}
+
+impl<'a> PostingCursor<'a> {
+    /// Cursor over the lists, the indices of each list greater than
+    /// those of the previous ones.
+    pub fn new(lists: Vec<&'a PostingList>) -> Self {
+        let cursor = lists.first().copied().map(ListCursor::new);
+        Self {
+            lists,
+            list: 0,
+            cursor,
+        }
+    }
+
+    /// Posting returned by the last call to `next` or `advance_to`.
+    #[inline]
+    pub fn current(&self) -> Option<RefEntry> {
+        self.cursor.as_ref()?.current()
+    }
+
+    /// Positions of the term in the current posting, in increasing order.
+    ///
+    /// Empty if the list has no positions, or the cursor is not on a posting.
+    #[inline]
+    pub fn positions(&self) -> Vec<u32> {
+        self.cursor
+            .as_ref()
+            .map(ListCursor::positions)
+            .unwrap_or_default()
+    }
+
+    /// Moves to the first posting with an index greater than or equal
+    /// to `target`, skipping whole blocks where possible.
+    ///
+    /// The cursor never moves backwards, if the current posting is
+    /// already past `target`, it is returned again.
+    pub fn advance_to(&mut self, target: usize) -> Option<RefEntry> {
+        loop {
+            if let Some(entry) = self.cursor.as_mut()?.advance_to(target) {
+                return Some(entry);
+            }
+            self.next_list()?;
+        }
+    }
+
+    /// Moves to the start of the next list, `None` after the last one.
+    fn next_list(&mut self) -> Option<()> {
+        let list = self.lists.get(self.list + 1)?;
+        self.cursor = Some(ListCursor::new(list));
+        self.list += 1;
+        Some(())
+    }
+}
+
+impl Iterator for PostingCursor<'_> {
+    type Item = RefEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.cursor.as_mut()?.next() {
+                return Some(entry);
+            }
+            self.next_list()?;
+        }
+    }
+}
+
+/// Forward-only cursor over a single [`PostingList`].
+#[derive(Clone, Debug)]
+struct ListCursor<'a> {
+    list: &'a PostingList,
+
+    /// Next block to decode.
+    block: usize,
+
+    /// Postings left in the current block.
+    remaining: usize,
+
+    offset: usize,
+    index: usize,
+
+    /// Whether the decoded last posting was returned.
+    exhausted: bool,
+
+    /// Posting the cursor is positioned on.
+    current: Option<RefEntry>,
+
+    /// Encoded positions of the current posting, `None` for the last one.
+    positions: Option<Range<usize>>,
+}
+
+impl<'a> ListCursor<'a> {
+    fn new(list: &'a PostingList) -> Self {
+        Self {
+            list,
+            block: 0,
+            remaining: 0,
+            offset: 0,
+            index: 0,
+            exhausted: false,
+            current: None,
+            positions: None,
+        }
+    }
+
+    #[inline]
+    fn current(&self) -> Option<RefEntry> {
+        self.current
+    }
+
+    fn positions(&self) -> Vec<u32> {
+        if self.current.is_none() {
+            return Vec::new();
+        }
+
+        let Some(range) = self.positions.clone() else {
+            return self.list.last_positions.clone();
+        };
+
+        let data = &self.list.data[..range.end];
+        let mut offset = range.start;
+        let mut positions = Vec::new();
+        let mut position = 0;
+
+        while offset < range.end {
+            position += read_varint(data, &mut offset) as u32;
+            positions.push(position);
+        }
+
+        positions
+    }
+
+    fn advance_to(&mut self, target: usize) -> Option<RefEntry> {
+        if let Some(current) = self.current.filter(|entry| entry.get_index() >= target) {
+            return Some(current);
+        }
+
+        let skips = &self.list.skips;
+
+        // The rest of the current block is before the target.
+        if self.remaining > 0 && skips[self.block - 1].last < target {
+            self.remaining = 0;
+        }
+
+        if self.remaining == 0 {
+            self.block += skips[self.block..].partition_point(|skip| skip.last < target);
+        }
+
+        self.find(|entry| entry.get_index() >= target)
+    }
+
+    fn decode(&mut self) -> Option<RefEntry> {
+        if self.remaining == 0 {
+            if self.block >= self.list.skips.len() {
+                if self.exhausted {
+                    return None;
+                }
+
+                self.exhausted = true;
+                self.positions = None;
+                return self.list.last.map(RefEntry::new);
+            }
+
+            self.enter(self.block);
+        }
+
+        let data = &self.list.data;
+        self.index += read_varint(data, &mut self.offset);
+        let frequency = read_varint(data, &mut self.offset);
+        self.remaining -= 1;
+
+        if self.list.positions {
+            let len = read_varint(data, &mut self.offset);
+            self.positions = Some(self.offset..self.offset + len);
+            self.offset += len;
+        }
+
+        Some(RefEntry::new(TfEntry::new(self.index, frequency)))
+    }
+
+    fn enter(&mut self, block: usize) {
+        self.offset = self.list.skips[block].offset;
+        self.index = self.list.block_base(block);
+        self.remaining = self.list.block_len(block);
+        self.block = block + 1;
+    }
+}
+
+impl Iterator for ListCursor<'_> {
+    type Item = RefEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.current = self.decode();
+        self.current
+    }
+}
+
+/// Adds the positions to the sorted positions.
+fn merge_positions(into: &mut Vec<u32>, positions: &[u32]) {
+    into.extend_from_slice(positions);
+    if !into.is_sorted() {
+        into.sort_unstable();
+    }
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_varint(buffer: &[u8], offset: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = buffer[*offset];
+        *offset += 1;
+
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte < 0x80 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::TfEntry,
+        posting::{BlockMax, PostingList, BLOCK_SIZE},
+    };
+
+    fn postings(list: &PostingList) -> Vec<(usize, usize)> {
+        list.cursor()
+            .map(|entry| (entry.get_index(), *entry.get_frequency()))
+            .collect()
+    }
+
+    fn sample(len: usize) -> PostingList {
+        PostingList::from_sorted((0..len).map(|index| TfEntry::new(index * 3, index % 5 + 1)))
+    }
+
+    #[test]
+    fn test_posting_list_insert() {
+        let mut list = PostingList::new();
+        list.insert(TfEntry::new(4, 1));
+        list.insert(TfEntry::new(4, 1));
+        list.insert(TfEntry::new(900, 1));
+        list.insert(TfEntry::new(1, 7));
+        list.insert(TfEntry::new(900, 1));
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(postings(&list), [(1, 7), (4, 2), (900, 2)]);
+    }
+
+    #[test]
+    fn test_posting_list_blocks() {
+        let len = BLOCK_SIZE * 3 + 17;
+        let list = sample(len);
+
+        let expected = (0..len)
+            .map(|index| (index * 3, index % 5 + 1))
+            .collect::<Vec<_>>();
+        assert_eq!(list.len(), len);
+        assert_eq!(postings(&list), expected);
+        assert!(list.size_in_bytes() < len * std::mem::size_of::<TfEntry>());
+    }
+
+    #[test]
+    fn test_posting_cursor_advance_to() {
+        let len = BLOCK_SIZE * 4;
+        let list = sample(len);
+        let mut cursor = list.cursor();
+
+        assert_eq!(cursor.advance_to(0).unwrap().get_index(), 0);
+        assert_eq!(cursor.advance_to(1).unwrap().get_index(), 3);
+        assert_eq!(cursor.advance_to(2).unwrap().get_index(), 3);
+        assert_eq!(cursor.current().unwrap().get_index(), 3);
+        assert_eq!(cursor.next().unwrap().get_index(), 6);
+
+        // Skips blocks, then lands between postings.
+        assert_eq!(cursor.advance_to(3 * 300 + 1).unwrap().get_index(), 3 * 301);
+        assert_eq!(cursor.next().unwrap().get_index(), 3 * 302);
+
+        // The last posting is kept decoded.
+        let last = 3 * (len - 1);
+        assert_eq!(cursor.advance_to(last).unwrap().get_index(), last);
+        assert!(cursor.advance_to(last + 1).is_none());
+        assert!(cursor.next().is_none());
+
+        assert!(list.contains(3 * 200));
+        assert!(!list.contains(3 * 200 + 1));
+        assert!(!PostingList::new().contains(0));
+    }
+
+    #[test]
+    fn test_posting_list_block_max() {
+        let len = BLOCK_SIZE * 2 + 1;
+        let mut list = sample(len);
+        list.insert(TfEntry::new(3 * len, 9));
+
+        assert_eq!(list.max_frequency(), 9);
+        assert_eq!(
+            list.block_max(0),
+            Some(BlockMax {
+                last: 3 * (BLOCK_SIZE - 1),
+                max_frequency: 5,
+            })
+        );
+        assert_eq!(
+            list.block_max(3 * BLOCK_SIZE - 2).unwrap().last,
+            3 * (BLOCK_SIZE * 2 - 1)
+        );
+
+        // The last posting, kept decoded, is its own block.
+        assert_eq!(
+            list.block_max(3 * len),
+            Some(BlockMax {
+                last: 3 * len,
+                max_frequency: 9,
+            })
+        );
+        assert!(list.block_max(3 * len + 1).is_none());
+        assert_eq!(PostingList::new().max_frequency(), 0);
+    }
+
+    #[test]
+    fn test_posting_list_retain() {
+        let mut list = sample(BLOCK_SIZE * 2);
+        list.retain(|entry| entry.get_index() % 2 == 0);
+
+        assert_eq!(list.len(), BLOCK_SIZE);
+        assert!(list.cursor().all(|entry| entry.get_index() % 6 == 0));
+
+        // Re-encoded as if built from the remaining postings.
+        let expected = (0..BLOCK_SIZE).map(|index| TfEntry::new(index * 6, index * 2 % 5 + 1));
+        assert_eq!(list, PostingList::from_sorted(expected));
+    }
+
+    #[test]
+    fn test_posting_list_positions() {
+        let mut list = PostingList::with_positions();
+        for index in 0..BLOCK_SIZE + 2 {
+            list.insert_at(index, 1);
+            list.insert_at(index, index as u32 + 2);
+        }
+        // Out of order, the list is re-encoded around it.
+        list.insert_at(3, 0);
+
+        let mut cursor = list.cursor();
+        assert_eq!(
+            cursor.advance_to(3).map(|entry| *entry.get_frequency()),
+            Some(3)
+        );
+        assert_eq!(cursor.positions(), [0, 1, 5]);
+        cursor.advance_to(BLOCK_SIZE + 1);
+        assert_eq!(cursor.positions(), [1, BLOCK_SIZE as u32 + 3]);
+
+        // Positions survive retaining and merging postings.
+        list.retain(|entry| entry.get_index() != 0);
+        list.push(TfEntry::new(BLOCK_SIZE + 1, 1), &[0]);
+        let mut cursor = list.cursor();
+        assert_eq!(cursor.advance_to(1).map(|entry| entry.get_index()), Some(1));
+        assert_eq!(cursor.positions(), [1, 3]);
+        cursor.advance_to(BLOCK_SIZE + 1);
+        assert_eq!(cursor.positions(), [0, 1, BLOCK_SIZE as u32 + 3]);
+
+        // Without positions, none are recorded.
+        let mut list = PostingList::new();
+        list.insert_at(0, 4);
+        let mut cursor = list.cursor();
+        assert!(cursor.next().is_some());
+        assert_eq!(cursor.positions(), Vec::<u32>::new());
+
+        list.enable_positions();
+        list.insert_at(0, 5);
+        let mut cursor = list.cursor();
+        assert_eq!(cursor.next().map(|entry| *entry.get_frequency()), Some(2));
+        assert_eq!(cursor.positions(), [5]);
+    }
+}
@@ -0,0 +1,856 @@
+//! Evaluation of query expressions against the inverted index.
+//!
+//! Within `And` and `Or` clauses, [`Expr::Required`] clauses must match and
+//! [`Expr::Not`] clauses must not match. When an `Or` has required clauses,
+//! its other clauses only contribute to the score. A clause made only of
+//! excluded terms matches nothing.
+//!
+//! A [`Expr::Wildcard`] term is expanded to the matching indexed terms,
+//! see [`Dictionary::wildcard`](crate::dictionary::Dictionary::wildcard),
+//! scored like their disjunction.
+//!
+//! Expansions are capped per term, see [`Scorer::with_max_expansions`],
+//! keeping the first terms in order for wildcards and the closest ones for
+//! fuzzy terms.
+//!
+//! A [`Expr::Fuzzy`] term is expanded to the indexed terms within its edit
+//! distance, see [`Dictionary::fuzzy`](crate::dictionary::Dictionary::fuzzy).
+//! A term `d` edits away scores `1 / (1 + d)` of its own score, and a
+//! resource containing several expanded terms keeps its best score.
+//!
+//! A [`Expr::Phrase`] is read from the term positions, see
+//! [`ReaderContext::positions`]. Each occurrence of the phrase counts
+//! `1 / (1 + distance)`, where the distance is the number of positions
+//! between its terms beyond their gaps in the query, e.g. left by dropped
+//! stopwords, and the summed frequency `f` boosts the score of the
+//! terms by `1 + f / (1 + f)`. Without positions in the index, a phrase
+//! matches like the conjunction of its terms.
+//!
+//! A [`Expr::Field`] restricts its terms to the field, other terms are
+//! searched in every field, see [`ReaderContext::fields_of`]. Expansions of
+//! such a term are searched in every field as well, and a phrase matches
+//! when its terms are next to each other within any field.
+//!
+//! [`Scorer::explain`] follows the same rules to explain the score of a
+//! single resource, see [`Explanation`].
+
+use std::{collections::BTreeSet, fmt::Debug, hash::Hash};
+
+use hashbrown::HashMap;
+
+use crate::{
+    distance::LevenshteinAutomaton,
+    explain::Explanation,
+    field,
+    query::parser::{Expr, MAX_DISTANCE},
+    reader::ReaderContext,
+    score::{Score, Scorer},
+};
+
+/// Number of indexed terms a fuzzy or wildcard term expands to, by default.
+pub const DEFAULT_MAX_EXPANSIONS: usize = 128;
+
+/// Score of every matching resource.
+pub type Matches = HashMap<usize, f32>;
+
+impl<'a, S> Scorer<'a, S>
+where
+    S: Score<'a, Key = usize, Value = f32>,
+{
+    /// Evaluates the expression, scoring the matched terms of every
+    /// matching resource with the underlying [`Score`] implementation.
+    pub fn evaluate(&self, expr: &Expr) -> Matches {
+        match expr {
+            Expr::Term(term) => self
+                .score(term)
+                .map(|scores| scores.into_iter().collect())
+                .unwrap_or_default(),
+            Expr::Fuzzy { term, distance } => self.fuzzy(term, *distance),
+            Expr::Wildcard(pattern) => self.wildcard(pattern),
+            Expr::Phrase {
+                terms,
+                positions,
+                slop,
+            } => self.phrase(terms, positions, *slop),
+            Expr::And(clauses) => self.boolean(clauses, true),
+            Expr::Or(clauses) => self.boolean(clauses, false),
+            Expr::Required(expr) => self.evaluate(expr),
+            Expr::Not(_) => Matches::new(),
+            Expr::Field(name, expr) => self.evaluate(&expr.as_ref().clone().qualify(name)),
+        }
+    }
+
+    /// Explains the score of the resource for the expression, `None` if
+    /// the resource does not match, see [`Score::explain`] for the terms.
+    pub fn explain(&self, expr: &Expr, index: usize) -> Option<Explanation> {
+        match expr {
+            Expr::Term(term) => self.explain_term(term, index),
+            Expr::Fuzzy { term, distance } => self.explain_fuzzy(term, *distance, index),
+            Expr::Wildcard(pattern) => {
+                let details = self
+                    .wildcard_terms(pattern)
+                    .into_iter()
+                    .filter_map(|term| self.explain_term(term, index))
+                    .collect::<Vec<_>>();
+
+                (!details.is_empty()).then(|| {
+                    Explanation::sum(
+                        format!("{}, sum of expansions:", field::display(pattern)),
+                        details,
+                    )
+                })
+            }
+            Expr::Phrase {
+                terms,
+                positions,
+                slop,
+            } => self.explain_phrase(terms, positions, *slop, index),
+            Expr::And(clauses) => self.explain_boolean(clauses, true, index),
+            Expr::Or(clauses) => self.explain_boolean(clauses, false, index),
+            Expr::Required(expr) => self.explain(expr, index),
+            Expr::Not(_) => None,
+            Expr::Field(name, expr) => self.explain(&expr.as_ref().clone().qualify(name), index),
+        }
+    }
+
+    fn explain_fuzzy(&self, key: &str, distance: u32, index: usize) -> Option<Explanation> {
+        let details = self
+            .fuzzy_terms(key, distance)
+            .into_iter()
+            .filter_map(|(term, distance)| {
+                let explanation = self.explain_term(term, index)?;
+                let weight = fuzzy_weight(distance);
+
+                let weight = Explanation::new(weight, "weight, 1 / (1 + distance) of:")
+                    .with_details(vec![Explanation::count(distance, "distance")]);
+                let value = explanation.value * weight.value;
+
+                Some(
+                    Explanation::new(
+                        value,
+                        format!("{}, weight * score of:", field::display(term)),
+                    )
+                    .with_details(vec![weight, explanation]),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let best = details.iter().map(|detail| detail.value).reduce(f32::max)?;
+        Some(
+            Explanation::new(best, format!("{}~{distance}, max of:", field::display(key)))
+                .with_details(details),
+        )
+    }
+
+    fn explain_phrase(
+        &self,
+        terms: &[String],
+        positions: &[u32],
+        slop: u32,
+        index: usize,
+    ) -> Option<Explanation> {
+        let details = terms
+            .iter()
+            .map(|term| self.explain_term(term, index))
+            .collect::<Option<Vec<_>>>()?;
+        let phrase = terms
+            .iter()
+            .map(|term| field::display(term))
+            .collect::<Vec<_>>();
+        let phrase = format!("\"{}\"~{slop}", phrase.join(" "));
+
+        if !self.reader().has_positions() {
+            return Some(Explanation::sum(format!("{phrase}, sum of:"), details));
+        }
+
+        let frequency = self.phrase_frequency(terms, positions, index, slop);
+        if frequency <= 0.0 {
+            return None;
+        }
+
+        let terms = Explanation::sum("terms, sum of:", details);
+        let boost = Explanation::new(
+            phrase_boost(frequency),
+            "phrase boost, 1 + frequency / (1 + frequency) of:",
+        )
+        .with_details(vec![Explanation::new(frequency, "phrase frequency")]);
+
+        Some(
+            Explanation::new(
+                terms.value * boost.value,
+                format!("{phrase}, terms * boost of:"),
+            )
+            .with_details(vec![terms, boost]),
+        )
+    }
+
+    fn explain_boolean(
+        &self,
+        clauses: &[Expr],
+        conjunction: bool,
+        index: usize,
+    ) -> Option<Explanation> {
+        let mut details = Vec::new();
+        let mut required = false;
+
+        for clause in clauses {
+            match clause {
+                Expr::Not(expr) => {
+                    if self.explain(expr, index).is_some() {
+                        return None;
+                    }
+                }
+                Expr::Required(expr) => {
+                    details.push(self.explain(expr, index)?);
+                    required = true;
+                }
+                expr if conjunction => {
+                    details.push(self.explain(expr, index)?);
+                    required = true;
+                }
+                expr => details.extend(self.explain(expr, index)),
+            }
+        }
+
+        let description = match (conjunction, required) {
+            (true, _) => "and, sum of:",
+            (false, true) => "or, sum of required and matching optional clauses:",
+            (false, false) => "or, sum of matching clauses:",
+        };
+        (!details.is_empty()).then(|| Explanation::sum(description, details))
+    }
+
+    fn wildcard(&self, pattern: &str) -> Matches {
+        union(
+            self.wildcard_terms(pattern)
+                .into_iter()
+                .map(|term| self.evaluate(&Expr::Term(term.to_string())))
+                .collect(),
+        )
+    }
+
+    /// Terms the pattern expands to, in order.
+    fn wildcard_terms(&self, pattern: &str) -> Vec<&'a str> {
+        let reader = self.reader();
+        let qualified = field::is_qualified(pattern);
+        let mut terms = BTreeSet::new();
+
+        for (field, term) in reader.fields_of(pattern) {
+            let pattern = field::qualify(field.unwrap_or_default(), term);
+
+            let expanded = reader
+                .dictionary()
+                .wildcard(&pattern)
+                .filter(|key| field.is_some() || !field::is_qualified(key))
+                .take(self.max_expansions())
+                .map(|key| unqualify(key, qualified));
+            terms.extend(expanded);
+        }
+
+        terms.into_iter().take(self.max_expansions()).collect()
+    }
+
+    fn fuzzy(&self, key: &str, distance: u32) -> Matches {
+        let mut matches = Matches::new();
+
+        for (term, distance) in self.fuzzy_terms(key, distance) {
+            let weight = fuzzy_weight(distance);
+
+            for (index, score) in self.score(term).unwrap_or_default() {
+                let best = matches.entry(index).or_insert(0.0);
+                *best = best.max(score * weight);
+            }
+        }
+
+        matches
+    }
+
+    /// Terms the key expands to with their distance, closest first.
+    ///
+    /// The distance is capped to [`MAX_DISTANCE`], and each field only
+    /// expands to its closest terms, see [`Dictionary::fuzzy_closest`].
+    ///
+    /// [`Dictionary::fuzzy_closest`]: crate::dictionary::Dictionary::fuzzy_closest
+    fn fuzzy_terms(&self, key: &str, distance: u32) -> Vec<(&'a str, usize)> {
+        let reader = self.reader();
+        let qualified = field::is_qualified(key);
+        let (_, term) = field::split(key);
+        let distance = distance.min(MAX_DISTANCE) as usize;
+        let automaton = LevenshteinAutomaton::new(term, distance).with_transpositions();
+
+        // Closest distance of every expanded term.
+        let mut expanded = HashMap::<&str, usize>::new();
+
+        for (field, _) in reader.fields_of(key) {
+            let prefix = field::qualify(field.unwrap_or_default(), "");
+
+            // Qualified keys belong to named fields, not to the default field.
+            let keep = |key: &str| field.is_some() || !field::is_qualified(key);
+            let closest =
+                reader
+                    .dictionary()
+                    .fuzzy_closest(&prefix, &automaton, self.max_expansions(), keep);
+
+            for (key, distance) in closest {
+                let closest = expanded
+                    .entry(unqualify(key, qualified))
+                    .or_insert(distance);
+                *closest = (*closest).min(distance);
+            }
+        }
+
+        let mut terms = expanded.into_iter().collect::<Vec<_>>();
+        terms.sort_unstable_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+        terms.truncate(self.max_expansions());
+        terms
+    }
+
+    fn phrase(&self, terms: &[String], positions: &[u32], slop: u32) -> Matches {
+        let mut matches = intersection(
+            terms
+                .iter()
+                .map(|term| self.evaluate(&Expr::Term(term.clone())))
+                .collect(),
+        );
+
+        if !self.reader().has_positions() {
+            return matches;
+        }
+
+        matches.retain(|&index, score| {
+            let frequency = self.phrase_frequency(terms, positions, index, slop);
+            *score *= phrase_boost(frequency);
+            frequency > 0.0
+        });
+
+        matches
+    }
+
+    /// Frequency of the phrase in the resource, summed over the fields the
+    /// phrase is searched in.
+    fn phrase_frequency(
+        &self,
+        terms: &[String],
+        positions: &[u32],
+        index: usize,
+        slop: u32,
+    ) -> f32 {
+        let reader = self.reader();
+
+        // The terms of a phrase share their field.
+        reader
+            .fields_of(&terms[0])
+            .into_iter()
+            .map(|(field, _)| {
+                let keys = terms
+                    .iter()
+                    .map(|term| field::qualify(field.unwrap_or_default(), field::split(term).1))
+                    .collect::<Vec<_>>();
+                phrase_frequency(reader, &keys, positions, index, slop)
+            })
+            .sum()
+    }
+
+    fn boolean(&self, clauses: &[Expr], conjunction: bool) -> Matches {
+        let mut must = Vec::new();
+        let mut should = Vec::new();
+        let mut must_not = Vec::new();
+
+        for clause in clauses {
+            match clause {
+                Expr::Not(expr) => must_not.push(self.evaluate(expr)),
+                Expr::Required(expr) => must.push(self.evaluate(expr)),
+                expr if conjunction => must.push(self.evaluate(expr)),
+                expr => should.push(self.evaluate(expr)),
+            }
+        }
+
+        let mut matches = match must.is_empty() {
+            true => union(should),
+            false => {
+                let mut matches = intersection(must);
+                for (index, score) in union(should) {
+                    if let Some(existing) = matches.get_mut(&index) {
+                        *existing += score;
+                    }
+                }
+                matches
+            }
+        };
+
+        for excluded in must_not {
+            matches.retain(|index, _| !excluded.contains_key(index));
+        }
+
+        matches
+    }
+}
+
+/// Weight of a fuzzy expansion `distance` edits away.
+#[inline]
+fn fuzzy_weight(distance: usize) -> f32 {
+    1.0 / (1.0 + distance as f32)
+}
+
+/// Boost of the terms of a phrase found `frequency` times.
+#[inline]
+fn phrase_boost(frequency: f32) -> f32 {
+    1.0 + frequency / (1.0 + frequency)
+}
+
+/// Sum of `1 / (1 + distance)` over the occurrences of the phrase in the
+/// resource, each starting at a different position of the first term.
+///
+/// The distance counts the positions between the terms beyond their
+/// distance in the query, see `query`.
+fn phrase_frequency<R>(
+    reader: &ReaderContext<'_, R>,
+    terms: &[String],
+    query: &[u32],
+    index: usize,
+    slop: u32,
+) -> f32
+where
+    R: Clone + Debug + Hash + Eq,
+{
+    let positions = terms
+        .iter()
+        .map(|term| {
+            reader
+                .positions(term, index)
+                .map(Iterator::collect::<Vec<_>>)
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    let (first, rest) = positions.split_first().expect("phrase without terms");
+
+    first
+        .iter()
+        .filter_map(|&start| {
+            // The closest following position minimizes the distance of the
+            // remaining terms as well.
+            rest.iter()
+                .zip(query.windows(2))
+                .try_fold((start, 0), |(previous, distance), (positions, pair)| {
+                    let gap = pair[1] - pair[0];
+                    let next =
+                        positions[positions.partition_point(|&p| p < previous + gap)..].first()?;
+                    let distance = distance + next - previous - gap;
+                    (distance <= slop).then_some((*next, distance))
+                })
+                .map(|(_, distance)| 1.0 / (1.0 + distance as f32))
+        })
+        .sum()
+}
+
+/// Term of an expanded key, searched in every field unless the expanded
+/// term was restricted to a field.
+fn unqualify(key: &str, qualified: bool) -> &str {
+    match qualified {
+        true => key,
+        false => field::split(key).1,
+    }
+}
+
+fn union(matches: Vec<Matches>) -> Matches {
+    matches
+        .into_iter()
+        .fold(Matches::new(), |mut union, other| {
+            for (index, score) in other {
+                *union.entry(index).or_default() += score;
+            }
+            union
+        })
+}
+
+fn intersection(mut matches: Vec<Matches>) -> Matches {
+    // Start from the smallest set.
+    matches.sort_unstable_by_key(Matches::len);
+    let mut matches = matches.into_iter();
+
+    let Some(mut intersection) = matches.next() else {
+        return Matches::new();
+    };
+
+    for other in matches {
+        intersection.retain(|index, score| match other.get(index) {
+            Some(other) => {
+                *score += other;
+                true
+            }
+            None => false,
+        });
+    }
+
+    intersection
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        facet::Facets,
+        field::FieldTokens,
+        index::{Index, Indexer},
+        normalizer::{Stopwords, TextNormalizer},
+        query::{eval::Matches, Query},
+        score::{Bm25Scorer, Score, Scorer, TfIdfScorer},
+        tokenizer::{Standard, TextTokenizer},
+        tokens,
+    };
+
+    fn test_index() -> Index<String> {
+        let mut index = Index::new(10, 8);
+        let documents = [
+            ("a", tokens!["rust", "async", "runtime"]),
+            ("b", tokens!["rust", "tokio", "java"]),
+            ("c", tokens!["rust", "sync"]),
+            ("d", tokens!["java", "tokio"]),
+            ("e", tokens!["python"]),
+        ];
+
+        for (resource, mut tokens) in documents {
+            let word_count = tokens.count();
+            index
+                .insert(resource.into(), word_count, &mut tokens)
+                .unwrap();
+        }
+        index
+    }
+
+    fn matches(index: &Index<String>, query: &str) -> BTreeSet<String> {
+        let reader = index.reader();
+        let scorer = Scorer::new(TfIdfScorer::new(&reader));
+        let expr = Query::new(query).parse().unwrap();
+
+        scorer
+            .evaluate(&expr)
+            .into_keys()
+            .filter_map(|key| index.get(key))
+            .collect()
+    }
+
+    fn set<const N: usize>(resources: [&str; N]) -> BTreeSet<String> {
+        resources.into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_eval_boolean() {
+        let index = test_index();
+
+        assert_eq!(matches(&index, "rust java"), set(["a", "b", "c", "d"]));
+        assert_eq!(matches(&index, "rust AND java"), set(["b"]));
+        assert_eq!(matches(&index, "rust -java"), set(["a", "c"]));
+        assert_eq!(matches(&index, "rust AND NOT java"), set(["a", "c"]));
+        assert_eq!(matches(&index, "+tokio rust"), set(["b", "d"]));
+        assert_eq!(matches(&index, "-java"), set([]));
+        assert_eq!(matches(&index, "missing"), set([]));
+
+        assert_eq!(
+            matches(&index, "rust AND (async OR tokio) -java"),
+            set(["a"])
+        );
+        assert_eq!(
+            matches(&index, "(rust OR python) AND NOT (tokio OR sync)"),
+            set(["a", "e"])
+        );
+    }
+
+    fn phrase_index() -> Index<String> {
+        let mut index = Index::new(10, 8).with_positions();
+        let documents = [
+            ("a", tokens!["quick", "brown", "fox"]),
+            ("b", tokens!["quick", "fox", "jumps"]),
+            ("c", tokens!["fox", "quick", "brown"]),
+            ("d", tokens!["quick", "brown", "lazy", "dog", "fox"]),
+            ("e", tokens!["quick", "fox", "quick", "fox"]),
+            ("f", tokens!["lazy", "dog"]),
+        ];
+
+        for (resource, mut tokens) in documents {
+            let word_count = tokens.count();
+            index
+                .insert(resource.into(), word_count, &mut tokens)
+                .unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_eval_phrase() {
+        let index = phrase_index();
+
+        assert_eq!(matches(&index, "\"quick brown fox\""), set(["a"]));
+        assert_eq!(matches(&index, "\"quick fox\""), set(["b", "e"]));
+        assert_eq!(matches(&index, "\"fox quick\""), set(["c", "e"]));
+        assert_eq!(matches(&index, "\"quick fox\"~1"), set(["a", "b", "e"]));
+        assert_eq!(
+            matches(&index, "\"quick fox\"~3"),
+            set(["a", "b", "d", "e"])
+        );
+        assert_eq!(matches(&index, "\"quick brown fox\"~2"), set(["a", "d"]));
+        assert_eq!(matches(&index, "\"quick fox\" -jumps"), set(["e"]));
+        assert_eq!(matches(&index, "\"quick missing\"~5"), set([]));
+
+        // Without positions, a phrase matches like a conjunction.
+        let index = test_index();
+        assert_eq!(matches(&index, "\"runtime rust\""), set(["a"]));
+    }
+
+    #[test]
+    fn test_eval_phrase_stopwords() {
+        let mut index = Index::new(10, 8).with_positions();
+        let mut normalizer = Stopwords::new(["in", "the"]);
+        let documents = [
+            ("a", tokens!["cat", "in", "the", "hat"]),
+            ("b", tokens!["cat", "hat"]),
+        ];
+
+        for (resource, mut tokens) in documents {
+            let word_count = tokens.count();
+            normalizer.normalize(&mut tokens);
+            index
+                .insert(resource.into(), word_count, &mut tokens)
+                .unwrap();
+        }
+
+        // Dropped stopwords still count in the distances between terms.
+        assert_eq!(matches(&index, "\"cat hat\""), set(["b"]));
+        assert_eq!(matches(&index, "\"cat hat\"~2"), set(["a", "b"]));
+
+        // Queries analyzed through the same stopwords keep their gaps.
+        let reader = index.reader();
+        let scorer = Scorer::new(TfIdfScorer::new(&reader));
+        let mut analyzed = |query: &str| {
+            let expr = Query::new(query).parse().unwrap().analyze(&mut |text| {
+                let mut tokens = Standard::new().tokenize(text);
+                normalizer.normalize(&mut tokens);
+                tokens
+            });
+            let hits = scorer.evaluate(&expr.unwrap()).into_keys();
+            hits.filter_map(|key| index.get(key))
+                .collect::<BTreeSet<_>>()
+        };
+
+        assert_eq!(analyzed("\"cat in the hat\""), set(["a"]));
+        assert_eq!(analyzed("\"the cat in hat\""), set([]));
+        assert_eq!(analyzed("\"cat in hat\"~1"), set(["a"]));
+        assert_eq!(analyzed("\"cat hat\""), set(["b"]));
+    }
+
+    #[test]
+    fn test_eval_phrase_scores() {
+        let index = phrase_index();
+        let reader = index.reader();
+        let scorer = Scorer::new(TfIdfScorer::new(&reader));
+
+        let evaluate = |query: &str| scorer.evaluate(&Query::new(query).parse().unwrap());
+        let terms = evaluate("quick AND fox");
+        let phrase = evaluate("\"quick fox\"~3");
+
+        // Exact occurrences boost more than sloppy ones, repeated ones more
+        // than single ones.
+        assert_eq!(phrase[&1], terms[&1] * 1.5);
+        assert_eq!(phrase[&0], terms[&0] * (1.0 + 0.5 / 1.5));
+        assert!(phrase[&4] > terms[&4] * 1.5);
+        assert!(phrase[&3] < terms[&3] * (1.0 + 0.5 / 1.5));
+    }
+
+    #[test]
+    fn test_eval_fuzzy() {
+        let mut index = Index::new(10, 8);
+        let documents = [
+            ("a", tokens!["color", "theory"]),
+            ("b", tokens!["colour", "theory"]),
+            ("c", tokens!["colors", "colour"]),
+            ("d", tokens!["cooler", "weather"]),
+        ];
+
+        for (resource, mut tokens) in documents {
+            let word_count = tokens.count();
+            index
+                .insert(resource.into(), word_count, &mut tokens)
+                .unwrap();
+        }
+
+        assert_eq!(matches(&index, "color~0"), set(["a"]));
+        assert_eq!(matches(&index, "color~1"), set(["a", "b", "c"]));
+        assert_eq!(matches(&index, "colro~1"), set(["a"]));
+        assert_eq!(matches(&index, "color~2"), set(["a", "b", "c", "d"]));
+        assert_eq!(matches(&index, "color~1 -theory"), set(["c"]));
+
+        let reader = index.reader();
+        let scorer = Scorer::new(TfIdfScorer::new(&reader));
+        let fuzzy = scorer.evaluate(&Query::new("colour~1").parse().unwrap());
+        let exact = |term| scorer.score(term).unwrap().into_iter().collect::<Matches>();
+
+        // Expanded terms score lower than exact matches, and a resource
+        // keeps the score of its best matching term.
+        assert_eq!(fuzzy[&1], exact("colour")[&1]);
+        assert_eq!(fuzzy[&0], exact("color")[&0] * 0.5);
+        assert_eq!(fuzzy[&2], exact("colour")[&2]);
+    }
+
+    #[test]
+    fn test_eval_wildcard() {
+        let mut index = Index::new(10, 8);
+        let documents = [
+            ("a", tokens!["tokenizer", "rust"]),
+            ("b", tokens!["tokenize", "tokenizer"]),
+            ("c", tokens!["token", "hello"]),
+            ("d", tokens!["hall", "tokio"]),
+            ("e", tokens!["shell"]),
+        ];
+
+        for (resource, mut tokens) in documents {
+            let word_count = tokens.count();
+            index
+                .insert(resource.into(), word_count, &mut tokens)
+                .unwrap();
+        }
+
+        assert_eq!(matches(&index, "tokeni*"), set(["a", "b"]));
+        assert_eq!(matches(&index, "tok*"), set(["a", "b", "c", "d"]));
+        assert_eq!(matches(&index, "h?ll*"), set(["c", "d"]));
+        assert_eq!(matches(&index, "*ell*"), set(["c", "e"]));
+        assert_eq!(matches(&index, "tok* -rust"), set(["b", "c", "d"]));
+        assert_eq!(matches(&index, "x*"), set([]));
+
+        let reader = index.reader();
+        let evaluate = |scorer: Scorer<'_, TfIdfScorer<'_, String>>, query: &str| {
+            scorer.evaluate(&Query::new(query).parse().unwrap())
+        };
+
+        // Postings of the expanded terms are combined.
+        let expanded = evaluate(Scorer::new(TfIdfScorer::new(&reader)), "tokeni*");
+        let terms = evaluate(Scorer::new(TfIdfScorer::new(&reader)), "tokenize tokenizer");
+        assert_eq!(expanded, terms);
+
+        // Only the first terms in order are expanded, "token" and "tokenize".
+        let scorer = Scorer::new(TfIdfScorer::new(&reader)).with_max_expansions(2);
+        let capped = evaluate(scorer, "tok*")
+            .into_keys()
+            .collect::<BTreeSet<_>>();
+        assert_eq!(capped, BTreeSet::from([1, 2]));
+
+        let scorer = Scorer::new(TfIdfScorer::new(&reader)).with_max_expansions(1);
+        let capped = evaluate(scorer, "hall~1")
+            .into_keys()
+            .collect::<BTreeSet<_>>();
+        assert_eq!(capped, BTreeSet::from([3]));
+    }
+
+    fn field_index() -> Index<String> {
+        let mut index = Index::new(10, 8).with_positions();
+        let documents = [
+            (
+                "a",
+                tokens!["rust", "guide"],
+                tokens!["learn", "quick", "fox"],
+            ),
+            ("b", tokens!["quick", "fox"], tokens!["rust", "colour"]),
+            ("c", tokens!["java"], tokens!["quick", "brown", "fox"]),
+            ("d", tokens!["quick"], tokens!["fox", "color"]),
+        ];
+
+        for (resource, title, body) in documents {
+            let fields = vec![
+                FieldTokens::new("title", title.count(), title),
+                FieldTokens::new("body", body.count(), body),
+            ];
+            index
+                .insert_fields(resource.into(), fields, Facets::new())
+                .unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_eval_fields() {
+        let index = field_index();
+
+        assert_eq!(matches(&index, "rust"), set(["a", "b"]));
+        assert_eq!(matches(&index, "title:rust"), set(["a"]));
+        assert_eq!(matches(&index, "body:rust"), set(["b"]));
+        assert_eq!(matches(&index, "title:(rust OR java)"), set(["a", "c"]));
+        assert_eq!(matches(&index, "quick -title:quick"), set(["a", "c"]));
+        assert_eq!(matches(&index, "title:missing"), set([]));
+
+        // Expansions stay within the field.
+        assert_eq!(matches(&index, "title:ru*"), set(["a"]));
+        assert_eq!(matches(&index, "gui*"), set(["a"]));
+        assert_eq!(matches(&index, "body:colr~1"), set(["d"]));
+        assert_eq!(matches(&index, "colr~1"), set(["d"]));
+        assert_eq!(matches(&index, "colour~1"), set(["b", "d"]));
+
+        // A phrase only matches within a field.
+        assert_eq!(matches(&index, "\"quick fox\""), set(["a", "b"]));
+        assert_eq!(matches(&index, "title:\"quick fox\""), set(["b"]));
+        assert_eq!(matches(&index, "\"quick fox\"~1"), set(["a", "b", "c"]));
+        assert_eq!(matches(&index, "\"guide learn\""), set([]));
+    }
+
+    #[test]
+    fn test_eval_explain() {
+        let index = field_index();
+        let reader = index.reader();
+        let scorer = Scorer::new(Bm25Scorer::new(&reader));
+        let queries = [
+            "rust",
+            "title:rust quick",
+            "+quick fox -java",
+            "quick AND fox",
+            "\"quick fox\"~1",
+            "colour~1",
+            "qu* title:guide",
+        ];
+
+        // An explanation adds up to the score of the resource.
+        for query in queries {
+            let expr = Query::new(query).parse().unwrap();
+            let matches = scorer.evaluate(&expr);
+
+            for index in 0..4 {
+                let explanation = scorer.explain(&expr, index);
+
+                match (matches.get(&index), explanation) {
+                    (Some(score), Some(explanation)) => {
+                        assert!((score - explanation.value).abs() < 1e-5, "{query}")
+                    }
+                    (None, None) => {}
+                    (score, explanation) => panic!("{query}: {score:?}, {explanation:?}"),
+                }
+            }
+        }
+
+        let expr = Query::new("\"quick fox\"").parse().unwrap();
+        let explanation = scorer.explain(&expr, 1).unwrap();
+        let boost = explanation
+            .find("phrase boost, 1 + frequency / (1 + frequency) of:")
+            .unwrap();
+        assert_eq!(boost.value, 1.5);
+        assert_eq!(explanation.details[0].details.len(), 2);
+    }
+
+    #[test]
+    fn test_eval_scores() {
+        let index = test_index();
+        let reader = index.reader();
+        let expr = Query::new("+rust tokio").parse().unwrap();
+
+        let term = |scorer: &Bm25Scorer<'_, String>, term: &str| -> Matches {
+            scorer.score(term).unwrap().into_iter().collect()
+        };
+
+        let bm25 = Bm25Scorer::new(&reader);
+        let (rust, tokio) = (term(&bm25, "rust"), term(&bm25, "tokio"));
+        let matches = Scorer::new(Bm25Scorer::new(&reader)).evaluate(&expr);
+
+        // Optional clauses only add to the score of required matches.
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[&0], rust[&0]);
+        assert_eq!(matches[&1], rust[&1] + tokio[&1]);
+    }
+}
@@ -0,0 +1,53 @@
+//! Queries over the inverted index.
+//!
+//! A [`Query`] is parsed into an [`Expr`], see [`parser`] for the syntax,
+//! and evaluated with a [`Score`](crate::score::Score) implementation,
+//! see [`Scorer::evaluate`](crate::score::Scorer::evaluate). Disjunctions
+//! of terms can be evaluated with dynamic pruning, see [`wand`].
+
+pub mod eval;
+pub mod parser;
+pub mod wand;
+
+use std::ops::Deref;
+
+use crate::{error::QueryError, token::Tokens, tokenizer::Tokenizer};
+
+pub use parser::{Expr, Parser};
+
+#[derive(Debug)]
+pub struct Query<'a>(&'a str);
+
+impl<'a> Query<'a> {
+    #[inline]
+    pub fn new(value: &'a str) -> Self {
+        Self(value)
+    }
+
+    #[inline]
+    pub fn tokenize(&self, tokenizer: &mut Tokenizer) -> Tokens {
+        tokenizer.tokenize(self.0)
+    }
+
+    /// Parses the query into an expression.
+    #[inline]
+    pub fn parse(&self) -> Result<Expr, QueryError> {
+        Parser::new(self.0).parse()
+    }
+}
+
+impl<'a> Deref for Query<'a> {
+    type Target = &'a str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Query<'_> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
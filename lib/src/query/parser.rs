@@ -0,0 +1,986 @@
+//! Boolean query parser.
+//!
+//! # Syntax
+//!
+//! ```text
+//! sequence := or+
+//! or       := and ("OR" and)*
+//! and      := unary ("AND" unary)*
+//! unary    := ("NOT" | "-" | "+") unary | primary
+//! primary  := field primary | term | fuzzy | wildcard | phrase | "(" sequence ")"
+//! field    := name ":"
+//! fuzzy    := term "~" distance?
+//! wildcard := term containing "*" or "?"
+//! phrase   := '"' term* '"' ("~" slop)?
+//! ```
+//!
+//! Adjacent clauses behave like `OR`. Within a sequence, `+clause` is
+//! required and `-clause` or `NOT clause` is excluded, for example
+//! `rust AND (async OR tokio) -java`.
+//!
+//! A wildcard term matches the indexed terms matching the glob pattern, `*`
+//! matches any sequence of characters and `?` a single character, as in
+//! `tokeni*` or `h?ll*`.
+//!
+//! A phrase matches its terms next to each other and in order, such as
+//! `"quick brown fox"`. With a slop, the terms may be up to `slop` positions
+//! apart in total, `"quick fox"~1` matches `quick brown fox`.
+//!
+//! A fuzzy term matches the indexed terms within `distance` edits, with
+//! adjacent transpositions counting as one edit. `color~1` matches `colour`,
+//! the distance defaults to [`DEFAULT_DISTANCE`] and is at most
+//! [`MAX_DISTANCE`].
+//!
+//! A field restricts the following clause to the named field, as in
+//! `title:rust` or `title:(rust OR go)`. Terms without a field are searched
+//! in every field, see [`crate::field`]. Modifiers after the colon apply to
+//! the field clause, `title:-java` is `-title:java`.
+//!
+//! Operators are case-sensitive, `and`, `or` and `not` are plain terms.
+
+use std::{iter::Peekable, str::CharIndices};
+
+use crate::{
+    error::QueryError,
+    field,
+    token::{Token, Tokens},
+};
+
+/// Edit distance of a fuzzy term without an explicit distance.
+pub const DEFAULT_DISTANCE: u32 = 2;
+
+/// Largest edit distance of a fuzzy term, larger distances match most of
+/// the dictionary.
+pub const MAX_DISTANCE: u32 = 2;
+
+/// Query expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Term(String),
+
+    /// Indexed terms within `distance` edits of the term.
+    Fuzzy {
+        term: String,
+        distance: u32,
+    },
+
+    /// Indexed terms matching the glob pattern.
+    Wildcard(String),
+
+    /// Terms in order, at most `slop` positions apart in total.
+    Phrase {
+        terms: Vec<String>,
+
+        /// Position of each term in the query, with the gaps left by the
+        /// terms dropped by analysis.
+        positions: Vec<u32>,
+        slop: u32,
+    },
+
+    /// Every clause must match.
+    And(Vec<Expr>),
+
+    /// At least one clause must match.
+    Or(Vec<Expr>),
+
+    /// Excluded from the enclosing clauses.
+    Not(Box<Expr>),
+
+    /// Required in the enclosing clauses.
+    Required(Box<Expr>),
+
+    /// Clause restricted to the named field.
+    Field(String, Box<Expr>),
+}
+
+impl Expr {
+    /// Conjunction of the clauses, nested conjunctions are flattened.
+    pub fn and(clauses: Vec<Expr>) -> Self {
+        Self::flatten(clauses, Self::And, |expr| match expr {
+            Self::And(clauses) => Ok(clauses),
+            expr => Err(expr),
+        })
+    }
+
+    /// Disjunction of the clauses, nested disjunctions without
+    /// required or excluded clauses are flattened.
+    pub fn or(clauses: Vec<Expr>) -> Self {
+        Self::flatten(clauses, Self::Or, |expr| match expr {
+            Self::Or(clauses) if !clauses.iter().any(Self::is_modifier) => Ok(clauses),
+            expr => Err(expr),
+        })
+    }
+
+    /// Whether the clause is required or excluded.
+    #[inline]
+    pub fn is_modifier(&self) -> bool {
+        matches!(self, Self::Not(_) | Self::Required(_))
+    }
+
+    fn flatten(
+        clauses: Vec<Expr>,
+        f: impl FnOnce(Vec<Expr>) -> Self,
+        split: impl Fn(Expr) -> Result<Vec<Expr>, Expr>,
+    ) -> Self {
+        let mut flat = Vec::with_capacity(clauses.len());
+
+        for clause in clauses {
+            match split(clause) {
+                Ok(clauses) => flat.extend(clauses),
+                Err(clause) => flat.push(clause),
+            }
+        }
+
+        match flat.len() {
+            // Modifiers only apply within a group.
+            1 if !flat[0].is_modifier() => flat.pop().unwrap(),
+            _ => f(flat),
+        }
+    }
+
+    /// Maps every term through `f`, usually the tokenizer and the normalizers
+    /// used at index time.
+    ///
+    /// A term mapped to several terms becomes their conjunction, a fuzzy term
+    /// the conjunction of fuzzy terms, and a phrase
+    /// left with a single term becomes that term. Clauses left without terms
+    /// are removed. Returns `None` if no term is left.
+    ///
+    /// A phrase is mapped as a whole, its terms keep the positions of their
+    /// tokens, so the terms dropped by `f` leave gaps as they do when indexed.
+    ///
+    /// The literal parts of a wildcard pattern are mapped on their own,
+    /// and only replaced when they map to a single term, otherwise they
+    /// are lowercased, e.g. a stopword prefix.
+    pub fn analyze(self, f: &mut impl FnMut(&str) -> Tokens) -> Option<Self> {
+        self.analyze_fields(&mut |_, term| f(term))
+    }
+
+    /// Same as [`Expr::analyze`], with the field of the term passed to `f`,
+    /// `None` outside of a field.
+    ///
+    /// Fields are resolved, the terms of a field clause are qualified with
+    /// the field, see [`Expr::qualify`].
+    pub fn analyze_fields(self, f: &mut impl FnMut(Option<&str>, &str) -> Tokens) -> Option<Self> {
+        self.analyze_in(None, f)
+    }
+
+    fn analyze_in(
+        self,
+        field: Option<&str>,
+        f: &mut impl FnMut(Option<&str>, &str) -> Tokens,
+    ) -> Option<Self> {
+        match self {
+            Self::Term(term) => {
+                let mut terms = f(field, &term)
+                    .into_iter()
+                    .map(Token::inner)
+                    .filter(|term| !term.is_empty())
+                    .map(Self::Term)
+                    .collect::<Vec<_>>();
+
+                match terms.len() {
+                    0 => None,
+                    1 => terms.pop(),
+                    _ => Some(Self::And(terms)),
+                }
+            }
+            Self::Fuzzy { term, distance } => {
+                let mut terms = f(field, &term)
+                    .into_iter()
+                    .map(Token::inner)
+                    .filter(|term| !term.is_empty())
+                    .map(|term| Self::Fuzzy { term, distance })
+                    .collect::<Vec<_>>();
+
+                match terms.len() {
+                    0 => None,
+                    1 => terms.pop(),
+                    _ => Some(Self::And(terms)),
+                }
+            }
+            Self::Wildcard(pattern) => {
+                let mut analyzed = String::with_capacity(pattern.len());
+
+                for part in pattern.split_inclusive(['*', '?']) {
+                    let literal = part.trim_end_matches(['*', '?']);
+                    let wildcards = &part[literal.len()..];
+
+                    let tokens = f(field, literal);
+                    match tokens.iter().as_slice() {
+                        [token] if !literal.is_empty() => analyzed.push_str(token),
+                        // Still case-folded, as indexed terms are.
+                        _ => analyzed.push_str(&literal.to_lowercase()),
+                    }
+                    analyzed.push_str(wildcards);
+                }
+
+                Some(Self::Wildcard(analyzed))
+            }
+            Self::Phrase { terms, slop, .. } => {
+                let (positions, mut terms): (Vec<_>, Vec<_>) = f(field, &terms.join(" "))
+                    .into_iter()
+                    .filter(|token| !token.is_empty())
+                    .map(|token| (token.position() as u32, token.inner()))
+                    .unzip();
+
+                match terms.len() {
+                    0 => None,
+                    1 => terms.pop().map(Self::Term),
+                    _ => Some(Self::Phrase {
+                        terms,
+                        positions,
+                        slop,
+                    }),
+                }
+            }
+            Self::And(clauses) => Self::analyze_all(clauses, field, f).map(Self::and),
+            Self::Or(clauses) => Self::analyze_all(clauses, field, f).map(Self::or),
+            Self::Not(expr) => expr
+                .analyze_in(field, f)
+                .map(|expr| Self::Not(Box::new(expr))),
+            Self::Required(expr) => expr
+                .analyze_in(field, f)
+                .map(|expr| Self::Required(Box::new(expr))),
+            Self::Field(name, expr) => expr
+                .analyze_in(Some(&name), f)
+                .map(|expr| expr.qualify(&name)),
+        }
+    }
+
+    fn analyze_all(
+        clauses: Vec<Expr>,
+        field: Option<&str>,
+        f: &mut impl FnMut(Option<&str>, &str) -> Tokens,
+    ) -> Option<Vec<Expr>> {
+        let clauses = clauses
+            .into_iter()
+            .filter_map(|clause| clause.analyze_in(field, f))
+            .collect::<Vec<_>>();
+
+        (!clauses.is_empty()).then_some(clauses)
+    }
+
+    /// Restricts every term to the field, replacing them with their key in
+    /// the field, see [`field::qualify`].
+    ///
+    /// Terms already restricted to a field keep it, the innermost field wins.
+    pub fn qualify(self, name: &str) -> Self {
+        let qualify = |term: String| match field::is_qualified(&term) {
+            true => term,
+            false => field::qualify(name, &term),
+        };
+
+        match self {
+            Self::Term(term) => Self::Term(qualify(term)),
+            Self::Fuzzy { term, distance } => Self::Fuzzy {
+                term: qualify(term),
+                distance,
+            },
+            Self::Wildcard(pattern) => Self::Wildcard(qualify(pattern)),
+            Self::Phrase {
+                terms,
+                positions,
+                slop,
+            } => Self::Phrase {
+                terms: terms.into_iter().map(qualify).collect(),
+                positions,
+                slop,
+            },
+            Self::And(clauses) => Self::And(Self::qualify_all(clauses, name)),
+            Self::Or(clauses) => Self::Or(Self::qualify_all(clauses, name)),
+            Self::Not(expr) => Self::Not(Box::new(expr.qualify(name))),
+            Self::Required(expr) => Self::Required(Box::new(expr.qualify(name))),
+            Self::Field(inner, expr) => expr.qualify(&inner),
+        }
+    }
+
+    fn qualify_all(clauses: Vec<Expr>, name: &str) -> Vec<Expr> {
+        clauses
+            .into_iter()
+            .map(|clause| clause.qualify(name))
+            .collect()
+    }
+
+    /// Terms matched by the expression, in order, e.g. to highlight them.
+    ///
+    /// Terms of excluded clauses are left out. Wildcard patterns are left
+    /// out too, and fuzzy terms are not expanded. Fields of [`Expr::Field`]
+    /// clauses are not resolved, see [`Expr::qualify`].
+    pub fn terms(&self) -> Vec<&str> {
+        let mut terms = Vec::new();
+        self.collect_terms(&mut terms);
+        terms
+    }
+
+    fn collect_terms<'a>(&'a self, terms: &mut Vec<&'a str>) {
+        match self {
+            Self::Term(term) | Self::Fuzzy { term, .. } => terms.push(term),
+            Self::Phrase { terms: phrase, .. } => terms.extend(phrase.iter().map(String::as_str)),
+            Self::And(clauses) | Self::Or(clauses) => clauses
+                .iter()
+                .for_each(|clause| clause.collect_terms(terms)),
+            Self::Required(expr) | Self::Field(_, expr) => expr.collect_terms(terms),
+            Self::Wildcard(_) | Self::Not(_) => {}
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Lexeme {
+    Field(String),
+    Word(String),
+    Fuzzy(String, u32),
+    Wildcard(String),
+    Phrase(Vec<String>, u32),
+    And,
+    Or,
+    Not,
+    Required,
+    Excluded,
+    Open,
+    Close,
+
+    /// Reported when reached by the parser.
+    Invalid(QueryError),
+}
+
+/// Splits the query into lexemes, with their byte offset.
+fn lex(query: &str) -> Vec<(usize, Lexeme)> {
+    let mut lexemes = Vec::new();
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((mut offset, char)) = chars.next() {
+        let lexeme = match char {
+            char if char.is_whitespace() => continue,
+            '(' => Lexeme::Open,
+            ')' => Lexeme::Close,
+            '"' => phrase(query, offset, &mut chars).unwrap_or_else(Lexeme::Invalid),
+
+            // Modifiers only prefix a clause, `tokio-rs` is a single word.
+            '+' | '-'
+                if chars
+                    .peek()
+                    .is_some_and(|&(_, next)| next == '"' || !is_boundary(next)) =>
+            {
+                match char {
+                    '+' => Lexeme::Required,
+                    _ => Lexeme::Excluded,
+                }
+            }
+
+            _ => {
+                let mut end = offset + char.len_utf8();
+                while let Some(&(next_offset, next)) = chars.peek() {
+                    if is_boundary(next) {
+                        break;
+                    }
+                    end = next_offset + next.len_utf8();
+                    chars.next();
+                }
+
+                let mut word = &query[offset..end];
+                if let Some((name, rest)) = field_prefix(word) {
+                    lexemes.push((offset, Lexeme::Field(name.to_string())));
+                    offset += name.len() + 1;
+                    word = rest;
+
+                    // Modifiers after the colon prefix the clause as well,
+                    // e.g. `title:-java`.
+                    while let Some(rest) = word.strip_prefix(['+', '-']) {
+                        let prefixes =
+                            !rest.is_empty() || chars.peek().is_some_and(|&(_, next)| next == '"');
+                        if !prefixes {
+                            break;
+                        }
+
+                        let modifier = match word.starts_with('+') {
+                            true => Lexeme::Required,
+                            false => Lexeme::Excluded,
+                        };
+                        lexemes.push((offset, modifier));
+                        offset += 1;
+                        word = rest;
+                    }
+
+                    // The clause follows the colon, e.g. `title:(rust OR go)`.
+                    if word.is_empty() {
+                        continue;
+                    }
+                }
+
+                match word {
+                    "AND" => Lexeme::And,
+                    "OR" => Lexeme::Or,
+                    "NOT" => Lexeme::Not,
+                    word if word.contains(['*', '?']) => Lexeme::Wildcard(word.to_string()),
+                    word => fuzzy(offset, word).unwrap_or_else(|| Lexeme::Word(word.to_string())),
+                }
+            }
+        };
+
+        lexemes.push((offset, lexeme));
+    }
+
+    lexemes
+}
+
+/// Splits `name:rest` into the field name and the rest of the word,
+/// `None` if the word does not start with a field.
+fn field_prefix(word: &str) -> Option<(&str, &str)> {
+    let (name, rest) = word.split_once(':')?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '_');
+
+    valid.then_some((name, rest))
+}
+
+/// Lexes `term~distance` at `offset`, `None` if the word is not a fuzzy term.
+fn fuzzy(offset: usize, word: &str) -> Option<Lexeme> {
+    let (term, distance) = word.rsplit_once('~')?;
+    if term.is_empty() {
+        return None;
+    }
+
+    let distance = match distance {
+        "" => DEFAULT_DISTANCE,
+        distance => distance.parse().ok()?,
+    };
+
+    match distance {
+        0..=MAX_DISTANCE => Some(Lexeme::Fuzzy(term.to_string(), distance)),
+        _ => Some(Lexeme::Invalid(QueryError::DistanceTooLarge(
+            offset, distance,
+        ))),
+    }
+}
+
+/// Lexes a phrase opened at `offset`, and its optional `~slop` suffix.
+fn phrase(
+    query: &str,
+    offset: usize,
+    chars: &mut Peekable<CharIndices>,
+) -> Result<Lexeme, QueryError> {
+    let start = offset + 1;
+    let end = loop {
+        match chars.next() {
+            Some((end, '"')) => break end,
+            Some(_) => continue,
+            None => return Err(QueryError::UnterminatedPhrase(offset)),
+        }
+    };
+
+    let terms = query[start..end]
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+
+    let Some(&(tilde, '~')) = chars.peek() else {
+        return Ok(Lexeme::Phrase(terms, 0));
+    };
+    chars.next();
+
+    let mut slop_end = tilde + 1;
+    while let Some(&(next_offset, next)) = chars.peek() {
+        if is_boundary(next) {
+            break;
+        }
+        slop_end = next_offset + next.len_utf8();
+        chars.next();
+    }
+
+    match query[tilde + 1..slop_end].parse() {
+        Ok(slop) => Ok(Lexeme::Phrase(terms, slop)),
+        Err(_) => {
+            let token = query[tilde..slop_end].to_string();
+            Err(QueryError::UnexpectedToken(tilde, token))
+        }
+    }
+}
+
+#[inline]
+fn is_boundary(char: char) -> bool {
+    char.is_whitespace() || matches!(char, '(' | ')' | '"')
+}
+
+/// Recursive descent parser for the query syntax.
+#[derive(Debug)]
+pub struct Parser {
+    lexemes: Vec<(usize, Lexeme)>,
+    position: usize,
+}
+
+impl Parser {
+    pub fn new(query: &str) -> Self {
+        Self {
+            lexemes: lex(query),
+            position: 0,
+        }
+    }
+
+    pub fn parse(mut self) -> Result<Expr, QueryError> {
+        if self.lexemes.is_empty() {
+            return Err(QueryError::Empty);
+        }
+
+        let expr = self.sequence()?;
+
+        match self.next() {
+            None => Ok(expr),
+            Some((offset, _)) => Err(QueryError::UnbalancedParenthesis(offset)),
+        }
+    }
+
+    fn sequence(&mut self) -> Result<Expr, QueryError> {
+        let mut clauses = Vec::new();
+
+        while !matches!(self.peek(), None | Some(Lexeme::Close)) {
+            clauses.push(self.or()?);
+        }
+
+        match self.lexemes.get(self.position) {
+            _ if !clauses.is_empty() => Ok(Expr::or(clauses)),
+            Some((offset, _)) => Err(QueryError::UnexpectedToken(*offset, ")".into())),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut clauses = vec![self.and()?];
+
+        while self.peek() == Some(&Lexeme::Or) {
+            self.next();
+            clauses.push(self.and()?);
+        }
+
+        match clauses.len() {
+            1 => Ok(clauses.pop().unwrap()),
+            _ => Ok(Expr::or(clauses)),
+        }
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut clauses = vec![self.unary()?];
+
+        while self.peek() == Some(&Lexeme::And) {
+            self.next();
+            clauses.push(self.unary()?);
+        }
+
+        match clauses.len() {
+            1 => Ok(clauses.pop().unwrap()),
+            _ => Ok(Expr::and(clauses)),
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, QueryError> {
+        match self.peek() {
+            Some(Lexeme::Not | Lexeme::Excluded) => {
+                self.next();
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Lexeme::Required) => {
+                self.next();
+                Ok(Expr::Required(Box::new(self.unary()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    /// Operand of a field prefix, modifiers apply to the whole field clause,
+    /// e.g. `title:-java` is `-title:java`.
+    fn field(&mut self, name: String) -> Result<Expr, QueryError> {
+        match self.peek() {
+            Some(Lexeme::Not | Lexeme::Excluded) => {
+                self.next();
+                Ok(Expr::Not(Box::new(self.field(name)?)))
+            }
+            Some(Lexeme::Required) => {
+                self.next();
+                Ok(Expr::Required(Box::new(self.field(name)?)))
+            }
+            _ => Ok(Expr::Field(name, Box::new(self.primary()?))),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        let Some((offset, lexeme)) = self.next() else {
+            return Err(QueryError::UnexpectedEnd);
+        };
+
+        match lexeme {
+            Lexeme::Field(name) => self.field(name),
+            Lexeme::Word(word) => Ok(Expr::Term(word)),
+            Lexeme::Fuzzy(term, distance) => Ok(Expr::Fuzzy { term, distance }),
+            Lexeme::Wildcard(pattern) => Ok(Expr::Wildcard(pattern)),
+            Lexeme::Phrase(terms, slop) => Ok(Expr::Phrase {
+                positions: (0..terms.len() as u32).collect(),
+                terms,
+                slop,
+            }),
+            Lexeme::Open => {
+                let expr = self.sequence()?;
+
+                match self.next() {
+                    Some((_, Lexeme::Close)) => Ok(expr),
+                    _ => Err(QueryError::UnbalancedParenthesis(offset)),
+                }
+            }
+            Lexeme::Close => Err(QueryError::UnexpectedToken(offset, ")".into())),
+            Lexeme::And => Err(QueryError::UnexpectedToken(offset, "AND".into())),
+            Lexeme::Or => Err(QueryError::UnexpectedToken(offset, "OR".into())),
+            Lexeme::Invalid(error) => Err(error),
+            Lexeme::Not | Lexeme::Required | Lexeme::Excluded => {
+                unreachable!("handled by unary and field")
+            }
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&Lexeme> {
+        self.lexemes.get(self.position).map(|(_, lexeme)| lexeme)
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, Lexeme)> {
+        let lexeme = self.lexemes.get(self.position).cloned();
+        self.position += lexeme.is_some() as usize;
+        lexeme
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::QueryError,
+        field,
+        query::parser::{Expr, Parser},
+        token::{Token, Tokens},
+    };
+
+    fn parse(query: &str) -> Result<Expr, QueryError> {
+        Parser::new(query).parse()
+    }
+
+    /// Words of the text split on `-` as well, with their position, mapped
+    /// by `f` and dropped when it returns `None`.
+    fn tokenize(text: &str, f: impl Fn(&str) -> Option<String>) -> Tokens {
+        text.split([' ', '-'])
+            .filter(|word| !word.is_empty())
+            .enumerate()
+            .filter_map(|(position, word)| Some(Token::from(f(word)?).with_position(position)))
+            .collect()
+    }
+
+    fn lowercase_without(stopwords: &'static [&'static str]) -> impl Fn(&str) -> Option<String> {
+        |word| Some(word.to_lowercase()).filter(|word| !stopwords.contains(&word.as_str()))
+    }
+
+    fn term(term: &str) -> Expr {
+        Expr::Term(term.into())
+    }
+
+    fn not(expr: Expr) -> Expr {
+        Expr::Not(Box::new(expr))
+    }
+
+    #[test]
+    fn test_parser_precedence() {
+        assert_eq!(
+            parse("rust AND (async OR tokio) -java"),
+            Ok(Expr::Or(vec![
+                Expr::And(vec![
+                    term("rust"),
+                    Expr::Or(vec![term("async"), term("tokio")])
+                ]),
+                not(term("java")),
+            ]))
+        );
+
+        assert_eq!(
+            parse("a OR b AND c"),
+            Ok(Expr::Or(vec![
+                term("a"),
+                Expr::And(vec![term("b"), term("c")])
+            ]))
+        );
+
+        assert_eq!(
+            parse("+rust tokio NOT java"),
+            Ok(Expr::Or(vec![
+                Expr::Required(Box::new(term("rust"))),
+                term("tokio"),
+                not(term("java")),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parser_words() {
+        assert_eq!(parse("tokio-rs"), Ok(term("tokio-rs")));
+        assert_eq!(parse("and or"), Ok(Expr::Or(vec![term("and"), term("or")])));
+        assert_eq!(parse("((rust))"), Ok(term("rust")));
+        assert_eq!(
+            parse("a - b"),
+            Ok(Expr::Or(vec![term("a"), term("-"), term("b")]))
+        );
+        assert_eq!(parse("-java"), Ok(Expr::Or(vec![not(term("java"))])));
+        assert_eq!(
+            parse("(+a b) c"),
+            Ok(Expr::Or(vec![
+                Expr::Or(vec![Expr::Required(Box::new(term("a"))), term("b")]),
+                term("c"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parser_phrases() {
+        let phrase = |terms: &[&str], slop| Expr::Phrase {
+            terms: terms.iter().map(ToString::to_string).collect(),
+            positions: (0..terms.len() as u32).collect(),
+            slop,
+        };
+
+        assert_eq!(
+            parse("\"quick brown  fox\""),
+            Ok(phrase(&["quick", "brown", "fox"], 0))
+        );
+        assert_eq!(
+            parse("\"quick fox\"~3 -\"lazy dog\""),
+            Ok(Expr::Or(vec![
+                phrase(&["quick", "fox"], 3),
+                not(phrase(&["lazy", "dog"], 0)),
+            ]))
+        );
+        assert_eq!(
+            parse("rust\"async\"(tokio)"),
+            Ok(Expr::Or(vec![
+                term("rust"),
+                phrase(&["async"], 0),
+                term("tokio")
+            ]))
+        );
+
+        assert_eq!(parse("\"quick fox"), Err(QueryError::UnterminatedPhrase(0)));
+        assert_eq!(
+            parse("\"quick fox\"~x"),
+            Err(QueryError::UnexpectedToken(11, "~x".into()))
+        );
+
+        let analyzed = parse("\"The Quick fox\"~2 \"the fox\"")
+            .unwrap()
+            .analyze(&mut |text| tokenize(text, lowercase_without(&["the"])));
+        assert_eq!(
+            analyzed,
+            Some(Expr::Or(vec![
+                Expr::Phrase {
+                    terms: vec!["quick".into(), "fox".into()],
+                    positions: vec![1, 2],
+                    slop: 2
+                },
+                term("fox")
+            ]))
+        );
+
+        // Dropped terms leave gaps between the positions.
+        let analyzed = parse("\"cat in the hat\"")
+            .unwrap()
+            .analyze(&mut |text| tokenize(text, lowercase_without(&["in", "the"])));
+        assert_eq!(
+            analyzed,
+            Some(Expr::Phrase {
+                terms: vec!["cat".into(), "hat".into()],
+                positions: vec![0, 3],
+                slop: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_parser_fuzzy() {
+        let fuzzy = |term: &str, distance| Expr::Fuzzy {
+            term: term.into(),
+            distance,
+        };
+
+        assert_eq!(parse("color~1"), Ok(fuzzy("color", 1)));
+        assert_eq!(parse("color~"), Ok(fuzzy("color", 2)));
+        assert_eq!(
+            parse("+colr~1 -gray~0"),
+            Ok(Expr::Or(vec![
+                Expr::Required(Box::new(fuzzy("colr", 1))),
+                not(fuzzy("gray", 0)),
+            ]))
+        );
+
+        // Larger distances match most of the dictionary.
+        assert_eq!(parse("color~3"), Err(QueryError::DistanceTooLarge(0, 3)));
+        assert_eq!(
+            parse("rust title:color~50"),
+            Err(QueryError::DistanceTooLarge(11, 50))
+        );
+
+        // Not a distance, plain words.
+        assert_eq!(parse("~1"), Ok(term("~1")));
+        assert_eq!(parse("a~b"), Ok(term("a~b")));
+
+        let analyzed = parse("Dark-Color~1")
+            .unwrap()
+            .analyze(&mut |text| tokenize(text, lowercase_without(&[])));
+        assert_eq!(
+            analyzed,
+            Some(Expr::And(vec![fuzzy("dark", 1), fuzzy("color", 1)]))
+        );
+    }
+
+    #[test]
+    fn test_parser_wildcard() {
+        let wildcard = |pattern: &str| Expr::Wildcard(pattern.into());
+
+        assert_eq!(parse("tokeni*"), Ok(wildcard("tokeni*")));
+        assert_eq!(
+            parse("h?ll* -*ing"),
+            Ok(Expr::Or(vec![wildcard("h?ll*"), not(wildcard("*ing"))]))
+        );
+
+        let analyzed = parse("H?LL*O The*")
+            .unwrap()
+            .analyze(&mut |text| tokenize(text, lowercase_without(&["the"])));
+        assert_eq!(
+            analyzed,
+            Some(Expr::Or(vec![wildcard("h?ll*o"), wildcard("the*")]))
+        );
+    }
+
+    #[test]
+    fn test_parser_errors() {
+        assert_eq!(parse("  "), Err(QueryError::Empty));
+        assert_eq!(parse("rust AND"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(parse("(rust"), Err(QueryError::UnbalancedParenthesis(0)));
+        assert_eq!(parse("rust)"), Err(QueryError::UnbalancedParenthesis(4)));
+        assert_eq!(
+            parse("OR rust"),
+            Err(QueryError::UnexpectedToken(0, "OR".into()))
+        );
+        assert_eq!(
+            parse("rust ()"),
+            Err(QueryError::UnexpectedToken(6, ")".into()))
+        );
+    }
+
+    #[test]
+    fn test_expr_analyze() {
+        let expr = parse("The AND (Tokio-RS OR of) -Java").unwrap();
+
+        let analyzed = expr.analyze(&mut |text| tokenize(text, lowercase_without(&["the", "of"])));
+
+        assert_eq!(
+            analyzed,
+            Some(Expr::Or(vec![
+                Expr::And(vec![term("tokio"), term("rs")]),
+                not(term("java")),
+            ]))
+        );
+
+        assert_eq!(
+            parse("the")
+                .unwrap()
+                .analyze(&mut |_| Tokens::from(Vec::new())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parser_fields() {
+        let field = |name: &str, expr| Expr::Field(name.into(), Box::new(expr));
+
+        assert_eq!(parse("title:rust"), Ok(field("title", term("rust"))));
+        assert_eq!(
+            parse("title:(rust OR go) -body:java"),
+            Ok(Expr::Or(vec![
+                field("title", Expr::Or(vec![term("rust"), term("go")])),
+                not(field("body", term("java"))),
+            ]))
+        );
+        assert_eq!(
+            parse("title:\"quick fox\"~1 title:colr~1"),
+            Ok(Expr::Or(vec![
+                field(
+                    "title",
+                    Expr::Phrase {
+                        terms: vec!["quick".into(), "fox".into()],
+                        positions: vec![0, 1],
+                        slop: 1
+                    }
+                ),
+                field(
+                    "title",
+                    Expr::Fuzzy {
+                        term: "colr".into(),
+                        distance: 1
+                    }
+                ),
+            ]))
+        );
+
+        // Modifiers after the prefix apply to the field clause.
+        assert_eq!(
+            parse("title: -java"),
+            Ok(Expr::Or(vec![not(field("title", term("java")))]))
+        );
+        assert_eq!(parse("title: NOT java"), parse("-title:java"));
+        assert_eq!(parse("title:-java"), parse("-title:java"));
+        assert_eq!(parse("title:+rust"), parse("+title:rust"));
+        assert_eq!(parse("title:-\"quick fox\""), parse("-title:\"quick fox\""));
+        assert_eq!(parse("title:-"), Ok(field("title", term("-"))));
+        assert_eq!(
+            parse("title:tokio-rs"),
+            Ok(field("title", term("tokio-rs")))
+        );
+        assert_eq!(parse("title: +rust"), parse("+title:rust"));
+        assert_eq!(parse("btitle:NOT "), Err(QueryError::UnexpectedEnd));
+        assert_eq!(
+            parse("-1OR title:NOT .."),
+            Ok(Expr::Or(vec![
+                not(term("1OR")),
+                not(field("title", term(".."))),
+            ]))
+        );
+
+        // Not a field name.
+        assert_eq!(parse(":rust"), Ok(term(":rust")));
+        assert_eq!(parse("a.b:c"), Ok(term("a.b:c")));
+        assert!(matches!(parse("title:"), Err(QueryError::UnexpectedEnd)));
+    }
+
+    #[test]
+    fn test_expr_analyze_fields() {
+        let expr = parse("title:(Rust AND body:Go*) Java").unwrap();
+
+        let analyzed = expr.analyze_fields(&mut |field, text| match field {
+            Some("title") => tokenize(text, |word| Some(word.to_uppercase())),
+            _ => tokenize(text, lowercase_without(&[])),
+        });
+
+        assert_eq!(
+            analyzed,
+            Some(Expr::Or(vec![
+                Expr::And(vec![
+                    term(&field::qualify("title", "RUST")),
+                    Expr::Wildcard(field::qualify("body", "go*")),
+                ]),
+                term("java"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_expr_terms() {
+        let expr = parse("+rust \"quick fox\" colr~1 tok* -java title:go").unwrap();
+
+        assert_eq!(expr.terms(), ["rust", "quick", "fox", "colr", "go"]);
+    }
+}
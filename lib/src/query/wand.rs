@@ -0,0 +1,497 @@
+//! Best hits of disjunctive queries, with dynamic pruning.
+//!
+//! [`Wand`] evaluates a disjunction of terms with WAND. Each term, in each
+//! field it is searched in (see [`ReaderContext::fields_of`]), keeps a
+//! cursor over its postings and an upper bound of their scores, from the
+//! highest frequency of the postings and the shortest length of the field.
+//! Scorers combining the fields of a term, e.g. BM25F, bound each field
+//! with the score of the term in that field alone, see
+//! [`PostingScore::term`].
+//! Resources are visited in index order, and a resource is only scored when
+//! the bounds of the terms it may contain exceed the lowest score of the
+//! best hits found so far.
+//!
+//! With block-max WAND, the bounds of each block of postings, see
+//! [`IdfEntry::block_max`], skip the ranges of resources the bounds of
+//! the whole postings did not rule out.
+//!
+//! Hits and scores are the same as the best hits of
+//! [`Scorer::evaluate`](crate::score::Scorer::evaluate).
+//!
+//! [`ReaderContext::fields_of`]: crate::reader::ReaderContext::fields_of
+
+use std::{cell::Cell, fmt::Debug, marker::PhantomData};
+
+use crate::{
+    aggregate::TopK, core::IdfEntry, field, posting::PostingCursor, query::parser::Expr,
+    score::PostingScore,
+};
+
+/// Relative margin of the upper bounds, so that rounding never makes a
+/// bound lower than the score it bounds.
+const BOUND_MARGIN: f32 = 1e-4;
+
+/// Postings of a term in one of its fields.
+#[derive(Debug)]
+struct Scope<'a, T> {
+    /// Position of the term in the disjunction.
+    term: usize,
+
+    field: Option<String>,
+    stats: T,
+
+    /// Weight of the term, see [`PostingScore::weight`].
+    weight: f32,
+    list: IdfEntry<'a>,
+    cursor: PostingCursor<'a>,
+
+    /// Shortest word count of the field.
+    min_count: usize,
+
+    /// Upper bound of the scores of every posting.
+    max_score: f32,
+}
+
+impl<T> Scope<'_, T> {
+    /// Resource the cursor is on, `None` once exhausted.
+    #[inline]
+    fn index(&self) -> Option<usize> {
+        self.cursor.current().map(|entry| entry.get_index())
+    }
+}
+
+/// Evaluator of disjunctions of terms, keeping the best `k` hits.
+#[derive(Debug)]
+pub struct Wand<'s, 'a, S: PostingScore<'a>> {
+    scorer: &'s S,
+    block_max: bool,
+
+    /// Resources scored by the last evaluation.
+    scored: Cell<usize>,
+
+    _marker: PhantomData<&'a S>,
+}
+
+impl<'s, 'a, S: PostingScore<'a> + 'a> Wand<'s, 'a, S> {
+    /// Evaluator with block-max WAND.
+    pub fn new(scorer: &'s S) -> Self {
+        Self {
+            scorer,
+            block_max: true,
+            scored: Cell::new(0),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Whether to skip resources with the bounds of the blocks of postings
+    /// as well, enabled by default.
+    pub fn with_block_max(mut self, block_max: bool) -> Self {
+        self.block_max = block_max;
+        self
+    }
+
+    /// Number of resources scored by the last evaluation.
+    #[inline]
+    pub fn scored(&self) -> usize {
+        self.scored.get()
+    }
+
+    /// Best `k` hits of the expression, by decreasing score.
+    ///
+    /// Returns `None` unless the expression is a term, or a disjunction of
+    /// terms, possibly restricted to fields.
+    pub fn evaluate(&self, expr: &Expr, k: usize) -> Option<Vec<(usize, f32)>> {
+        terms(expr).map(|terms| self.top_k(&terms, k))
+    }
+
+    /// Best `k` hits of the disjunction of the terms, by decreasing score.
+    pub fn top_k(&self, terms: &[String], k: usize) -> Vec<(usize, f32)> {
+        self.scored.set(0);
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let reader = self.scorer.reader();
+        let mut scopes = self.scopes(terms);
+        let mut top = TopK::new(k);
+        let mut scored = 0;
+
+        // Scopes left, by the resource they are on.
+        let mut order = (0..scopes.len()).collect::<Vec<_>>();
+
+        loop {
+            order.retain(|&scope| scopes[scope].index().is_some());
+            order.sort_unstable_by_key(|&scope| scopes[scope].index());
+
+            let threshold = top.threshold();
+            let Some(mut pivot) = pivot(&scopes, &order, threshold) else {
+                break;
+            };
+
+            // Every scope on the pivot resource takes part.
+            let index = scopes[order[pivot]].index().unwrap();
+            pivot += order[pivot + 1..]
+                .iter()
+                .take_while(|&&scope| scopes[scope].index() == Some(index))
+                .count();
+
+            if let Some(threshold) = threshold.filter(|_| self.block_max) {
+                let (bound, end) = self.block_bound(&scopes, &order, pivot, index);
+
+                if bound <= threshold {
+                    for &scope in &order[..=pivot] {
+                        scopes[scope].cursor.advance_to(end);
+                    }
+                    continue;
+                }
+            }
+
+            // Resources before the pivot cannot reach the threshold.
+            if scopes[order[0]].index() != Some(index) {
+                for &scope in &order[..pivot] {
+                    scopes[scope].cursor.advance_to(index);
+                }
+                continue;
+            }
+
+            if !reader.is_deleted(index) {
+                top.insert(index, self.score(&scopes, index));
+                scored += 1;
+            }
+
+            for &scope in &order[..=pivot] {
+                scopes[scope].cursor.next();
+            }
+        }
+
+        self.scored.set(scored);
+        top.into_sorted()
+    }
+
+    /// Postings of every term in every field, in the order of the terms.
+    fn scopes(&self, terms: &[String]) -> Vec<Scope<'a, S::Stats>> {
+        let reader = self.scorer.reader();
+        let mut scopes = Vec::new();
+
+        for (position, term) in terms.iter().enumerate() {
+            let weight = self.scorer.weight(term);
+
+            for (field, term) in reader.fields_of(term) {
+                let key = field::qualify(field.unwrap_or_default(), term);
+
+                let Some(stats) = self.scorer.stats(&key, field) else {
+                    continue;
+                };
+                let Some(entry) = reader.get_entry(&key) else {
+                    continue;
+                };
+
+                let min_count = reader.min_field_count(field);
+                let mut cursor = entry.iter();
+                cursor.next();
+
+                scopes.push(Scope {
+                    term: position,
+                    field: field.map(String::from),
+                    stats,
+                    weight,
+                    max_score: self.bound(weight, stats, entry.max_frequency(), min_count),
+                    list: entry,
+                    cursor,
+                    min_count,
+                });
+            }
+        }
+
+        scopes
+    }
+
+    /// Upper bound of the scores of postings up to the frequency.
+    #[inline]
+    fn bound(&self, weight: f32, stats: S::Stats, max_frequency: usize, min_count: usize) -> f32 {
+        let posting = self.scorer.posting(stats, max_frequency, min_count);
+        self.scorer.term(weight, posting) * (1.0 + BOUND_MARGIN)
+    }
+
+    /// Upper bound of the scores of the resources from `index` to the
+    /// returned end, excluded, from the blocks of the scopes up to the pivot.
+    fn block_bound(
+        &self,
+        scopes: &[Scope<'a, S::Stats>],
+        order: &[usize],
+        pivot: usize,
+        index: usize,
+    ) -> (f32, usize) {
+        // Later scopes are past the range.
+        let mut end = order
+            .get(pivot + 1)
+            .and_then(|&scope| scopes[scope].index())
+            .unwrap_or(usize::MAX);
+        let mut bound = 0.0;
+
+        for &scope in &order[..=pivot] {
+            let scope = &scopes[scope];
+            let Some(block) = scope.list.block_max(index) else {
+                continue;
+            };
+
+            bound += self.bound(
+                scope.weight,
+                scope.stats,
+                block.max_frequency,
+                scope.min_count,
+            );
+            end = end.min(block.last + 1);
+        }
+
+        (bound, end)
+    }
+
+    /// Score of the resource, summed per term then across terms, as in
+    /// [`Scorer::evaluate`](crate::score::Scorer::evaluate).
+    fn score(&self, scopes: &[Scope<'a, S::Stats>], index: usize) -> f32 {
+        let reader = self.scorer.reader();
+        let mut total = 0.0;
+
+        // Position and weight of the term being summed.
+        let mut term = None;
+        let mut sum = 0.0;
+
+        for scope in scopes {
+            let Some(entry) = scope
+                .cursor
+                .current()
+                .filter(|entry| entry.get_index() == index)
+            else {
+                continue;
+            };
+
+            if term.map(|(position, _)| position) != Some(scope.term) {
+                if let Some((_, weight)) = term {
+                    total += self.scorer.term(weight, sum);
+                }
+                sum = 0.0;
+                term = Some((scope.term, scope.weight));
+            }
+
+            let count = reader.field_count(index, scope.field.as_deref());
+            sum += self
+                .scorer
+                .posting(scope.stats, *entry.get_frequency(), count);
+        }
+
+        match term {
+            Some((_, weight)) => total + self.scorer.term(weight, sum),
+            None => total,
+        }
+    }
+}
+
+/// Position in `order` of the first scope at which the summed bounds
+/// exceed the threshold, the first scope without a threshold.
+fn pivot<T>(scopes: &[Scope<'_, T>], order: &[usize], threshold: Option<f32>) -> Option<usize> {
+    let Some(threshold) = threshold else {
+        return (!order.is_empty()).then_some(0);
+    };
+
+    let mut bound = 0.0;
+    order.iter().position(|&scope| {
+        bound += scopes[scope].max_score;
+        bound > threshold
+    })
+}
+
+/// Terms of the expression if it is a term, or a disjunction of terms
+/// possibly restricted to fields, `None` otherwise.
+pub fn terms(expr: &Expr) -> Option<Vec<String>> {
+    let mut terms = Vec::new();
+    disjunction(expr, &mut terms).then_some(terms)
+}
+
+/// Collects the terms of a disjunction, `false` for any other expression.
+fn disjunction(expr: &Expr, terms: &mut Vec<String>) -> bool {
+    match expr {
+        Expr::Term(term) => {
+            terms.push(term.clone());
+            true
+        }
+        Expr::Or(clauses) => clauses.iter().all(|clause| disjunction(clause, terms)),
+        Expr::Field(name, expr) => disjunction(&expr.as_ref().clone().qualify(name), terms),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        aggregate::TopK,
+        facet::Facets,
+        field::FieldTokens,
+        index::{Index, Indexer},
+        query::{wand::Wand, Query},
+        score::{Bm25Scorer, Bm25fScorer, PostingScore, Score, Scorer, TfIdfScorer},
+        token::{Token, Tokens},
+    };
+
+    const WORDS: [&str; 8] = [
+        "rust", "java", "python", "tokio", "async", "runtime", "fast", "safe",
+    ];
+
+    // Deterministic pseudo-random documents, long enough to fill blocks.
+    fn corpus() -> Vec<Vec<&'static str>> {
+        let mut state = 0x2545_f491_u64;
+        let mut next = move |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound) as usize
+        };
+
+        (0..1000)
+            .map(|_| {
+                let len = 1 + next(12);
+                (0..len)
+                    .map(|_| WORDS[next(WORDS.len() as u64) * next(3) / 2])
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn test_index() -> Index<String> {
+        let mut index = Index::new(10, 8);
+
+        for (position, words) in corpus().into_iter().enumerate() {
+            let mut tokens = words.iter().map(Token::from).collect::<Tokens>();
+            index
+                .insert(position.to_string(), words.len(), &mut tokens)
+                .unwrap();
+        }
+
+        // Deleted resources are never hits.
+        for position in (0..1000).step_by(7) {
+            index.delete(position).unwrap();
+        }
+        index
+    }
+
+    fn field_index() -> Index<String> {
+        let mut index = Index::new(10, 8);
+
+        for (position, words) in corpus().into_iter().enumerate() {
+            let (title, body) = words.split_at(words.len() / 3);
+            let fields = [("title", title), ("body", body)]
+                .into_iter()
+                .filter(|(_, words)| !words.is_empty())
+                .map(|(name, words)| {
+                    let tokens = words.iter().map(Token::from).collect::<Tokens>();
+                    FieldTokens::new(name, words.len(), tokens)
+                })
+                .collect();
+            index
+                .insert_fields(position.to_string(), fields, Facets::new())
+                .unwrap();
+        }
+        index
+    }
+
+    fn assert_exhaustive<'a, S>(new: impl Fn() -> S, query: &str, k: usize)
+    where
+        S: PostingScore<'a> + 'a,
+    {
+        let expr = Query::new(query).parse().unwrap();
+        let expected = {
+            let mut top = TopK::new(k);
+            top.extend(Scorer::new(new()).evaluate(&expr));
+            top.into_sorted()
+        };
+        let scorer = new();
+
+        for block_max in [false, true] {
+            let wand = Wand::new(&scorer).with_block_max(block_max);
+            let actual = wand.evaluate(&expr, k).unwrap();
+
+            assert_eq!(actual, expected, "{query}, k = {k}, block-max: {block_max}");
+        }
+    }
+
+    const QUERIES: [&str; 5] = [
+        "rust",
+        "rust java",
+        "python tokio async",
+        "rust OR fast OR safe OR missing",
+        "title:rust body:java runtime",
+    ];
+
+    #[test]
+    fn test_wand_tfidf() {
+        let index = test_index();
+        let reader = index.reader();
+
+        for query in QUERIES {
+            for k in [1, 10, 100, 2000] {
+                assert_exhaustive(|| TfIdfScorer::new(&reader), query, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wand_bm25() {
+        for index in [test_index(), field_index()] {
+            let reader = index.reader();
+
+            for query in QUERIES {
+                for k in [1, 10, 100, 2000] {
+                    assert_exhaustive(|| Bm25Scorer::new(&reader), query, k);
+                    let custom = || Bm25Scorer::with_params(&reader, 2.0, 0.3);
+                    assert_exhaustive(custom, query, k);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wand_bm25f() {
+        for index in [test_index(), field_index()] {
+            let reader = index.reader();
+
+            for query in QUERIES {
+                for k in [1, 10, 100, 2000] {
+                    assert_exhaustive(|| Bm25fScorer::new(&reader), query, k);
+                    let boosted = || Bm25fScorer::new(&reader).with_boost("title", 2.5);
+                    assert_exhaustive(boosted, query, k);
+                }
+            }
+        }
+
+        let index = field_index();
+        let reader = index.reader();
+        let scorer = Bm25fScorer::new(&reader);
+        let expr = Query::new("rust python").parse().unwrap();
+
+        let exhaustive = Scorer::new(Bm25fScorer::new(&reader)).evaluate(&expr).len();
+        let wand = Wand::new(&scorer);
+        wand.evaluate(&expr, 10).unwrap();
+        assert!(wand.scored() < exhaustive);
+    }
+
+    #[test]
+    fn test_wand_pruning() {
+        let index = test_index();
+        let reader = index.reader();
+        let scorer = Bm25Scorer::new(&reader);
+        let expr = Query::new("rust python").parse().unwrap();
+
+        let exhaustive = Scorer::new(Bm25Scorer::new(&reader)).evaluate(&expr).len();
+        let wand = Wand::new(&scorer);
+        wand.evaluate(&expr, 10).unwrap();
+
+        assert!(wand.scored() < exhaustive);
+        assert_eq!(wand.evaluate(&expr, 0), Some(Vec::new()));
+
+        // Only disjunctions of terms are evaluated.
+        for query in ["rust AND java", "rust -java", "\"rust java\"", "ru*"] {
+            let expr = Query::new(query).parse().unwrap();
+            assert!(wand.evaluate(&expr, 10).is_none());
+        }
+    }
+}
@@ -1,15 +1,21 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, hash::Hash};
 
-use crate::core::{IdfEntry, InvertedIndex, Resource, Store, TermCounter};
+use crate::{
+    core::{IdfEntry, InvertedIndex, RefEntry, Resource, Store, TermCounter},
+    dictionary::Dictionary,
+    facet::Facets,
+    field,
+    posting::PostingCursor,
+};
 
 #[derive(Debug)]
-pub struct IndexReader<'r, R: Clone + Debug> {
+pub struct IndexReader<'r, R: Clone + Debug + Hash + Eq> {
     store: &'r Store<R>,
     index: &'r InvertedIndex,
     count: &'r TermCounter,
 }
 
-impl<'r, R: Clone + Debug> IndexReader<'r, R> {
+impl<'r, R: Clone + Debug + Hash + Eq> IndexReader<'r, R> {
     pub fn new(store: &'r Store<R>, index: &'r InvertedIndex, count: &'r TermCounter) -> Self {
         Self {
             store,
@@ -19,34 +25,115 @@ impl<'r, R: Clone + Debug> IndexReader<'r, R> {
     }
 }
 
-impl<'r, R: Clone + Debug> IndexReader<'r, R> {
-    /// Number of indexed documents
+impl<'r, R: Clone + Debug + Hash + Eq> IndexReader<'r, R> {
+    /// Number of indexed documents, excluding deleted ones
     #[inline]
     pub fn total_documents(&self) -> usize {
-        self.store.len()
+        self.store.live()
+    }
+
+    /// Whether the resource is deleted
+    #[inline]
+    pub fn is_deleted(&self, index: usize) -> bool {
+        self.store.is_deleted(index)
+    }
+
+    /// Average word count of the indexed documents
+    #[inline]
+    pub fn average_count(&self) -> f32 {
+        self.store.average_count()
+    }
+
+    /// Average word count of the field, `None` is the default field
+    #[inline]
+    pub fn average_field_count(&self, field: Option<&str>) -> f32 {
+        self.store.average_field_count(field)
+    }
+
+    /// Lower bound of the word count of the field, `None` is the default field
+    #[inline]
+    pub fn min_field_count(&self, field: Option<&str>) -> usize {
+        self.store.min_field_count(field)
+    }
+
+    /// Names of the indexed fields, in order
+    #[inline]
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.store.fields()
     }
 
     // TODO:
     // - Define the TF-IDF ops in trait
     // - Whether to return Option<T> or concrete type?
 
-    /// Number of documents containing the term
+    /// Number of documents containing the term, excluding deleted ones
     #[inline]
     pub fn document_frequency(&self, term: &str) -> Option<usize> {
-        self.index.get_entry_with(term, |entry| entry.count())
+        let entry = self.index.get_term_entries(term)?;
+
+        if !self.store.has_tombstones() {
+            return Some(entry.count());
+        }
+
+        let count = entry
+            .iter()
+            .filter(|entry| !self.is_deleted(entry.get_index()))
+            .count();
+
+        (count > 0).then_some(count)
+    }
+
+    /// Whether the index records term positions
+    #[inline]
+    pub fn has_positions(&self) -> bool {
+        self.index.has_positions()
+    }
+
+    /// Positions of the term in the resource, in increasing order
+    #[inline]
+    pub fn get_positions(&self, term: &str, index: usize) -> Option<Vec<u32>> {
+        self.index.get_positions(term, index)
     }
 
     /// Get indexed entries for a term
     #[inline]
-    pub fn get_term_entries(&self, term: &str) -> Option<&IdfEntry> {
+    pub fn dictionary(&self) -> &Dictionary {
+        self.index.dictionary()
+    }
+
+    #[inline]
+    pub fn get_term_entries(&self, term: &str) -> Option<IdfEntry<'_>> {
         self.index.get_term_entries(term)
     }
 
+    /// Index of the live resource with the given key
+    #[inline]
+    pub fn lookup(&self, key: &R) -> Option<usize> {
+        self.store.lookup(key)
+    }
+
     #[inline]
     pub fn get_index(&self, index: usize) -> Option<&Resource<R>> {
         self.store.get(index)
     }
 
+    /// Iterates over the indexed resources, ordered by index.
+    ///
+    /// Deleted resources are included, see [`IndexReader::is_deleted`].
+    #[inline]
+    pub fn resources(&self) -> impl Iterator<Item = &Resource<R>> {
+        self.store.iter()
+    }
+
+    /// Iterates over every indexed term, in order.
+    #[inline]
+    pub fn terms(&self) -> impl Iterator<Item = (&str, IdfEntry<'_>)> {
+        self.index.iter()
+    }
+
+    /// # Safety
+    /// The caller ensures that the term was inserted into the counter.
+    ///
     /// # Panics
     /// If no term exists in the index.
     #[inline]
@@ -56,11 +143,11 @@ impl<'r, R: Clone + Debug> IndexReader<'r, R> {
 }
 
 #[derive(Debug)]
-pub struct ReaderContext<'rctx, R: Clone + Debug> {
+pub struct ReaderContext<'rctx, R: Clone + Debug + Hash + Eq> {
     reader: IndexReader<'rctx, R>,
 }
 
-impl<'rctx, R: Clone + Debug> ReaderContext<'rctx, R> {
+impl<'rctx, R: Clone + Debug + Hash + Eq> ReaderContext<'rctx, R> {
     pub fn new(reader: IndexReader<'rctx, R>) -> Self {
         Self { reader }
     }
@@ -75,11 +162,67 @@ impl<'rctx, R: Clone + Debug> ReaderContext<'rctx, R> {
         self.reader.document_frequency(term)
     }
 
+    #[inline]
+    pub fn average_count(&self) -> f32 {
+        self.reader.average_count()
+    }
+
+    /// Average word count of the field, `None` is the default field.
+    #[inline]
+    pub fn average_field_count(&self, field: Option<&str>) -> f32 {
+        self.reader.average_field_count(field)
+    }
+
+    /// Lower bound of the word count of the field in the resources
+    /// containing it, `None` is the default field.
+    #[inline]
+    pub fn min_field_count(&self, field: Option<&str>) -> usize {
+        self.reader.min_field_count(field)
+    }
+
+    /// Names of the fields of the indexed resources, in order.
+    ///
+    /// The default field is not included.
+    #[inline]
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.reader.fields()
+    }
+
+    /// Fields a key is searched in, with the term in each field.
+    ///
+    /// A qualified key is only searched in its own field, a plain term in
+    /// the default field, `None`, and in every named field.
+    pub fn fields_of<'k>(&'k self, key: &'k str) -> Vec<(Option<&'k str>, &'k str)> {
+        match field::split(key) {
+            (Some(field), term) => vec![(Some(field), term)],
+            (None, term) => std::iter::once(None)
+                .chain(self.fields().map(Some))
+                .map(|field| (field, term))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    pub fn is_deleted(&self, index: usize) -> bool {
+        self.reader.is_deleted(index)
+    }
+
+    /// Returns the resource, unless it is deleted.
     #[inline]
     pub fn get_resource(&self, index: usize) -> Option<R> {
+        if self.is_deleted(index) {
+            return None;
+        }
+
         self.store().get_path(index)
     }
 
+    /// Index of the live resource with the given key, e.g. a path or URL.
+    #[inline]
+    pub fn lookup(&self, key: &R) -> Option<usize> {
+        self.reader.lookup(key)
+    }
+
     // Always greater than zero,
     // since empty documents are not indexed.
     #[inline]
@@ -87,6 +230,12 @@ impl<'rctx, R: Clone + Debug> ReaderContext<'rctx, R> {
         self.store().get(index).unwrap().count()
     }
 
+    /// Word count of the field in the resource, `None` is the default field.
+    #[inline]
+    pub fn field_count(&self, index: usize, field: Option<&str>) -> usize {
+        self.store().get(index).unwrap().field_count(field)
+    }
+
     #[inline]
     fn inverted_index(&self) -> &InvertedIndex {
         self.reader.index
@@ -99,10 +248,64 @@ impl<'rctx, R: Clone + Debug> ReaderContext<'rctx, R> {
 
     // low-level function to retrieve `IdfEntry`, if exists.
     #[inline]
-    pub fn get_entry(&self, term: &str) -> Option<&IdfEntry> {
+    pub fn get_entry(&self, term: &str) -> Option<IdfEntry<'_>> {
         self.reader.get_term_entries(term)
     }
 
+    /// Postings of the term, excluding deleted resources.
+    #[inline]
+    pub fn postings(&self, term: &str) -> Option<impl Iterator<Item = RefEntry> + '_> {
+        let entry = self.get_entry(term)?;
+        Some(
+            entry
+                .iter()
+                .filter(|entry| !self.is_deleted(entry.get_index())),
+        )
+    }
+
+    /// Cursor over the postings of the term, ordered by resource index.
+    ///
+    /// Deleted resources are included, see [`ReaderContext::is_deleted`].
+    #[inline]
+    pub fn cursor(&self, term: &str) -> Option<PostingCursor<'_>> {
+        self.get_entry(term).map(|entry| entry.iter())
+    }
+
+    /// Positions of the term in the resource, in increasing order.
+    ///
+    /// Returns `None` if the index does not record positions,
+    /// the resource is deleted or does not contain the term.
+    #[inline]
+    pub fn positions(&self, term: &str, index: usize) -> Option<impl Iterator<Item = u32> + '_> {
+        if self.is_deleted(index) {
+            return None;
+        }
+
+        let positions = self.reader.get_positions(term, index)?;
+        Some(positions.into_iter())
+    }
+
+    #[inline]
+    pub fn has_positions(&self) -> bool {
+        self.reader.has_positions()
+    }
+
+    /// Facets of the resource, `None` if it is deleted.
+    #[inline]
+    pub fn facets(&self, index: usize) -> Option<&Facets> {
+        if self.is_deleted(index) {
+            return None;
+        }
+
+        self.store().get(index).map(Resource::facets)
+    }
+
+    /// Sorted terms, including terms only found in deleted resources.
+    #[inline]
+    pub fn dictionary(&self) -> &Dictionary {
+        self.reader.dictionary()
+    }
+
     // low-level function to perform read operations on the entry, if exists.
     #[inline]
     pub fn get_entry_with<O>(&self, term: &str, f: impl FnOnce(&IdfEntry) -> O) -> Option<O> {
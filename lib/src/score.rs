@@ -1,19 +1,68 @@
-use std::{fmt::Debug, marker::PhantomData};
+use std::{fmt::Debug, hash::Hash, marker::PhantomData};
 
-use crate::{reader::ReaderContext, token::Token};
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    explain::Explanation, field, query::eval::DEFAULT_MAX_EXPANSIONS, rank::BM25Inner,
+    reader::ReaderContext, token::Token,
+};
 
 pub trait Score<'a>: Debug {
-    type Item: Clone + Debug;
+    type Item: Clone + Debug + Hash + Eq;
     type Key;
     type Value;
 
     fn new(strategy: &'a ReaderContext<'a, Self::Item>) -> Self;
     fn score(&self, term: &str) -> Option<Vec<(Self::Key, Self::Value)>>;
+
+    /// Explains the score of the term in the resource, `None` if the
+    /// resource does not contain the term or is deleted.
+    fn explain(&self, term: &str, index: usize) -> Option<Explanation>;
+
+    /// Reader the terms are scored from.
+    fn reader(&self) -> &'a ReaderContext<'a, Self::Item>;
 }
 
+/// A [`Score`] made of an independent score per posting, summed across
+/// the fields of a term, see [`Wand`](crate::query::wand::Wand).
+///
+/// Scores must not decrease with the frequency of the term, nor increase
+/// with the word count of the field, so the highest frequency and the
+/// shortest field bound the scores of a set of postings.
+pub trait PostingScore<'a>: Score<'a, Key = usize, Value = f32> {
+    /// Statistics of a term shared by its postings, e.g. its idf.
+    type Stats: Copy + Debug;
+
+    /// Statistics of the key in the field, `None` if it is not indexed.
+    fn stats(&self, key: &str, field: Option<&str>) -> Option<Self::Stats>;
+
+    /// Score of a posting of the term, in a field of `word_count` words.
+    fn posting(&self, stats: Self::Stats, frequency: usize, word_count: usize) -> f32;
+
+    /// Weight of the term shared by its fields, see [`PostingScore::term`].
+    #[inline]
+    fn weight(&self, _term: &str) -> f32 {
+        1.0
+    }
+
+    /// Score of the term from the sum of the scores of its postings across
+    /// fields, the sum itself by default.
+    ///
+    /// Must not decrease with the sum, nor exceed the sum of the scores of
+    /// the parts of the sum, so the bounds of the fields still add up.
+    #[inline]
+    fn term(&self, _weight: f32, sum: f32) -> f32 {
+        sum
+    }
+}
+
+/// Scored entries produced by a [`Score`] implementation for a single term.
+pub type Scores<'a, S> = Vec<(<S as Score<'a>>::Key, <S as Score<'a>>::Value)>;
+
 #[derive(Debug)]
 pub struct Scorer<'a, S: Score<'a>> {
     inner: S,
+    max_expansions: usize,
     _marker: PhantomData<&'a S>,
 }
 
@@ -21,14 +70,37 @@ impl<'a, S: Score<'a>> Scorer<'a, S> {
     pub fn new(strategy: S) -> Self {
         Self {
             inner: strategy,
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
             _marker: PhantomData,
         }
     }
 
+    /// Caps the number of indexed terms a fuzzy or wildcard term expands to.
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    #[inline]
+    pub fn max_expansions(&self) -> usize {
+        self.max_expansions
+    }
+
     pub fn score(&self, term: &str) -> Option<Vec<(S::Key, S::Value)>> {
         self.inner.score(term)
     }
 
+    /// Explains the score of a single term, see [`Score::explain`].
+    #[inline]
+    pub fn explain_term(&self, term: &str, index: usize) -> Option<Explanation> {
+        self.inner.explain(term, index)
+    }
+
+    #[inline]
+    pub fn reader(&self) -> &'a ReaderContext<'a, S::Item> {
+        self.inner.reader()
+    }
+
     pub fn score_and_apply<F>(&mut self, mut f: F, tokens: impl IntoIterator<Item = Token>)
     where
         F: FnMut(Vec<(S::Key, S::Value)>),
@@ -40,20 +112,17 @@ impl<'a, S: Score<'a>> Scorer<'a, S> {
         }
     }
 
-    pub fn from_tokens(
-        &self,
-        tokens: impl Iterator<Item = Token>,
-    ) -> Vec<Option<Vec<(S::Key, S::Value)>>> {
+    pub fn from_tokens(&self, tokens: impl Iterator<Item = Token>) -> Vec<Option<Scores<'a, S>>> {
         tokens.map(|token| self.inner.score(&token)).collect::<_>()
     }
 }
 
 #[derive(Debug)]
-pub struct TfIdfScorer<'a, R: Clone + Debug> {
+pub struct TfIdfScorer<'a, R: Clone + Debug + Hash + Eq> {
     reader: &'a ReaderContext<'a, R>,
 }
 
-impl<R: Clone + Debug> TfIdfScorer<'_, R> {
+impl<R: Clone + Debug + Hash + Eq> TfIdfScorer<'_, R> {
     // FIXME: Need more robust conversion mechanism.
     pub fn tf(&self, frequency: usize, word_count: usize) -> f32 {
         frequency as f32 / word_count as f32
@@ -64,7 +133,7 @@ impl<R: Clone + Debug> TfIdfScorer<'_, R> {
     }
 }
 
-impl<'a, R: Clone + Debug> Score<'a> for TfIdfScorer<'a, R> {
+impl<'a, R: Clone + Debug + Hash + Eq> Score<'a> for TfIdfScorer<'a, R> {
     type Item = R;
     type Key = usize;
     type Value = f32;
@@ -73,29 +142,750 @@ impl<'a, R: Clone + Debug> Score<'a> for TfIdfScorer<'a, R> {
         Self { reader }
     }
 
+    #[inline]
+    fn reader(&self) -> &'a ReaderContext<'a, R> {
+        self.reader
+    }
+
     fn score(&self, term: &str) -> Option<Vec<(Self::Key, Self::Value)>> {
+        across_fields(self.reader, term, |key, field| {
+            score_postings(self, key, field)
+        })
+    }
+
+    fn explain(&self, term: &str, index: usize) -> Option<Explanation> {
+        explain_fields(self.reader, term, |key, field| {
+            let frequency = frequency(self.reader, key, index)?;
+            let total_documents = self.reader.total_documents();
+            let document_frequency = self.reader.document_frequency(key)?;
+            let count = self.reader.field_count(index, field);
+
+            let idf = self.idf(total_documents, document_frequency);
+            let tf = self.tf(frequency, count);
+
+            let explanation = Explanation::new(
+                tf * idf,
+                format!("weight({}), tf * idf of:", field::display(key)),
+            )
+            .with_details(vec![
+                Explanation::new(tf, "tf, frequency / field length of:").with_details(vec![
+                    Explanation::count(frequency, "frequency"),
+                    Explanation::count(count, "field length"),
+                ]),
+                Explanation::new(idf, "idf, log10(documents / document frequency) of:")
+                    .with_details(vec![
+                        Explanation::count(total_documents, "documents"),
+                        Explanation::count(document_frequency, "document frequency"),
+                    ]),
+            ]);
+
+            Some(explanation)
+        })
+    }
+}
+
+impl<'a, R: Clone + Debug + Hash + Eq> PostingScore<'a> for TfIdfScorer<'a, R> {
+    /// Idf of the term.
+    type Stats = f32;
+
+    fn stats(&self, key: &str, _: Option<&str>) -> Option<Self::Stats> {
         let total_documents = self.reader.total_documents();
+        let document_frequency = self.reader.document_frequency(key)?;
+
+        Some(self.idf(total_documents, document_frequency))
+    }
+
+    #[inline]
+    fn posting(&self, idf: Self::Stats, frequency: usize, word_count: usize) -> f32 {
+        self.tf(frequency, word_count) * idf
+    }
+}
+
+#[derive(Debug)]
+pub struct Bm25Scorer<'a, R: Clone + Debug + Hash + Eq> {
+    reader: &'a ReaderContext<'a, R>,
+    inner: BM25Inner,
+}
+
+impl<'a, R: Clone + Debug + Hash + Eq> Bm25Scorer<'a, R> {
+    /// Creates a scorer with custom term frequency saturation (`k1`)
+    /// and document length normalization (`b`).
+    pub fn with_params(reader: &'a ReaderContext<'a, R>, k1: f32, b: f32) -> Self {
+        Self {
+            reader,
+            inner: BM25Inner::new(k1, b),
+        }
+    }
+
+    #[inline]
+    pub fn k1(&self) -> f32 {
+        self.inner.k1
+    }
 
-        self.reader.get_entry_with(term, |idf_entry| {
-            debug_assert!(idf_entry.count() > 0);
-            let document_frequency = idf_entry.count();
-
-            idf_entry
-                .iter()
-                .map(|ref_entry| {
-                    let index = ref_entry.get_index();
-                    let frequency = *ref_entry.get_frequency();
-
-                    // Always greater than zero, empty documents are not indexed.
-                    let count = self.reader.count(index);
-                    debug_assert!(count > 0);
-
-                    let tf = self.tf(frequency, count);
-                    let idf = self.idf(total_documents, document_frequency);
-                    let tfidf = tf * idf;
-                    (index, tfidf)
-                })
-                .collect::<Vec<_>>()
+    #[inline]
+    pub fn b(&self) -> f32 {
+        self.inner.b
+    }
+}
+
+impl<'a, R: Clone + Debug + Hash + Eq> Score<'a> for Bm25Scorer<'a, R> {
+    type Item = R;
+    type Key = usize;
+    type Value = f32;
+
+    fn new(reader: &'a ReaderContext<'a, R>) -> Self {
+        Self::with_params(reader, BM25Inner::K1, BM25Inner::B)
+    }
+
+    #[inline]
+    fn reader(&self) -> &'a ReaderContext<'a, R> {
+        self.reader
+    }
+
+    fn score(&self, term: &str) -> Option<Vec<(Self::Key, Self::Value)>> {
+        across_fields(self.reader, term, |key, field| {
+            score_postings(self, key, field)
+        })
+    }
+
+    fn explain(&self, term: &str, index: usize) -> Option<Explanation> {
+        explain_fields(self.reader, term, |key, field| {
+            let frequency = frequency(self.reader, key, index)?;
+            let stats @ (idf, avg_word_count) = self.stats(key, field)?;
+            let document_frequency = self.reader.document_frequency(key)?;
+            let count = self.reader.field_count(index, field);
+
+            let normalization = self.inner.normalization(count, avg_word_count);
+            let score = self.posting(stats, frequency, count);
+
+            let explanation = Explanation::new(
+                score,
+                format!("weight({}), idf * tf of:", field::display(key)),
+            )
+            .with_details(vec![
+                explain_idf(self.reader.total_documents(), document_frequency, idf),
+                Explanation::new(
+                    score / idf,
+                    "tf, frequency * (k1 + 1) / (frequency + k1 * length normalization) of:",
+                )
+                .with_details(vec![
+                    Explanation::count(frequency, "frequency"),
+                    Explanation::new(self.k1(), "k1"),
+                    explain_normalization(self.b(), count, avg_word_count, normalization),
+                ]),
+            ]);
+
+            Some(explanation)
         })
     }
 }
+
+impl<'a, R: Clone + Debug + Hash + Eq> PostingScore<'a> for Bm25Scorer<'a, R> {
+    /// Idf of the term, and average word count of the field.
+    type Stats = (f32, f32);
+
+    fn stats(&self, key: &str, field: Option<&str>) -> Option<Self::Stats> {
+        let total_documents = self.reader.total_documents();
+        let document_frequency = self.reader.document_frequency(key)?;
+        let idf = self.inner.idf(total_documents, document_frequency);
+
+        Some((idf, self.reader.average_field_count(field)))
+    }
+
+    #[inline]
+    fn posting(
+        &self,
+        (idf, avg_word_count): Self::Stats,
+        frequency: usize,
+        word_count: usize,
+    ) -> f32 {
+        self.inner
+            .calculate(frequency, word_count, avg_word_count, idf)
+    }
+}
+
+/// BM25F, BM25 over the fields of the resources.
+///
+/// The frequencies of a term in each field are normalized by the length of
+/// the field, weighted by the boost of the field and summed before being
+/// saturated, so a term repeated across fields does not score as several
+/// terms. The document frequency is the number of resources containing the
+/// term in any of the searched fields.
+#[derive(Debug)]
+pub struct Bm25fScorer<'a, R: Clone + Debug + Hash + Eq> {
+    reader: &'a ReaderContext<'a, R>,
+    inner: BM25Inner,
+
+    /// Boost of each field, the default field is the empty name.
+    boosts: HashMap<String, f32>,
+}
+
+impl<'a, R: Clone + Debug + Hash + Eq> Bm25fScorer<'a, R> {
+    /// Creates a scorer with custom term frequency saturation (`k1`)
+    /// and field length normalization (`b`).
+    pub fn with_params(reader: &'a ReaderContext<'a, R>, k1: f32, b: f32) -> Self {
+        Self {
+            reader,
+            inner: BM25Inner::new(k1, b),
+            boosts: HashMap::new(),
+        }
+    }
+
+    /// Weights the field, fields are weighted `1.0` by default.
+    pub fn with_boost(mut self, field: impl Into<String>, boost: f32) -> Self {
+        self.boosts.insert(field.into(), boost);
+        self
+    }
+
+    /// Boost of the field, `None` is the default field.
+    #[inline]
+    pub fn boost(&self, field: Option<&str>) -> f32 {
+        let field = field.unwrap_or_default();
+        self.boosts.get(field).copied().unwrap_or(1.0)
+    }
+
+    #[inline]
+    pub fn k1(&self) -> f32 {
+        self.inner.k1
+    }
+
+    #[inline]
+    pub fn b(&self) -> f32 {
+        self.inner.b
+    }
+
+    /// Number of resources containing the term in any of its fields.
+    fn document_frequency(&self, term: &str) -> usize {
+        let mut resources = HashSet::new();
+
+        for (field, term) in self.reader.fields_of(term) {
+            let key = field::qualify(field.unwrap_or_default(), term);
+            if let Some(postings) = self.reader.postings(&key) {
+                resources.extend(postings.map(|ref_entry| ref_entry.get_index()));
+            }
+        }
+
+        resources.len()
+    }
+}
+
+impl<'a, R: Clone + Debug + Hash + Eq> Score<'a> for Bm25fScorer<'a, R> {
+    type Item = R;
+    type Key = usize;
+    type Value = f32;
+
+    fn new(reader: &'a ReaderContext<'a, R>) -> Self {
+        Self::with_params(reader, BM25Inner::K1, BM25Inner::B)
+    }
+
+    #[inline]
+    fn reader(&self) -> &'a ReaderContext<'a, R> {
+        self.reader
+    }
+
+    fn score(&self, term: &str) -> Option<Vec<(Self::Key, Self::Value)>> {
+        // Weighted and normalized frequency of the term in each resource.
+        let mut frequencies = HashMap::<usize, f32>::new();
+
+        for (field, term) in self.reader.fields_of(term) {
+            let key = field::qualify(field.unwrap_or_default(), term);
+            let Some(postings) = self.reader.postings(&key) else {
+                continue;
+            };
+
+            let stats = (self.boost(field), self.reader.average_field_count(field));
+
+            for ref_entry in postings {
+                let index = ref_entry.get_index();
+                let frequency = *ref_entry.get_frequency();
+                let count = self.reader.field_count(index, field);
+
+                *frequencies.entry(index).or_default() += self.posting(stats, frequency, count);
+            }
+        }
+
+        if frequencies.is_empty() {
+            return None;
+        }
+
+        let idf = self
+            .inner
+            .idf(self.reader.total_documents(), frequencies.len());
+
+        let scores = frequencies
+            .into_iter()
+            .map(|(index, tf)| (index, self.term(idf, tf)))
+            .collect::<Vec<_>>();
+
+        Some(scores)
+    }
+
+    fn explain(&self, term: &str, index: usize) -> Option<Explanation> {
+        let mut fields = Vec::new();
+        let mut tf = 0.0;
+
+        // Resources containing the term in any field.
+        let mut resources = HashSet::new();
+
+        for (field, term) in self.reader.fields_of(term) {
+            let key = field::qualify(field.unwrap_or_default(), term);
+            let Some(postings) = self.reader.postings(&key) else {
+                continue;
+            };
+            resources.extend(postings.map(|ref_entry| ref_entry.get_index()));
+
+            let Some(frequency) = frequency(self.reader, &key, index) else {
+                continue;
+            };
+
+            let boost = self.boost(field);
+            let count = self.reader.field_count(index, field);
+            let avg_word_count = self.reader.average_field_count(field);
+            let normalization = self.inner.normalization(count, avg_word_count);
+
+            let weighted = boost * frequency as f32 / normalization;
+            tf += weighted;
+
+            fields.push(
+                Explanation::new(
+                    weighted,
+                    format!(
+                        "{}, boost * frequency / length normalization of:",
+                        field.unwrap_or("default field")
+                    ),
+                )
+                .with_details(vec![
+                    Explanation::new(boost, "boost"),
+                    Explanation::count(frequency, "frequency"),
+                    explain_normalization(self.b(), count, avg_word_count, normalization),
+                ]),
+            );
+        }
+
+        if fields.is_empty() {
+            return None;
+        }
+
+        let total_documents = self.reader.total_documents();
+        let idf = self.inner.idf(total_documents, resources.len());
+        let score = self.inner.saturate(tf, idf);
+
+        let explanation = Explanation::new(
+            score,
+            format!("weight({}), idf * tf of:", field::display(term)),
+        )
+        .with_details(vec![
+            explain_idf(total_documents, resources.len(), idf),
+            Explanation::new(score / idf, "tf, tf * (k1 + 1) / (k1 + tf) of:").with_details(vec![
+                Explanation::new(self.k1(), "k1"),
+                Explanation::new(tf, "tf, sum of fields:").with_details(fields),
+            ]),
+        ]);
+
+        Some(explanation)
+    }
+}
+
+impl<'a, R: Clone + Debug + Hash + Eq> PostingScore<'a> for Bm25fScorer<'a, R> {
+    /// Boost and average word count of the field.
+    type Stats = (f32, f32);
+
+    fn stats(&self, key: &str, field: Option<&str>) -> Option<Self::Stats> {
+        self.reader.document_frequency(key)?;
+        Some((self.boost(field), self.reader.average_field_count(field)))
+    }
+
+    /// Weighted and normalized frequency of the term in the field.
+    #[inline]
+    fn posting(
+        &self,
+        (boost, avg_word_count): Self::Stats,
+        frequency: usize,
+        word_count: usize,
+    ) -> f32 {
+        let normalization = self.inner.normalization(word_count, avg_word_count);
+        boost * frequency as f32 / normalization
+    }
+
+    /// Idf of the term, from the resources containing it in any field.
+    fn weight(&self, term: &str) -> f32 {
+        let total_documents = self.reader.total_documents();
+        self.inner
+            .idf(total_documents, self.document_frequency(term))
+    }
+
+    /// Saturated sum of the fields, saturation is concave so it never
+    /// exceeds the sum of the saturated fields.
+    #[inline]
+    fn term(&self, idf: f32, tf: f32) -> f32 {
+        self.inner.saturate(tf, idf)
+    }
+}
+
+/// Scores every live posting of the key in the field.
+fn score_postings<'a, S>(scorer: &S, key: &str, field: Option<&str>) -> Option<Vec<(usize, f32)>>
+where
+    S: PostingScore<'a> + 'a,
+{
+    let reader = scorer.reader();
+    let stats = scorer.stats(key, field)?;
+
+    let scores = reader
+        .postings(key)?
+        .map(|ref_entry| {
+            let index = ref_entry.get_index();
+            let frequency = *ref_entry.get_frequency();
+
+            // Always greater than zero, the resource contains the term.
+            let count = reader.field_count(index, field);
+            debug_assert!(count > 0);
+
+            (index, scorer.posting(stats, frequency, count))
+        })
+        .collect::<Vec<_>>();
+
+    Some(scores)
+}
+
+/// Frequency of the key in the resource, `None` if the resource is
+/// deleted or does not contain the key.
+fn frequency<R>(reader: &ReaderContext<'_, R>, key: &str, index: usize) -> Option<usize>
+where
+    R: Clone + Debug + Hash + Eq,
+{
+    if reader.is_deleted(index) {
+        return None;
+    }
+
+    reader
+        .cursor(key)?
+        .advance_to(index)
+        .filter(|entry| entry.get_index() == index)
+        .map(|entry| *entry.get_frequency())
+}
+
+fn explain_idf(total_documents: usize, document_frequency: usize, idf: f32) -> Explanation {
+    Explanation::new(
+        idf,
+        "idf, log10((documents - document frequency + 0.5) / (document frequency + 0.5) + 1) of:",
+    )
+    .with_details(vec![
+        Explanation::count(total_documents, "documents"),
+        Explanation::count(document_frequency, "document frequency"),
+    ])
+}
+
+fn explain_normalization(
+    b: f32,
+    word_count: usize,
+    avg_word_count: f32,
+    normalization: f32,
+) -> Explanation {
+    Explanation::new(
+        normalization,
+        "length normalization, 1 - b + b * field length / average field length of:",
+    )
+    .with_details(vec![
+        Explanation::new(b, "b"),
+        Explanation::count(word_count, "field length"),
+        Explanation::new(avg_word_count, "average field length"),
+    ])
+}
+
+/// Explains the key with `f` in each field it is searched in, like
+/// [`across_fields`].
+fn explain_fields<R, F>(reader: &ReaderContext<'_, R>, key: &str, mut f: F) -> Option<Explanation>
+where
+    R: Clone + Debug + Hash + Eq,
+    F: FnMut(&str, Option<&str>) -> Option<Explanation>,
+{
+    let fields = reader.fields_of(key);
+
+    if let [(field, _)] = fields.as_slice() {
+        return f(key, *field);
+    }
+
+    let details = fields
+        .into_iter()
+        .filter_map(|(field, term)| f(&field::qualify(field.unwrap_or_default(), term), field))
+        .collect::<Vec<_>>();
+
+    (!details.is_empty())
+        .then(|| Explanation::sum(format!("{}, sum of fields:", field::display(key)), details))
+}
+
+/// Scores the key with `f` in each field it is searched in, see
+/// [`ReaderContext::fields_of`], summing the scores of every resource.
+///
+/// `f` is called with the key of the term in the field and the field,
+/// `None` if the term is not found in any field.
+fn across_fields<R, F>(
+    reader: &ReaderContext<'_, R>,
+    key: &str,
+    mut f: F,
+) -> Option<Vec<(usize, f32)>>
+where
+    R: Clone + Debug + Hash + Eq,
+    F: FnMut(&str, Option<&str>) -> Option<Vec<(usize, f32)>>,
+{
+    let fields = reader.fields_of(key);
+
+    // A qualified key, or an index without named fields.
+    if let [(field, _)] = fields.as_slice() {
+        return f(key, *field);
+    }
+
+    let mut scores = HashMap::<usize, f32>::new();
+    let mut found = false;
+
+    for (field, term) in fields {
+        let Some(field_scores) = f(&field::qualify(field.unwrap_or_default(), term), field) else {
+            continue;
+        };
+
+        found = true;
+        for (index, score) in field_scores {
+            *scores.entry(index).or_default() += score;
+        }
+    }
+
+    found.then(|| scores.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        facet::Facets,
+        field::{self, FieldTokens},
+        index::{Index, Indexer},
+        query::eval::Matches,
+        score::{Bm25Scorer, Bm25fScorer, Score, TfIdfScorer},
+        tokens,
+    };
+
+    fn test_index() -> Index<String> {
+        let mut index = Index::new(10, 8);
+        index
+            .insert("short".into(), 2, &mut tokens!["rust", "fast"])
+            .unwrap();
+        index
+            .insert(
+                "long".into(),
+                6,
+                &mut tokens!["rust", "is", "a", "very", "long", "document"],
+            )
+            .unwrap();
+        index
+            .insert("other".into(), 1, &mut tokens!["java"])
+            .unwrap();
+        index
+    }
+
+    #[test]
+    fn test_bm25_average_count() {
+        let index = test_index();
+        let reader = index.reader();
+
+        assert_eq!(reader.average_count(), 3.0);
+    }
+
+    #[test]
+    fn test_bm25_prefers_shorter_documents() {
+        let index = test_index();
+        let reader = index.reader();
+        let scorer = Bm25Scorer::new(&reader);
+
+        let mut scores = scorer.score("rust").unwrap();
+        scores.sort_by_key(|(index, _)| *index);
+
+        assert_eq!(scores.len(), 2);
+        assert!(scores[0].1 > scores[1].1);
+        assert!(scorer.score("python").is_none());
+    }
+
+    #[test]
+    fn test_bm25_score_value() {
+        let index = test_index();
+        let reader = index.reader();
+        let scorer = Bm25Scorer::with_params(&reader, 1.5, 0.75);
+
+        let scores = scorer.score("java").unwrap();
+
+        // idf = log10((3 - 1 + 0.5) / (1 + 0.5) + 1)
+        // tf  = (1 * 2.5) / (1 + 1.5 * (0.25 + 0.75 * 1 / 3))
+        let idf = (2.5f32 / 1.5 + 1.0).log10();
+        let tf = 2.5 / (1.0 + 1.5 * (0.25 + 0.75 / 3.0));
+
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].0, 2);
+        assert!((scores[0].1 - idf * tf).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_bm25_explain() {
+        let index = test_index();
+        let reader = index.reader();
+        let scorer = Bm25Scorer::with_params(&reader, 1.5, 0.75);
+
+        let explanation = scorer.explain("java", 2).unwrap();
+        let idf = (2.5f32 / 1.5 + 1.0).log10();
+
+        assert_eq!(explanation.value, scorer.score("java").unwrap()[0].1);
+        assert_eq!(explanation.details[0].value, idf);
+        assert_eq!(explanation.find("frequency").unwrap().value, 1.0);
+        assert_eq!(explanation.find("k1").unwrap().value, 1.5);
+        assert_eq!(explanation.find("field length").unwrap().value, 1.0);
+        assert_eq!(explanation.find("average field length").unwrap().value, 3.0);
+        assert!(scorer.explain("java", 0).is_none());
+        assert!(scorer.explain("python", 0).is_none());
+
+        let tfidf = TfIdfScorer::new(&reader);
+        let explanation = tfidf.explain("rust", 1).unwrap();
+        assert_eq!(explanation.value, scores(&tfidf, "rust")[&1]);
+        assert_eq!(explanation.details[0].value, 1.0 / 6.0);
+    }
+
+    #[test]
+    fn test_bm25_without_length_normalization() {
+        let index = test_index();
+        let reader = index.reader();
+        let scorer = Bm25Scorer::with_params(&reader, 1.2, 0.0);
+
+        let scores = scorer.score("rust").unwrap();
+
+        assert_eq!(scores.len(), 2);
+        assert!((scores[0].1 - scores[1].1).abs() < 1e-6);
+    }
+
+    fn field_index() -> Index<String> {
+        let mut index = Index::new(10, 8);
+        let documents = [
+            ("a", tokens!["rust"], tokens!["a", "guide", "to", "java"]),
+            (
+                "b",
+                tokens!["java", "guide"],
+                tokens!["rust", "for", "java", "developers"],
+            ),
+            ("c", tokens!["python"], tokens!["snakes"]),
+        ];
+
+        for (resource, title, body) in documents {
+            let fields = vec![
+                FieldTokens::new("title", title.count(), title),
+                FieldTokens::new("body", body.count(), body),
+            ];
+            index
+                .insert_fields(resource.into(), fields, Facets::new())
+                .unwrap();
+        }
+        index
+    }
+
+    fn scores<'a>(scorer: &impl Score<'a, Key = usize, Value = f32>, term: &str) -> Matches {
+        scorer.score(term).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_field_lengths() {
+        let index = field_index();
+        let reader = index.reader();
+
+        assert_eq!(reader.fields().collect::<Vec<_>>(), ["body", "title"]);
+        assert_eq!(reader.field_count(1, Some("title")), 2);
+        assert_eq!(reader.field_count(1, Some("body")), 4);
+        assert_eq!(reader.field_count(1, None), 0);
+        assert_eq!(reader.average_field_count(Some("body")), 3.0);
+        assert_eq!(reader.average_field_count(Some("title")), 4.0 / 3.0);
+        assert_eq!(reader.average_count(), 13.0 / 3.0);
+    }
+
+    #[test]
+    fn test_bm25_fields() {
+        let index = field_index();
+        let reader = index.reader();
+        let scorer = Bm25Scorer::new(&reader);
+
+        // A plain term is scored in every field.
+        let title = scores(&scorer, &field::qualify("title", "rust"));
+        let body = scores(&scorer, &field::qualify("body", "rust"));
+        let rust = scores(&scorer, "rust");
+
+        assert_eq!(title.len(), 1);
+        assert_eq!(rust[&0], title[&0]);
+        assert_eq!(rust[&1], body[&1]);
+        assert!(scorer.score(&field::qualify("title", "snakes")).is_none());
+    }
+
+    #[test]
+    fn test_bm25f_single_field() {
+        let index = test_index();
+        let reader = index.reader();
+        let bm25 = Bm25Scorer::new(&reader);
+        let bm25f = Bm25fScorer::new(&reader);
+
+        // Without named fields, BM25F is BM25.
+        for term in ["rust", "java", "document"] {
+            let (expected, actual) = (scores(&bm25, term), scores(&bm25f, term));
+
+            assert_eq!(expected.len(), actual.len());
+            for (index, score) in expected {
+                assert!((actual[&index] - score).abs() < 1e-6);
+            }
+        }
+        assert!(bm25f.score("python").is_none());
+    }
+
+    #[test]
+    fn test_bm25f_boost() {
+        let index = field_index();
+        let reader = index.reader();
+
+        let title = Bm25fScorer::new(&reader).with_boost("title", 5.0);
+        let body = Bm25fScorer::new(&reader).with_boost("body", 5.0);
+
+        // "rust" is in the title of `a` and in the body of `b`.
+        let (title, body) = (scores(&title, "rust"), scores(&body, "rust"));
+        assert!(title[&0] > title[&1]);
+        assert!(body[&1] > body[&0]);
+
+        // A term repeated across fields saturates as a single term.
+        let bm25f = Bm25fScorer::new(&reader);
+        let java = scores(&bm25f, "java");
+        let bm25 = Bm25Scorer::new(&reader);
+        let summed = scores(&bm25, "java");
+        assert_eq!(java.len(), 2);
+        assert!(java[&1] < summed[&1]);
+    }
+
+    #[test]
+    fn test_bm25f_explain() {
+        let index = field_index();
+        let reader = index.reader();
+        let scorer = Bm25fScorer::new(&reader).with_boost("title", 5.0);
+
+        // "java" is in the title and the body of `b`.
+        let explanation = scorer.explain("java", 1).unwrap();
+        let fields = explanation
+            .find("tf, sum of fields:")
+            .unwrap()
+            .details
+            .iter()
+            .map(|field| (field.description.as_str(), field.details[0].value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(explanation.value, scores(&scorer, "java")[&1]);
+        assert_eq!(explanation.find("document frequency").unwrap().value, 2.0);
+        assert_eq!(
+            fields,
+            [
+                ("body, boost * frequency / length normalization of:", 1.0),
+                ("title, boost * frequency / length normalization of:", 5.0),
+            ]
+        );
+
+        let bm25 = Bm25Scorer::new(&reader);
+        let explanation = bm25.explain("rust", 1).unwrap();
+        assert_eq!(explanation.value, scores(&bm25, "rust")[&1]);
+        assert!(scorer.explain("java", 2).is_none());
+    }
+}
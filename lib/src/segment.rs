@@ -0,0 +1,382 @@
+//! Immutable on-disk segments.
+//!
+//! A segment is a point-in-time copy of a [`CoreIndex`], it contains the
+//! resources with their word counts and the term dictionary with postings.
+//!
+//! # Layout
+//!
+//! All integers are little-endian, byte strings are prefixed with their `u32` length.
+//!
+//! ```text
+//! magic "IDXS" | version: u32 | body length: u64 | body | crc32(body): u32
+//!
+//! body      := flags: u8, resources: u64, resource*, terms: u64, term*
+//! resource  := word count: u64, deleted: u8, codec bytes, facets, fields
+//! facets    := pairs: u64, (field bytes, value bytes)*
+//! fields    := fields: u64, (field bytes, word count: u64)*
+//! term      := term bytes, postings: u64, posting*
+//! posting   := index: u64, frequency: u64, [positions: u64, position: u32*]
+//! ```
+//!
+//! Positions are only written when the `FLAG_POSITIONS` flag is set.
+//!
+//! Terms are sorted and postings are ordered by resource index,
+//! so writing the same index twice produces identical segments.
+//!
+//! Deleted resources keep their slot, postings of deleted resources
+//! are persisted until the index is compacted.
+//!
+//! # Versions
+//!
+//! Segments of another [`VERSION`] are rejected with
+//! [`SegmentError::UnsupportedVersion`], they are not migrated. An index
+//! written by an older version must be rebuilt from its documents.
+
+use std::{
+    fmt::Debug,
+    fs::{self, File},
+    hash::Hash,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    codec::{Codec, Decoder, Encoder},
+    core::{Resource, TfEntry},
+    error::{Error, SegmentError},
+    index::CoreIndex,
+};
+
+/// Magic bytes at the start of every segment.
+pub const MAGIC: &[u8; 4] = b"IDXS";
+
+/// Current segment format version.
+pub const VERSION: u32 = 5;
+
+/// The segment records term positions.
+const FLAG_POSITIONS: u8 = 1;
+
+/// Serializes a [`CoreIndex`] into a segment.
+#[derive(Debug)]
+pub struct SegmentWriter<'c, R> {
+    codec: &'c dyn Codec<R>,
+}
+
+impl<'c, R: Clone + Debug + Hash + Eq> SegmentWriter<'c, R> {
+    pub fn new(codec: &'c dyn Codec<R>) -> Self {
+        Self { codec }
+    }
+
+    /// Encodes the index into an in-memory segment.
+    pub fn encode(&self, core: &CoreIndex<R>) -> Vec<u8> {
+        let reader = core.reader();
+        let mut body = Encoder::new();
+
+        let positions = reader.has_positions();
+        body.u8(if positions { FLAG_POSITIONS } else { 0 });
+
+        body.usize(reader.resources().count());
+        for (index, resource) in reader.resources().enumerate() {
+            body.usize(resource.count());
+            body.u8(reader.is_deleted(index) as u8);
+            body.resource(self.codec, resource.resource_ref());
+            body.facets(resource.facets());
+            body.fields(resource.fields());
+        }
+
+        let mut terms = reader.terms().collect::<Vec<_>>();
+        terms.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+        body.usize(terms.len());
+        for (term, idf_entry) in terms {
+            body.bytes(term.as_bytes());
+            body.usize(idf_entry.count());
+
+            // Postings are ordered by resource index.
+            let mut cursor = idf_entry.iter();
+            while let Some(entry) = cursor.next() {
+                body.usize(entry.get_index());
+                body.usize(*entry.get_frequency());
+
+                if positions {
+                    let positions = cursor.positions();
+                    body.usize(positions.len());
+                    positions.iter().for_each(|&position| body.u32(position));
+                }
+            }
+        }
+
+        let body = body.into_inner();
+        let mut segment = Encoder::new();
+        segment.raw(MAGIC);
+        segment.u32(VERSION);
+        segment.usize(body.len());
+        segment.raw(&body);
+        segment.u32(crc32fast::hash(&body));
+        segment.into_inner()
+    }
+
+    /// Writes the segment to `path`.
+    ///
+    /// The segment is first written to a temporary file next to `path`
+    /// and renamed once synced, so readers never observe a partial segment.
+    pub fn write<P: AsRef<Path>>(&self, path: P, core: &CoreIndex<R>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let buffer = self.encode(core);
+
+        let temporary = temporary_path(path);
+        let mut file = File::create(&temporary).map_err(SegmentError::from)?;
+        file.write_all(&buffer).map_err(SegmentError::from)?;
+        file.sync_all().map_err(SegmentError::from)?;
+
+        fs::rename(&temporary, path).map_err(SegmentError::from)?;
+        Ok(())
+    }
+}
+
+/// Deserializes a segment into a [`CoreIndex`].
+#[derive(Debug)]
+pub struct SegmentReader<'c, R> {
+    codec: &'c dyn Codec<R>,
+}
+
+impl<'c, R: Clone + Debug + Hash + Eq> SegmentReader<'c, R> {
+    pub fn new(codec: &'c dyn Codec<R>) -> Self {
+        Self { codec }
+    }
+
+    /// Decodes an in-memory segment.
+    pub fn decode(&self, bytes: &[u8]) -> Result<CoreIndex<R>, Error> {
+        let mut segment = Decoder::new(bytes);
+
+        if segment.raw(MAGIC.len())? != MAGIC {
+            return Err(SegmentError::InvalidMagic.into());
+        }
+
+        let version = segment.u32()?;
+        if version != VERSION {
+            return Err(SegmentError::UnsupportedVersion(version).into());
+        }
+
+        let len = segment.usize()?;
+        let body = segment.raw(len)?;
+        if segment.u32()? != crc32fast::hash(body) {
+            return Err(SegmentError::Checksum.into());
+        }
+
+        if !segment.is_empty() {
+            return Err(SegmentError::Corrupted("trailing bytes".into()).into());
+        }
+
+        self.decode_body(body)
+    }
+
+    /// Reads the segment stored at `path`.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<CoreIndex<R>, Error> {
+        let bytes = fs::read(path).map_err(SegmentError::from)?;
+        self.decode(&bytes)
+    }
+
+    fn decode_body(&self, body: &[u8]) -> Result<CoreIndex<R>, Error> {
+        let mut body = Decoder::new(body);
+
+        let flags = body.u8()?;
+        let positions = flags & FLAG_POSITIONS != 0;
+
+        let resources = body.usize()?;
+        let mut core = match positions {
+            true => CoreIndex::with_positions(resources),
+            false => CoreIndex::with_capacity(resources),
+        };
+        let mut writer = core.writer();
+
+        for _ in 0..resources {
+            let word_count = body.usize()?;
+            if word_count == 0 {
+                return Err(SegmentError::Corrupted("empty resource".into()).into());
+            }
+
+            let deleted = body.u8()? != 0;
+            let resource = Resource::new(body.resource(self.codec)?, word_count)
+                .with_facets(body.facets()?)
+                .with_fields(body.fields()?);
+
+            if resource
+                .fields()
+                .iter()
+                .map(|(_, count)| count)
+                .sum::<usize>()
+                > word_count
+            {
+                return Err(SegmentError::Corrupted("invalid field word counts".into()).into());
+            }
+
+            match deleted {
+                true => writer.insert_deleted_resource(resource),
+                false => writer.insert_resource(resource),
+            };
+        }
+
+        let terms = body.usize()?;
+        for _ in 0..terms {
+            let term = body.string()?;
+            let postings = body.usize()?;
+
+            for _ in 0..postings {
+                let index = body.usize()?;
+                let frequency = body.usize()?;
+
+                if index >= resources || frequency == 0 {
+                    return Err(
+                        SegmentError::Corrupted(format!("invalid posting for '{term}'")).into(),
+                    );
+                }
+
+                let positions = match positions {
+                    true => (0..body.usize()?)
+                        .map(|_| body.u32())
+                        .collect::<Result<Vec<_>, _>>()?,
+                    false => Vec::new(),
+                };
+
+                writer.push_term(term.clone(), TfEntry::new(index, frequency), &positions);
+            }
+        }
+
+        if !body.is_empty() {
+            return Err(SegmentError::Corrupted("trailing bytes".into()).into());
+        }
+
+        Ok(core)
+    }
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        codec::StringCodec,
+        error::{Error, SegmentError},
+        index::{Index, Indexer},
+        segment::{SegmentReader, SegmentWriter, VERSION},
+        tokens,
+    };
+
+    fn test_index() -> Index<String> {
+        let mut index = Index::new(10, 8);
+        index
+            .insert(
+                "sample.txt".into(),
+                4,
+                &mut tokens!["quick", "brown", "fox", "fox"],
+            )
+            .unwrap();
+        index
+            .insert("sample2.txt".into(), 2, &mut tokens!["lazy", "fox"])
+            .unwrap();
+        index
+    }
+
+    #[test]
+    fn test_segment_roundtrip() {
+        let index = test_index();
+        let codec = StringCodec::new();
+
+        let buffer = SegmentWriter::new(&codec).encode(&index.core);
+        let core = SegmentReader::new(&codec).decode(&buffer).unwrap();
+        let reader = core.reader();
+
+        assert_eq!(reader.total_documents(), 2);
+        assert_eq!(reader.average_count(), 3.0);
+        assert_eq!(reader.document_frequency("fox"), Some(2));
+        assert_eq!(reader.document_frequency("lazy"), Some(1));
+        assert_eq!(reader.get_index(1).unwrap().resource(), "sample2.txt");
+
+        let frequency = reader
+            .get_term_entries("fox")
+            .unwrap()
+            .iter()
+            .find(|entry| entry.get_index() == 0)
+            .map(|entry| *entry.get_frequency());
+        assert_eq!(frequency, Some(2));
+
+        // Deterministic output.
+        assert_eq!(SegmentWriter::new(&codec).encode(&core), buffer);
+    }
+
+    #[test]
+    fn test_segment_positions() {
+        let mut index = Index::new(10, 8).with_positions();
+        index
+            .insert("a.txt".into(), 3, &mut tokens!["fox", "and", "fox"])
+            .unwrap();
+        let codec = StringCodec::new();
+
+        let buffer = SegmentWriter::new(&codec).encode(&index.core);
+        let core = SegmentReader::new(&codec).decode(&buffer).unwrap();
+
+        assert!(core.has_positions());
+        assert_eq!(core.reader().get_positions("fox", 0), Some(vec![0, 2]));
+        assert_eq!(SegmentWriter::new(&codec).encode(&core), buffer);
+
+        // Without positions, nothing is written per posting.
+        let plain = SegmentWriter::new(&codec).encode(&test_index().core);
+        let core = SegmentReader::new(&codec).decode(&plain).unwrap();
+        assert!(!core.has_positions());
+    }
+
+    #[test]
+    fn test_segment_file() {
+        let index = test_index();
+        let codec = StringCodec::new();
+        let path = std::env::temp_dir().join(format!("idx-segment-{}.idx", std::process::id()));
+
+        SegmentWriter::new(&codec)
+            .write(&path, &index.core)
+            .unwrap();
+        let core = SegmentReader::new(&codec).read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(core.reader().total_documents(), 2);
+    }
+
+    #[test]
+    fn test_segment_invalid() {
+        let index = test_index();
+        let codec = StringCodec::new();
+        let reader = SegmentReader::new(&codec);
+        let buffer = SegmentWriter::new(&codec).encode(&index.core);
+
+        let mut magic = buffer.clone();
+        magic[0] = b'X';
+        assert!(matches!(
+            reader.decode(&magic),
+            Err(Error::Segment(SegmentError::InvalidMagic))
+        ));
+
+        let mut version = buffer.clone();
+        version[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            reader.decode(&version),
+            Err(Error::Segment(SegmentError::UnsupportedVersion(_)))
+        ));
+
+        let mut corrupted = buffer.clone();
+        let last = corrupted.len() - 5;
+        corrupted[last] ^= 0xff;
+        assert!(matches!(
+            reader.decode(&corrupted),
+            Err(Error::Segment(SegmentError::Checksum))
+        ));
+
+        assert!(matches!(
+            reader.decode(&buffer[..buffer.len() - 1]),
+            Err(Error::Segment(SegmentError::UnexpectedEof))
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+//! Point-in-time snapshots for concurrent reads.
+//!
+//! The writer owns the mutable [`CoreIndex`] and publishes immutable copies
+//! of it, see [`Index::publish`](crate::index::Index::publish). Copies share
+//! the frozen layers of the index, see [`crate::layer`]. Readers load
+//! the latest snapshot without taking a lock, and keep searching it while the
+//! writer continues indexing.
+//!
+//! Every snapshot carries a generation, incremented on each publish. Replaced
+//! snapshots are reclaimed with epoch-based reclamation, once the last reader
+//! holding them drops its handle.
+
+extern crate crossbeam_epoch;
+
+use std::{
+    fmt::{self, Debug},
+    hash::Hash,
+    sync::{atomic::Ordering, Arc},
+};
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+
+use crate::{index::CoreIndex, reader::ReaderContext};
+
+/// Immutable view of the index at a given generation.
+#[derive(Debug)]
+pub struct Snapshot<R: Clone + Debug + Hash + Eq> {
+    generation: u64,
+    core: CoreIndex<R>,
+}
+
+impl<R: Clone + Debug + Hash + Eq> Snapshot<R> {
+    pub fn new(generation: u64, core: CoreIndex<R>) -> Self {
+        Self { generation, core }
+    }
+
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    #[inline]
+    pub fn core(&self) -> &CoreIndex<R> {
+        &self.core
+    }
+
+    #[inline]
+    pub fn reader(&self) -> ReaderContext<'_, R> {
+        ReaderContext::new(self.core.reader())
+    }
+}
+
+/// Slot holding the latest published snapshot.
+pub(crate) struct Published<R: Clone + Debug + Hash + Eq> {
+    current: Atomic<Arc<Snapshot<R>>>,
+}
+
+impl<R: Clone + Debug + Hash + Eq> Published<R> {
+    pub(crate) fn new(snapshot: Snapshot<R>) -> Self {
+        Self {
+            current: Atomic::new(Arc::new(snapshot)),
+        }
+    }
+
+    pub(crate) fn load(&self) -> Arc<Snapshot<R>> {
+        let guard = epoch::pin();
+        let current = self.current.load(Ordering::Acquire, &guard);
+
+        // SAFETY:
+        // - The slot is never null.
+        // - A replaced snapshot is not reclaimed while the guard is pinned.
+        unsafe { current.deref() }.clone()
+    }
+
+    pub(crate) fn store(&self, snapshot: Snapshot<R>)
+    where
+        R: Send + Sync + 'static,
+    {
+        let guard = epoch::pin();
+        let previous = self
+            .current
+            .swap(Owned::new(Arc::new(snapshot)), Ordering::AcqRel, &guard);
+
+        // SAFETY:
+        // - The previous snapshot is unreachable from the slot.
+        // - Readers still holding it own their `Arc`.
+        unsafe { guard.defer_destroy(previous) };
+    }
+}
+
+impl<R: Clone + Debug + Hash + Eq> Drop for Published<R> {
+    fn drop(&mut self) {
+        // SAFETY: No reader can access the slot anymore.
+        unsafe {
+            let guard = epoch::unprotected();
+            drop(self.current.load(Ordering::Relaxed, guard).into_owned());
+        }
+    }
+}
+
+impl<R: Clone + Debug + Hash + Eq> Debug for Published<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Published")
+            .field("generation", &self.load().generation())
+            .finish()
+    }
+}
+
+/// Cloneable, `Send + Sync` handle to the snapshots of an index.
+///
+/// Loading a snapshot never blocks, neither the caller nor the writer.
+#[derive(Debug)]
+pub struct SnapshotReader<R: Clone + Debug + Hash + Eq> {
+    published: Arc<Published<R>>,
+}
+
+impl<R: Clone + Debug + Hash + Eq> SnapshotReader<R> {
+    pub(crate) fn new(published: Arc<Published<R>>) -> Self {
+        Self { published }
+    }
+
+    /// Returns the latest published snapshot.
+    #[inline]
+    pub fn load(&self) -> Arc<Snapshot<R>> {
+        self.published.load()
+    }
+}
+
+impl<R: Clone + Debug + Hash + Eq> Clone for SnapshotReader<R> {
+    fn clone(&self) -> Self {
+        Self::new(Arc::clone(&self.published))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    use crate::{
+        index::{Index, Indexer},
+        score::{Score, TfIdfScorer},
+        snapshot::{Snapshot, SnapshotReader},
+        tokens,
+    };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_snapshot_send_sync() {
+        assert_send_sync::<SnapshotReader<String>>();
+        assert_send_sync::<Snapshot<String>>();
+    }
+
+    #[test]
+    fn test_snapshot_isolation() {
+        let mut index: Index<String> = Index::new(10, 8);
+        let snapshots = index.snapshots();
+        assert_eq!(snapshots.load().generation(), 0);
+
+        index
+            .insert("a.txt".into(), 2, &mut tokens!["quick", "fox"])
+            .unwrap();
+        assert_eq!(snapshots.load().reader().total_documents(), 0);
+
+        assert_eq!(index.publish(), 1);
+        let first = snapshots.load();
+
+        index
+            .insert("b.txt".into(), 2, &mut tokens!["lazy", "fox"])
+            .unwrap();
+        index.publish();
+
+        assert_eq!(first.generation(), 1);
+        assert_eq!(first.reader().total_documents(), 1);
+        assert_eq!(first.reader().document_frequency("fox"), Some(1));
+        assert_eq!(snapshots.load().reader().document_frequency("fox"), Some(2));
+    }
+
+    #[test]
+    fn test_snapshot_concurrent_reads() {
+        const DOCUMENTS: usize = 200;
+
+        let mut index = Index::new(10, 8);
+        let snapshots = index.snapshots();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let readers = (0..4)
+            .map(|_| {
+                let snapshots = snapshots.clone();
+                let done = Arc::clone(&done);
+
+                thread::spawn(move || {
+                    let mut generation = 0;
+
+                    while !done.load(Ordering::Acquire) {
+                        let snapshot = snapshots.load();
+                        let reader = snapshot.reader();
+
+                        // Every document contains "fox", one per generation.
+                        let documents = snapshot.generation() as usize;
+                        assert!(snapshot.generation() >= generation);
+                        assert_eq!(reader.total_documents(), documents);
+
+                        if documents > 0 {
+                            assert_eq!(reader.document_frequency("fox"), Some(documents));
+                            let scores = TfIdfScorer::new(&reader).score("fox").unwrap();
+                            assert_eq!(scores.len(), documents);
+                        }
+
+                        generation = snapshot.generation();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for document in 0..DOCUMENTS {
+            index
+                .insert(format!("{document}.txt"), 2, &mut tokens!["quick", "fox"])
+                .unwrap();
+            index.publish();
+        }
+
+        done.store(true, Ordering::Release);
+        readers
+            .into_iter()
+            .for_each(|reader| reader.join().unwrap());
+
+        assert_eq!(snapshots.load().reader().total_documents(), DOCUMENTS);
+    }
+}
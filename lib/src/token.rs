@@ -1,28 +1,102 @@
 use std::{
-    ops::{Deref, DerefMut},
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut, Range},
     slice::{Iter, IterMut},
 };
 
+/// Text of a token, with the byte range it was read from in the original
+/// text and its position among the tokens of the text.
+///
+/// Normalizers rewrite the text in place, so a token keeps its offsets and
+/// its position once normalized. Tokens compare and hash by text only.
 // TODO: Use generics to perform ops on
 // byte arrays and support multiple types.
-#[derive(Debug, Default, Hash, Eq, PartialEq)]
-pub struct Token(String);
+#[derive(Clone, Debug, Default)]
+pub struct Token {
+    text: String,
+    start: usize,
+    end: usize,
+    position: usize,
+}
 
 impl Token {
+    /// Token read from `offsets` of the text, the `position`-th one.
+    #[inline]
+    pub fn new(text: impl Into<String>, offsets: Range<usize>, position: usize) -> Self {
+        Self {
+            text: text.into(),
+            start: offsets.start,
+            end: offsets.end,
+            position,
+        }
+    }
+
+    /// Sets the byte range of the token in the original text.
+    #[inline]
+    pub fn with_offsets(mut self, offsets: Range<usize>) -> Self {
+        self.start = offsets.start;
+        self.end = offsets.end;
+        self
+    }
+
+    /// Sets the position of the token among the tokens of the text.
+    #[inline]
+    pub fn with_position(mut self, position: usize) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Byte offset of the start of the token in the original text.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Byte offset of the end of the token in the original text, exclusive.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    #[inline]
+    pub fn offsets(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Position of the token among the tokens of the text, as tokenized.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
     pub fn inner(self) -> String {
-        self.0
+        self.text
     }
 
     pub fn inner_ref(&self) -> &String {
-        &self.0
+        &self.text
     }
 
     pub fn inner_mut(&mut self) -> &mut String {
-        &mut self.0
+        &mut self.text
     }
 
     pub fn as_str(&self) -> &str {
-        &self.0
+        &self.text
+    }
+}
+
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+impl Eq for Token {}
+
+impl Hash for Token {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.text.hash(state)
     }
 }
 
@@ -30,56 +104,56 @@ impl Deref for Token {
     type Target = String;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.text
     }
 }
 
 impl From<Token> for String {
     fn from(value: Token) -> Self {
-        value.0
+        value.text
     }
 }
 
 impl DerefMut for Token {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.text
     }
 }
 
 impl From<String> for Token {
     fn from(value: String) -> Self {
-        Token(value)
+        Token::new(value, 0..0, 0)
     }
 }
 
 impl From<&str> for Token {
     fn from(value: &str) -> Self {
-        Token(String::from(value))
+        Token::new(value, 0..0, 0)
     }
 }
 
 impl From<&&str> for Token {
     fn from(value: &&str) -> Self {
-        Token(String::from(*value))
+        Token::new(*value, 0..0, 0)
     }
 }
 
 impl AsMut<str> for Token {
     fn as_mut(&mut self) -> &mut str {
-        &mut self.0
+        &mut self.text
     }
 }
 
 impl AsRef<str> for Token {
     fn as_ref(&self) -> &str {
-        &self.0
+        &self.text
     }
 }
 
 pub type Tokens = TokenIter<Token>;
 pub type TokenIter<T> = TokenVec<T>;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TokenVec<T: Into<Token> + PartialEq>(Vec<T>);
 
 impl<T: Into<Token> + PartialEq> TokenVec<T> {
@@ -122,7 +196,7 @@ impl<T: Into<Token> + PartialEq> TokenVec<T> {
     }
 
     #[inline]
-    fn iter(&self) -> Iter<'_, T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         self.0.iter()
     }
 
@@ -170,8 +244,11 @@ impl<T: Into<Token> + PartialEq> IntoIterator for TokenVec<T> {
 #[macro_export]
 macro_rules! tokens {
     ( $( $token:expr ),* $(,)? ) => {{
-        $crate::token::TokenVec::from(vec![
-            $( $crate::token::Token::from($token) ),*
-        ])
+        let tokens: Vec<$crate::token::Token> = vec![$( $crate::token::Token::from($token) ),*];
+        tokens
+            .into_iter()
+            .enumerate()
+            .map(|(position, token)| token.with_position(position))
+            .collect::<$crate::token::TokenVec<_>>()
     }};
 }
@@ -1,29 +1,405 @@
-use crate::tokenizer::{Standard, TextTokenizer, Tokens};
+//! Tokens of the text of an HTML document.
+//!
+//! Tags and comments are dropped, as are the contents of the elements that
+//! are not rendered, e.g. `<script>`. Entities are decoded, a token with an
+//! entity spans the entity in the markup. Offsets of the tokens are in the
+//! markup, not in the extracted text.
+//!
+//! The title, the headings and the `alt` text of the images are parts of
+//! the document, each one with a [`Route`]: in the text of the document,
+//! in a field of its own, see [`Html::fields`], or skipped.
 
-use html2text;
+use std::ops::Range;
 
-#[derive(Debug, Default)]
+use markup5ever::data::NAMED_ENTITIES;
+
+use crate::tokenizer::{Standard, TextTokenizer, Token, Tokens};
+
+/// Elements whose contents are not text of the document.
+const SKIPPED: [&str; 3] = ["script", "style", "template"];
+
+/// Elements that do not separate the words around them.
+const INLINE: [&str; 17] = [
+    "a", "abbr", "b", "cite", "code", "em", "font", "i", "mark", "q", "s", "small", "span",
+    "strong", "sub", "sup", "u",
+];
+
+/// Length of the longest entity, from the `&` to the `;`.
+const MAX_ENTITY: usize = 32;
+
+const HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Part {
+    /// Text of the `<title>` element.
+    Title,
+
+    /// Text of the `<h1>` to `<h6>` elements.
+    Headings,
+
+    /// `alt` text of the `<img>` elements.
+    Alt,
+}
+
+impl Part {
+    pub const ALL: [Part; 3] = [Part::Title, Part::Headings, Part::Alt];
+
+    /// Name of the field of the part.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Part::Title => "title",
+            Part::Headings => "headings",
+            Part::Alt => "alt",
+        }
+    }
+}
+
+/// Where the text of a part of the document goes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Route {
+    /// In the text of the document, where it occurs.
+    #[default]
+    Body,
+
+    /// In the field of the part only.
+    Field,
+
+    Skip,
+}
+
+/// HTML tokenizer.
+///
+/// By default, the title is skipped, like browsers do not render it in the
+/// page, and headings and `alt` text are in the text of the document.
+#[derive(Clone, Debug)]
 pub struct Html {
     tokenizer: Standard,
+    routes: [Route; 3],
+}
+
+impl Default for Html {
+    fn default() -> Self {
+        Self {
+            tokenizer: Standard::new(),
+            routes: [Route::Skip, Route::Body, Route::Body],
+        }
+    }
 }
 
 impl Html {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Routes the text of the part.
+    pub fn with_route(mut self, part: Part, route: Route) -> Self {
+        self.routes[part as usize] = route;
+        self
+    }
+
+    #[inline]
+    pub fn route(&self, part: Part) -> Route {
+        self.routes[part as usize]
+    }
+
+    /// Text of each non-empty part routed to a field, with the name of the
+    /// field, see [`Part::name`].
+    pub fn fields(&self, html: &str) -> Vec<(&'static str, String)> {
+        self.fields_of(&Markup::extract(html, self))
+    }
+
+    /// Tokens of the document and the text of its fields, see
+    /// [`Html::fields`], from a single pass over the markup.
+    pub fn extract(&self, html: &str) -> (Tokens, Vec<(&'static str, String)>) {
+        let markup = Markup::extract(html, self);
+        (self.tokens_of(&markup), self.fields_of(&markup))
+    }
+
+    fn fields_of(&self, markup: &Markup) -> Vec<(&'static str, String)> {
+        Part::ALL
+            .into_iter()
+            .filter(|&part| self.route(part) == Route::Field)
+            .map(|part| {
+                let words = markup.parts[part as usize].split_whitespace();
+                (part.name(), words.collect::<Vec<_>>().join(" "))
+            })
+            .filter(|(_, text)| !text.is_empty())
+            .collect()
+    }
+
+    fn tokens_of(&self, markup: &Markup) -> Tokens {
+        self.tokenizer
+            .spans(&markup.text)
+            .enumerate()
+            .map(|(position, (start, token))| {
+                let end = start + token.len();
+                let offsets = markup.sources[start].0..markup.sources[end - 1].1;
+                Token::new(token, offsets, position)
+            })
+            .collect()
+    }
 }
 
 impl TextTokenizer for Html {
     fn tokenize<T: AsRef<str>>(&mut self, text: T) -> Tokens {
-        let clean_text = html2text::from_read(text.as_ref().as_bytes(), 100);
-        self.tokenizer.tokenize(clean_text)
+        self.tokens_of(&Markup::extract(text.as_ref(), self))
+    }
+}
+
+/// Text of a document, with the byte range in the markup of each byte of
+/// the text, and the text of its parts routed to a field.
+#[derive(Debug, Default)]
+struct Markup {
+    text: String,
+    sources: Vec<(usize, usize)>,
+    parts: [String; 3],
+}
+
+impl Markup {
+    fn extract(html: &str, routes: &Html) -> Self {
+        let lowercase = html.to_ascii_lowercase();
+        let mut markup = Self::default();
+        let mut part = None;
+        let mut cursor = 0;
+
+        while cursor < html.len() {
+            let text_end = next_tag(&lowercase, cursor);
+            let route = part.map_or(Route::Body, |part| routes.route(part));
+            markup.push_text(html, cursor..text_end, part, route);
+
+            cursor = text_end;
+            if cursor == html.len() {
+                break;
+            }
+
+            if lowercase[cursor..].starts_with("<!--") {
+                cursor = find_end(&lowercase, cursor, "-->");
+                continue;
+            }
+
+            let end = tag_end(&lowercase, cursor);
+            let closing = lowercase[cursor..].starts_with("</");
+            let name = tag_name(&lowercase[cursor + 1..end]);
+
+            if !closing && SKIPPED.contains(&name) {
+                cursor = find_end(&lowercase, end, &format!("</{name}"));
+                cursor = find_end(&lowercase, cursor, ">");
+                continue;
+            }
+
+            if !INLINE.contains(&name) {
+                markup.separate((cursor, end));
+            }
+
+            part = match (name, closing) {
+                ("title", false) => Some(Part::Title),
+                (name, false) if HEADINGS.contains(&name) => Some(Part::Headings),
+                ("title", true) => None,
+                (name, true) if HEADINGS.contains(&name) => None,
+                _ => part,
+            };
+
+            if name == "img" && !closing {
+                if let Some(alt) = attribute(&lowercase, cursor..end, "alt") {
+                    let route = routes.route(Part::Alt);
+                    markup.push_text(html, alt, Some(Part::Alt), route);
+                    markup.separate((cursor, end));
+                }
+            }
+
+            cursor = end;
+        }
+
+        markup
+    }
+
+    /// Pushes the decoded text of the range, of the part, where it is routed.
+    fn push_text(&mut self, html: &str, range: Range<usize>, part: Option<Part>, route: Route) {
+        let mut cursor = range.start;
+
+        while cursor < range.end {
+            let (chars, end) = entity(html, cursor)
+                .filter(|&(_, end)| end <= range.end)
+                .unwrap_or_else(|| {
+                    let char = html[cursor..].chars().next().unwrap();
+                    ([Some(char), None], cursor + char.len_utf8())
+                });
+
+            for char in chars.into_iter().flatten() {
+                match (route, part) {
+                    (Route::Body, _) => self.push(char, (cursor, end)),
+                    (Route::Field, Some(part)) => self.parts[part as usize].push(char),
+                    _ => {}
+                }
+            }
+            cursor = end;
+        }
+    }
+
+    fn push(&mut self, char: char, source: (usize, usize)) {
+        self.text.push(char);
+        self.sources
+            .extend(std::iter::repeat_n(source, char.len_utf8()));
+    }
+
+    /// Separates the words before and after the tag at `source`.
+    fn separate(&mut self, source: (usize, usize)) {
+        self.push(' ', source);
+        self.parts.iter_mut().for_each(|part| part.push(' '));
+    }
+}
+
+/// Offset of the next tag or comment from `from`, the end of the text if
+/// there is none.
+fn next_tag(text: &str, from: usize) -> usize {
+    text[from..]
+        .match_indices('<')
+        .map(|(offset, _)| from + offset)
+        .find(|&offset| is_tag(&text[offset..]))
+        .unwrap_or(text.len())
+}
+
+/// Offset past the first `pattern` from `from`, the end of the text if the
+/// pattern is missing.
+fn find_end(text: &str, from: usize, pattern: &str) -> usize {
+    text[from..]
+        .find(pattern)
+        .map_or(text.len(), |offset| from + offset + pattern.len())
+}
+
+/// Offset past the `>` closing the tag at `from`, skipping quoted attribute
+/// values, the end of the text if the tag is not closed.
+fn tag_end(text: &str, from: usize) -> usize {
+    let mut quote = None;
+
+    // A quote only opens a value right after the `=`.
+    let mut after_equals = false;
+
+    for (offset, &byte) in text.as_bytes().iter().enumerate().skip(from) {
+        if let Some(open) = quote {
+            if byte == open {
+                quote = None;
+            }
+            continue;
+        }
+
+        match byte {
+            b'>' => return offset + 1,
+            b'"' | b'\'' if after_equals => quote = Some(byte),
+            _ => {}
+        }
+
+        if !byte.is_ascii_whitespace() {
+            after_equals = byte == b'=';
+        }
+    }
+
+    text.len()
+}
+
+fn is_tag(text: &str) -> bool {
+    text[1..]
+        .chars()
+        .next()
+        .is_some_and(|char| char.is_ascii_alphabetic() || matches!(char, '/' | '!' | '?'))
+}
+
+/// Name of the tag, lowercase, from its text between the angle brackets.
+fn tag_name(tag: &str) -> &str {
+    let tag = tag.trim_start_matches('/');
+    let end = tag
+        .find(|char: char| !char.is_ascii_alphanumeric())
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+/// Byte range of the value of the attribute of the tag at `tag`, the
+/// attribute name in lowercase.
+fn attribute(lowercase: &str, tag: Range<usize>, name: &str) -> Option<Range<usize>> {
+    let text = &lowercase[tag.clone()];
+    let mut cursor = 1 + text[1..].find(|char: char| !char.is_ascii_alphanumeric())?;
+
+    loop {
+        let rest = &text[cursor..];
+        let start = cursor + rest.find(|char: char| !char.is_ascii_whitespace() && char != '/')?;
+        let rest = &text[start..];
+        let len =
+            rest.find(|char: char| char.is_ascii_whitespace() || matches!(char, '=' | '>' | '/'))?;
+        let attribute = &rest[..len];
+        cursor = start + len;
+
+        if attribute.is_empty() {
+            return None;
+        }
+
+        let rest = text[cursor..].trim_ascii_start();
+        if !rest.starts_with('=') {
+            continue;
+        }
+
+        // Value, quoted or up to the next whitespace, ASCII only as in HTML.
+        let value_start = text.len() - rest[1..].trim_ascii_start().len();
+        let value = match text[value_start..].chars().next()? {
+            quote @ ('"' | '\'') => {
+                let end = text[value_start + 1..].find(quote)? + value_start + 1;
+                value_start + 1..end
+            }
+            _ => {
+                let len = text[value_start..]
+                    .find(|char: char| char.is_ascii_whitespace() || char == '>')
+                    .unwrap_or(text.len() - value_start);
+                value_start..value_start + len
+            }
+        };
+        cursor = (value.end + 1).min(text.len());
+
+        if attribute == name {
+            return Some(tag.start + value.start..tag.start + value.end);
+        }
     }
 }
 
+/// Characters of the entity at `start`, if any, and the offset past the
+/// entity. Named entities are decoded from the HTML5 table, some of them
+/// stand for two characters.
+fn entity(html: &str, start: usize) -> Option<([Option<char>; 2], usize)> {
+    if !html[start..].starts_with('&') {
+        return None;
+    }
+
+    let end = start + html[start..].find(';').filter(|&len| len <= MAX_ENTITY)?;
+    let name = &html[start + 1..end];
+
+    let decoded = match name.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            [Some(char::from_u32(code)?), None]
+        }
+        None => {
+            // The table also maps the prefixes of the names, to nothing.
+            let &(first, second) = NAMED_ENTITIES
+                .get(&html[start + 1..=end])
+                .filter(|&&(first, _)| first != 0)?;
+            [
+                char::from_u32(first),
+                char::from_u32(second).filter(|&c| c != '\0'),
+            ]
+        }
+    };
+
+    Some((decoded, end + 1))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        tokenizer::{html::Html, TextTokenizer},
+        tokenizer::{
+            html::{Html, Part, Route},
+            TextTokenizer,
+        },
         tokens,
     };
 
@@ -35,35 +411,150 @@ mod tests {
         assert_eq!(tokens, tokens!["Hello", "world"]);
     }
 
-    // #[test]
-    // fn test_tokenizer_html_with_tags() {
-    //     let mut html_tokenizer = Html::new();
-    //     let text = "<div><p>This is <b>bold</b> and <i>italic</i>.</p></div>";
-    //     let tokens = html_tokenizer.tokenize(text);
-    //     assert_eq!(tokens, tokens!["This", "is", "bold", "and", "italic"]);
-    // }
-
-    // #[test]
-    // fn test_tokenizer_html_complex() {
-    //     let mut html_tokenizer = Html::new();
-    //     let text = r#"
-    //         <html>
-    //             <head>
-    //                 <title>Test</title>
-    //             </head>
-    //             <body>
-    //                 <h1>Header</h1>
-    //                 <p>Paragraph with <a href="#">link</a> and <img src="image.jpg" alt="image">.</p>
-    //                 <ul>
-    //                     <li>List item 1</li>
-    //                     <li>List item 2</li>
-    //                 </ul>
-    //             </body>
-    //         </html>
-    //     "#;
-    //     let tokens = html_tokenizer.tokenize(text);
-    //     assert_eq!(tokens, vec!["Header", "Paragraph", "with", "link", "and", "image", "List", "item", "1", "List", "item", "2"]);
-    // }
+    #[test]
+    fn test_tokenizer_html_with_tags() {
+        let mut html_tokenizer = Html::new();
+        let text = "<div><p>This is <b>bold</b> and <i>italic</i>.</p></div>";
+        let tokens = html_tokenizer.tokenize(text);
+        assert_eq!(tokens, tokens!["This", "is", "bold", "and", "italic"]);
+    }
+
+    #[test]
+    fn test_tokenizer_html_complex() {
+        let mut html_tokenizer = Html::new();
+        let text = r##"
+            <html>
+                <head>
+                    <title>Test</title>
+                </head>
+                <body>
+                    <h1>Header</h1>
+                    <p>Paragraph with <a href="#">link</a> and <img src="image.jpg" alt="image">.</p>
+                    <ul>
+                        <li>List item 1</li>
+                        <li>List item 2</li>
+                    </ul>
+                </body>
+            </html>
+        "##;
+        let tokens = html_tokenizer.tokenize(text);
+        assert_eq!(
+            tokens,
+            tokens![
+                "Header",
+                "Paragraph",
+                "with",
+                "link",
+                "and",
+                "image",
+                "List",
+                "item",
+                "1",
+                "List",
+                "item",
+                "2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_html_fields() {
+        let text = r#"<title>Rust &amp; HTML</title>
+            <h1>Getting started</h1><p>Install <img alt='the toolchain' src=x.png> first.</p>
+            <h2>Next</h2><p>Done <img src=y.png alt=logo></p>"#;
+
+        let mut html_tokenizer = Html::new()
+            .with_route(Part::Title, Route::Field)
+            .with_route(Part::Headings, Route::Field)
+            .with_route(Part::Alt, Route::Skip);
+
+        let tokens = html_tokenizer.tokenize(text);
+        assert_eq!(tokens, tokens!["Install", "first", "Done"]);
+        assert_eq!(
+            html_tokenizer.fields(text),
+            [
+                ("title", "Rust & HTML".to_string()),
+                ("headings", "Getting started Next".to_string())
+            ]
+        );
+        assert_eq!(
+            html_tokenizer.extract(text),
+            (tokens, html_tokenizer.fields(text))
+        );
+
+        // Inline text keeps its offsets in the markup.
+        let mut html_tokenizer = Html::new().with_route(Part::Title, Route::Body);
+        let tokens = html_tokenizer.tokenize(text);
+        let words = tokens
+            .iter()
+            .map(|token| &text[token.offsets()])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            [
+                "Rust",
+                "&amp;",
+                "HTML",
+                "Getting",
+                "started",
+                "Install",
+                "the",
+                "toolchain",
+                "first",
+                "Next",
+                "Done",
+                "logo"
+            ]
+        );
+        assert!(html_tokenizer.fields(text).is_empty());
+    }
+
+    #[test]
+    fn test_tokenizer_html_quoted_attributes() {
+        let text = r#"<p>x <img alt="a > b" title='c>d'> y <a href=q>z</a></p>"#;
+
+        let mut html_tokenizer = Html::new().with_route(Part::Alt, Route::Field);
+        let tokens = html_tokenizer.tokenize(text);
+        assert_eq!(tokens, tokens!["x", "y", "z"]);
+        assert_eq!(html_tokenizer.fields(text), [("alt", "a > b".to_string())]);
+
+        // Quotes outside of values do not hide the end of the tag.
+        let mut html_tokenizer = Html::new();
+        let tokens = html_tokenizer.tokenize(r#"<p class=a"b>c</p>"#);
+        assert_eq!(tokens, tokens!["c"]);
+    }
+
+    #[test]
+    fn test_tokenizer_html_unicode_attributes() {
+        // A non-breaking space is part of an unquoted value.
+        let text = "<img src=a\u{a0}alt=b alt=c\u{a0}d>x";
+
+        let mut html_tokenizer = Html::new().with_route(Part::Alt, Route::Field);
+        assert_eq!(html_tokenizer.tokenize(text), tokens!["x"]);
+        assert_eq!(html_tokenizer.fields(text), [("alt", "c d".to_string())]);
+    }
+
+    #[test]
+    fn test_tokenizer_html_offsets() {
+        let mut html_tokenizer = Html::new();
+        let text = "<p>Caf&eacute; <b>bo</b>ld &amp;co</p><p>next</p>";
+        let tokens = html_tokenizer.tokenize(text);
+
+        assert_eq!(tokens, tokens!["Café", "bold", "&co", "next"]);
+        let offsets = tokens
+            .iter()
+            .map(|token| (token.position(), &text[token.offsets()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            [
+                (0, "Caf&eacute;"),
+                (1, "bo</b>ld"),
+                (2, "&amp;co"),
+                (3, "next")
+            ]
+        );
+    }
 
     #[test]
     fn test_tokenizer_html_empty() {
@@ -92,6 +583,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tokenizer_html_named_entities() {
+        let mut html_tokenizer = Html::new();
+        let text = "<p>na&iuml;ve &Eacute;cole x&CounterClockwiseContourIntegral;y \
+                    a&NotEqualTilde;b &unknown; &eacute</p>";
+        let tokens = html_tokenizer.tokenize(text);
+        assert_eq!(
+            tokens,
+            tokens!["naïve", "École", "x∳y", "a≂̸b", "&unknown", "&eacute"]
+        );
+    }
+
     #[test]
     fn test_tokenizer_html_script_and_style() {
         let mut html_tokenizer = Html::new();
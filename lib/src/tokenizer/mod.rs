@@ -1,8 +1,8 @@
-mod html;
+pub mod html;
 mod standard;
 mod whitespace;
 
-pub use {standard::Standard, whitespace::Whitespace};
+pub use {html::Html, standard::Standard, whitespace::Whitespace};
 
 use crate::token::{Token, Tokens};
 
@@ -10,6 +10,7 @@ use crate::token::{Token, Tokens};
 pub enum Tokenizer {
     Standard(Standard),
     Whitespace(Whitespace),
+    Html(Html),
 }
 
 impl Tokenizer {
@@ -17,17 +18,23 @@ impl Tokenizer {
         match self {
             Tokenizer::Standard(tokenizer) => tokenizer.tokenize(text),
             Tokenizer::Whitespace(tokenizer) => tokenizer.tokenize(text),
+            Tokenizer::Html(tokenizer) => tokenizer.tokenize(text),
         }
     }
 }
 
+/// Byte offset of a token borrowed from the text.
+#[inline]
+fn offset(text: &str, token: &str) -> usize {
+    token.as_ptr() as usize - text.as_ptr() as usize
+}
+
 pub trait TextTokenizer {
     fn tokenize<T: AsRef<str>>(&mut self, text: T) -> Tokens;
 }
 
 // TODO
-// 1. HTML Tokenizer
-// 2. Regex Tokenizer
+// 1. Regex Tokenizer
 
 #[cfg(test)]
 mod tests {
@@ -46,8 +53,45 @@ mod tests {
     //         assert_eq!(tokens, expected);
     //     }
 
+    use crate::tokenizer::{Standard, Tokenizer, Whitespace};
+
     #[test]
     fn test_tokens() {
         // let tokens = tokens!["one"];
     }
+
+    #[test]
+    fn test_tokenizer_offsets() {
+        let text = "Héllo, wörld!  (again)";
+        let offsets = |mut tokenizer: Tokenizer| {
+            tokenizer
+                .tokenize(text)
+                .iter()
+                .map(|token| (token.position(), token.offsets(), token.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let tokens = offsets(Tokenizer::Standard(Standard::new()));
+        assert_eq!(
+            tokens,
+            [
+                (0, 0..6, "Héllo".to_string()),
+                (1, 8..14, "wörld".to_string()),
+                (2, 18..23, "again".to_string())
+            ]
+        );
+        assert!(tokens
+            .iter()
+            .all(|(_, range, token)| &text[range.clone()] == token));
+
+        let tokens = offsets(Tokenizer::Whitespace(Whitespace::new()));
+        assert_eq!(
+            tokens,
+            [
+                (0, 0..7, "Héllo,".to_string()),
+                (1, 8..15, "wörld!".to_string()),
+                (2, 17..24, "(again)".to_string())
+            ]
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use std::{collections::HashSet, sync::OnceLock};
 
-use crate::tokenizer::{TextTokenizer, Token, Tokens};
+use crate::tokenizer::{offset, TextTokenizer, Token, Tokens};
 
 fn delimiters() -> &'static HashSet<&'static char> {
     static SET: OnceLock<HashSet<&char>> = OnceLock::new();
@@ -21,14 +21,22 @@ impl Standard {
     pub fn new() -> Self {
         Self
     }
+
+    /// Tokens of the text with their byte offset in the text.
+    pub fn spans<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> {
+        text.split(|ch: char| delimiters().contains(&ch))
+            .filter(|s| !s.is_empty())
+            .map(move |token| (offset(text, token), token))
+    }
 }
 
 impl TextTokenizer for Standard {
     fn tokenize<T: AsRef<str>>(&mut self, text: T) -> Tokens {
-        text.as_ref()
-            .split(|ch: char| delimiters().contains(&ch))
-            .filter(|s| !s.is_empty())
-            .map(Token::from)
+        self.spans(text.as_ref())
+            .enumerate()
+            .map(|(position, (start, token))| {
+                Token::new(token, start..start + token.len(), position)
+            })
             .collect()
     }
 }
@@ -1,4 +1,4 @@
-use crate::tokenizer::{TextTokenizer, Token, Tokens};
+use crate::tokenizer::{offset, TextTokenizer, Token, Tokens};
 
 #[derive(Clone, Debug, Default)]
 pub struct Whitespace;
@@ -7,11 +7,22 @@ impl Whitespace {
     pub fn new() -> Self {
         Self
     }
+
+    /// Tokens of the text with their byte offset in the text.
+    pub fn spans<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> {
+        text.split_whitespace()
+            .map(move |token| (offset(text, token), token))
+    }
 }
 
 impl TextTokenizer for Whitespace {
     fn tokenize<T: AsRef<str>>(&mut self, text: T) -> Tokens {
-        text.as_ref().split_whitespace().map(Token::from).collect()
+        self.spans(text.as_ref())
+            .enumerate()
+            .map(|(position, (start, token))| {
+                Token::new(token, start..start + token.len(), position)
+            })
+            .collect()
     }
 }
 
@@ -0,0 +1,468 @@
+//! Write-ahead log for crash-safe indexing.
+//!
+//! Every insert, update and delete is appended to the log, and synced, before
+//! it is applied to the in-memory [`CoreIndex`](crate::index::CoreIndex).
+//! After a crash, the log is replayed on top of the last persisted segment so
+//! that the store and the inverted index end up consistent.
+//!
+//! # Layout
+//!
+//! ```text
+//! record  := payload length: u32 | crc32(payload): u32 | payload
+//! payload := kind: u8 | resource index: u64 | operation
+//!
+//! insert  := word count: u64 | codec bytes | terms: u64 | (term bytes | position: u64)*
+//!            | facets | fields
+//! facets  := pairs: u64 | (field bytes | value bytes)*
+//! fields  := fields: u64 | (field bytes | word count: u64)*
+//! update  := replaced index: u64 | insert
+//! delete  := (empty)
+//! ```
+//!
+//! Inserts and updates logged by earlier versions have no positions, they
+//! are replayed with the ordinals of the terms as positions.
+//!
+//! A record that is truncated or fails its checksum marks the end of the log,
+//! it belongs to an operation that was never acknowledged.
+//!
+//! The resource index stored in each record makes replay idempotent,
+//! inserts already contained in the segment are skipped and deletes
+//! can safely be applied more than once.
+
+use std::{
+    fmt::Debug,
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    codec::{Codec, Decoder, Encoder},
+    error::{Error, SegmentError},
+    facet::Facets,
+};
+
+/// Size of the record header, payload length and checksum.
+const HEADER_SIZE: usize = 8;
+
+const KIND_DELETE: u8 = 2;
+const KIND_INSERT: u8 = 3;
+const KIND_UPDATE: u8 = 4;
+
+/// Inserts and updates without term positions.
+const KIND_INSERT_UNPOSITIONED: u8 = 0;
+const KIND_UPDATE_UNPOSITIONED: u8 = 1;
+
+/// A logged operation.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Record<R> {
+    Insert {
+        /// Index the resource is assigned in the store.
+        index: usize,
+
+        /// Resource deleted by the same operation, when updating.
+        replaces: Option<usize>,
+
+        resource: R,
+        word_count: usize,
+
+        /// Terms with their position.
+        terms: Vec<(String, usize)>,
+        facets: Facets,
+
+        /// Word count of each named field.
+        fields: Vec<(String, usize)>,
+    },
+    Delete {
+        index: usize,
+    },
+}
+
+#[derive(Debug)]
+pub struct WriteAheadLog<R> {
+    path: PathBuf,
+    file: File,
+    codec: Box<dyn Codec<R>>,
+
+    /// Size of the log in bytes.
+    size: u64,
+}
+
+impl<R> WriteAheadLog<R> {
+    /// Opens the log at `path`, creating it if it does not exist.
+    pub fn open<P, C>(path: P, codec: C) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        C: Codec<R> + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .map_err(SegmentError::from)?;
+        let size = file.metadata().map_err(SegmentError::from)?.len();
+
+        Ok(Self {
+            path,
+            file,
+            codec: Box::new(codec),
+            size,
+        })
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[inline]
+    pub fn codec(&self) -> &dyn Codec<R> {
+        self.codec.as_ref()
+    }
+
+    /// Size of the log in bytes, to decide when to checkpoint.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Appends an insert to the log, returning once it is durable.
+    ///
+    /// When `replaces` is set, the insert and the delete of the
+    /// replaced resource are logged as a single update.
+    #[allow(clippy::too_many_arguments)]
+    pub fn append_insert<'t>(
+        &mut self,
+        index: usize,
+        replaces: Option<usize>,
+        resource: &R,
+        word_count: usize,
+        terms: impl ExactSizeIterator<Item = (&'t str, usize)>,
+        facets: &Facets,
+        fields: &[(String, usize)],
+    ) -> Result<(), Error> {
+        let mut payload = Encoder::new();
+
+        match replaces {
+            Some(replaces) => {
+                payload.u8(KIND_UPDATE);
+                payload.usize(index);
+                payload.usize(replaces);
+            }
+            None => {
+                payload.u8(KIND_INSERT);
+                payload.usize(index);
+            }
+        }
+
+        payload.usize(word_count);
+        payload.resource(self.codec.as_ref(), resource);
+        payload.usize(terms.len());
+        terms.for_each(|(term, position)| {
+            payload.bytes(term.as_bytes());
+            payload.usize(position);
+        });
+        payload.facets(facets);
+        payload.fields(fields);
+
+        self.write(payload.into_inner())
+    }
+
+    /// Appends a delete to the log, returning once it is durable.
+    pub fn append_delete(&mut self, index: usize) -> Result<(), Error> {
+        let mut payload = Encoder::new();
+        payload.u8(KIND_DELETE);
+        payload.usize(index);
+
+        self.write(payload.into_inner())
+    }
+
+    fn write(&mut self, payload: Vec<u8>) -> Result<(), Error> {
+        let mut record = Encoder::new();
+        record.u32(payload.len() as u32);
+        record.u32(crc32fast::hash(&payload));
+        record.raw(&payload);
+        let record = record.into_inner();
+
+        self.file.write_all(&record).map_err(SegmentError::from)?;
+        self.file.sync_data().map_err(SegmentError::from)?;
+        self.size += record.len() as u64;
+        Ok(())
+    }
+
+    /// Reads every complete record, in insertion order.
+    ///
+    /// A torn record at the end of the log is discarded and the
+    /// log is truncated to the last complete record.
+    pub fn replay(&mut self) -> Result<Vec<Record<R>>, Error> {
+        let mut buffer = Vec::new();
+        let mut file = File::open(&self.path).map_err(SegmentError::from)?;
+        file.read_to_end(&mut buffer).map_err(SegmentError::from)?;
+
+        let mut records = Vec::new();
+        let mut offset = 0;
+
+        while let Some(payload) = next_payload(&buffer[offset..]) {
+            records.push(self.decode(payload)?);
+            offset += HEADER_SIZE + payload.len();
+        }
+
+        if offset < buffer.len() {
+            self.file
+                .set_len(offset as u64)
+                .map_err(SegmentError::from)?;
+            self.file.sync_all().map_err(SegmentError::from)?;
+        }
+        self.size = offset as u64;
+
+        Ok(records)
+    }
+
+    /// Discards every record, once they are persisted elsewhere.
+    pub fn truncate(&mut self) -> Result<(), Error> {
+        self.file.set_len(0).map_err(SegmentError::from)?;
+        self.file.sync_all().map_err(SegmentError::from)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Record<R>, Error> {
+        let mut decoder = Decoder::new(payload);
+
+        let kind = decoder.u8()?;
+        let index = decoder.usize()?;
+
+        let record = match kind {
+            KIND_DELETE => Record::Delete { index },
+            KIND_INSERT | KIND_UPDATE | KIND_INSERT_UNPOSITIONED | KIND_UPDATE_UNPOSITIONED => {
+                let replaces = match kind {
+                    KIND_UPDATE | KIND_UPDATE_UNPOSITIONED => Some(decoder.usize()?),
+                    _ => None,
+                };
+
+                let word_count = decoder.usize()?;
+                let resource = decoder.resource(self.codec.as_ref())?;
+
+                let positioned = matches!(kind, KIND_INSERT | KIND_UPDATE);
+                let len = decoder.usize()?;
+                let terms = (0..len)
+                    .map(|ordinal| {
+                        let term = decoder.string()?;
+                        let position = match positioned {
+                            true => decoder.usize()?,
+                            false => ordinal,
+                        };
+                        Ok((term, position))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let facets = decoder.facets()?;
+                let fields = decoder.fields()?;
+
+                if word_count == 0 {
+                    return Err(SegmentError::Corrupted("empty log record".into()).into());
+                }
+
+                if fields.iter().map(|(_, count)| count).sum::<usize>() > word_count {
+                    let message = "invalid field word counts in log record";
+                    return Err(SegmentError::Corrupted(message.into()).into());
+                }
+
+                Record::Insert {
+                    index,
+                    replaces,
+                    resource,
+                    word_count,
+                    terms,
+                    facets,
+                    fields,
+                }
+            }
+            kind => {
+                let message = format!("unknown log record kind {kind}");
+                return Err(SegmentError::Corrupted(message).into());
+            }
+        };
+
+        if !decoder.is_empty() {
+            return Err(SegmentError::Corrupted("invalid log record".into()).into());
+        }
+
+        Ok(record)
+    }
+}
+
+/// Returns the payload of the first record, if it is complete and intact.
+fn next_payload(buffer: &[u8]) -> Option<&[u8]> {
+    let mut header = Decoder::new(buffer);
+    let len = header.u32().ok()? as usize;
+    let checksum = header.u32().ok()?;
+    let payload = header.raw(len).ok()?;
+
+    (crc32fast::hash(payload) == checksum).then_some(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        codec::{Encoder, StringCodec},
+        facet::Facets,
+        wal::{Record, WriteAheadLog, KIND_INSERT_UNPOSITIONED},
+    };
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("idx-wal-{}-{name}.log", std::process::id()))
+    }
+
+    #[test]
+    fn test_wal_append_and_replay() {
+        let path = temp_path("replay");
+        let mut wal = WriteAheadLog::open(&path, StringCodec).unwrap();
+
+        wal.append_insert(
+            0,
+            None,
+            &"a.txt".to_string(),
+            2,
+            [("quick", 0), ("fox", 2)].into_iter(),
+            &Facets::new().with("extension", "txt"),
+            &[("title".into(), 1)],
+        )
+        .unwrap();
+        wal.append_delete(0).unwrap();
+        wal.append_insert(
+            1,
+            Some(0),
+            &"a.txt".to_string(),
+            1,
+            [("dog", 0)].into_iter(),
+            &Facets::new(),
+            &[],
+        )
+        .unwrap();
+
+        let mut wal = WriteAheadLog::open(&path, StringCodec).unwrap();
+        let records = wal.replay().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                Record::Insert {
+                    index: 0,
+                    replaces: None,
+                    resource: "a.txt".to_string(),
+                    word_count: 2,
+                    terms: vec![("quick".into(), 0), ("fox".into(), 2)],
+                    facets: Facets::new().with("extension", "txt"),
+                    fields: vec![("title".into(), 1)],
+                },
+                Record::Delete { index: 0 },
+                Record::Insert {
+                    index: 1,
+                    replaces: Some(0),
+                    resource: "a.txt".to_string(),
+                    word_count: 1,
+                    terms: vec![("dog".into(), 0)],
+                    facets: Facets::new(),
+                    fields: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wal_torn_record() {
+        let path = temp_path("torn");
+        let mut wal = WriteAheadLog::open(&path, StringCodec).unwrap();
+
+        wal.append_insert(
+            0,
+            None,
+            &"a.txt".to_string(),
+            1,
+            [("fox", 0)].into_iter(),
+            &Facets::new(),
+            &[],
+        )
+        .unwrap();
+        let valid = std::fs::metadata(&path).unwrap().len();
+        wal.append_insert(
+            1,
+            None,
+            &"b.txt".to_string(),
+            1,
+            [("dog", 0)].into_iter(),
+            &Facets::new(),
+            &[],
+        )
+        .unwrap();
+
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(valid + 5).unwrap();
+
+        let mut wal = WriteAheadLog::open(&path, StringCodec).unwrap();
+        let records = wal.replay().unwrap();
+        let len = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(len, valid);
+        assert_eq!(wal.size(), valid);
+    }
+
+    #[test]
+    fn test_wal_truncate() {
+        let path = temp_path("truncate");
+        let mut wal = WriteAheadLog::open(&path, StringCodec).unwrap();
+
+        wal.append_insert(
+            0,
+            None,
+            &"a.txt".to_string(),
+            1,
+            [("fox", 0)].into_iter(),
+            &Facets::new(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(wal.size(), std::fs::metadata(&path).unwrap().len());
+        assert!(wal.size() > 0);
+
+        wal.truncate().unwrap();
+        assert_eq!(wal.size(), 0);
+
+        let records = wal.replay().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn test_wal_replay_unpositioned() {
+        let path = temp_path("unpositioned");
+        let mut wal = WriteAheadLog::open(&path, StringCodec).unwrap();
+
+        // Insert logged before terms carried their position.
+        let mut payload = Encoder::new();
+        payload.u8(KIND_INSERT_UNPOSITIONED);
+        payload.usize(0);
+        payload.usize(2);
+        payload.resource(&StringCodec, &"a.txt".to_string());
+        payload.usize(2);
+        payload.bytes(b"quick");
+        payload.bytes(b"fox");
+        payload.facets(&Facets::new());
+        payload.fields(&[]);
+        wal.write(payload.into_inner()).unwrap();
+
+        let records = wal.replay().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let Record::Insert { terms, .. } = &records[0] else {
+            panic!("{records:?}");
+        };
+        assert_eq!(terms, &[("quick".to_string(), 0), ("fox".to_string(), 1)]);
+    }
+}
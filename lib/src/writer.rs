@@ -1,17 +1,18 @@
-use std::{fmt::Debug, marker::PhantomData};
+use std::{fmt::Debug, hash::Hash, marker::PhantomData};
 
 use crate::{
     core::{InvertedIndex, Resource, Store, TermCounter, TfEntry},
+    facet::Facets,
     token::Token,
 };
 
-pub struct IndexWriter<'w, R: Clone + Debug> {
+pub struct IndexWriter<'w, R: Clone + Debug + Hash + Eq> {
     store: &'w mut Store<R>,
     index: &'w mut InvertedIndex,
     count: &'w mut TermCounter,
 }
 
-impl<'w, R: Clone + Debug> IndexWriter<'w, R> {
+impl<'w, R: Clone + Debug + Hash + Eq> IndexWriter<'w, R> {
     pub fn new(
         store: &'w mut Store<R>,
         index: &'w mut InvertedIndex,
@@ -25,7 +26,7 @@ impl<'w, R: Clone + Debug> IndexWriter<'w, R> {
     }
 }
 
-impl<'w, R: Clone + Debug> IndexWriter<'w, R> {
+impl<'w, R: Clone + Debug + Hash + Eq> IndexWriter<'w, R> {
     // Insert a term into the term counter
     #[inline]
     pub fn insert_counter(&mut self, term: String) {
@@ -37,15 +38,45 @@ impl<'w, R: Clone + Debug> IndexWriter<'w, R> {
         self.store.insert(entry)
     }
 
+    // Insert a tombstoned file entry
+    pub fn insert_deleted_resource(&mut self, entry: Resource<R>) -> usize {
+        self.store.insert_deleted(entry)
+    }
+
+    // Index of the live file entry with the given key
+    pub fn lookup(&self, key: &R) -> Option<usize> {
+        self.store.lookup(key)
+    }
+
+    // Tombstone a file entry
+    pub fn delete_resource(&mut self, index: usize) -> bool {
+        self.store.delete(index)
+    }
+
     pub fn insert_term(&mut self, term: String, entry: TfEntry) {
         self.index.add_term(term, entry)
     }
 
+    // Insert an occurrence of a term, its position is recorded if the index records positions
+    #[inline]
+    pub fn insert_term_at(&mut self, term: String, index: usize, position: u32) {
+        self.index.add_term_at(term, index, position)
+    }
+
+    // Insert a whole posting of a term with its positions
+    #[inline]
+    pub fn push_term(&mut self, term: String, entry: TfEntry, positions: &[u32]) {
+        self.index.push_term(term, entry, positions)
+    }
+
     // Reset the term counter
     pub fn reset_count(&mut self) {
         self.count.reset()
     }
 
+    /// # Safety
+    /// The caller ensures that the term was inserted into the counter.
+    ///
     /// # Panics
     /// If no term exists in the index.
     #[inline]
@@ -61,13 +92,13 @@ pub struct TermEntryState {
     index: usize,
 }
 
-pub struct WriterContext<'wctx, S, R: Clone + Debug> {
+pub struct WriterContext<'wctx, S, R: Clone + Debug + Hash + Eq> {
     writer: IndexWriter<'wctx, R>,
     data: Option<S>,
     _marker: PhantomData<S>,
 }
 
-impl<'wctx, S, R: Clone + Debug> WriterContext<'wctx, S, R> {
+impl<'wctx, S, R: Clone + Debug + Hash + Eq> WriterContext<'wctx, S, R> {
     pub fn new(writer: IndexWriter<'wctx, R>) -> Self {
         Self {
             writer,
@@ -85,13 +116,22 @@ impl<'wctx, S, R: Clone + Debug> WriterContext<'wctx, S, R> {
     }
 }
 
-impl<'wctx, R: Clone + Debug> WriterContext<'wctx, ResourceState, R> {
-    pub fn entry(
-        mut self,
+impl<'wctx, R: Clone + Debug + Hash + Eq> WriterContext<'wctx, ResourceState, R> {
+    pub fn entry(self, resource: R, word_count: usize) -> WriterContext<'wctx, TermEntryState, R> {
+        self.entry_with_facets(resource, word_count, Facets::new())
+    }
+
+    pub fn entry_with_facets(
+        self,
         resource: R,
         word_count: usize,
+        facets: Facets,
     ) -> WriterContext<'wctx, TermEntryState, R> {
-        let entry = Resource::new(resource, word_count);
+        self.resource_entry(Resource::new(resource, word_count).with_facets(facets))
+    }
+
+    /// Inserts a resource built by the caller, e.g. with per-field word counts.
+    pub fn resource_entry(mut self, entry: Resource<R>) -> WriterContext<'wctx, TermEntryState, R> {
         let index = self.writer.insert_resource(entry);
         WriterContext::<'wctx, TermEntryState, R>::new_with_data(
             self.writer,
@@ -100,7 +140,13 @@ impl<'wctx, R: Clone + Debug> WriterContext<'wctx, ResourceState, R> {
     }
 }
 
-impl<'wctx, R: Clone + Debug> WriterContext<'wctx, TermEntryState, R> {
+impl<'wctx, R: Clone + Debug + Hash + Eq> WriterContext<'wctx, TermEntryState, R> {
+    /// Index of the resource the terms are inserted for.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.data.unwrap().index
+    }
+
     pub fn insert_term(&mut self, term: String) {
         self.writer.insert_counter(term.clone());
 
@@ -116,6 +162,15 @@ impl<'wctx, R: Clone + Debug> WriterContext<'wctx, TermEntryState, R> {
         self.insert_term(term);
     }
 
+    /// Inserts the term occurring at `position` in the resource.
+    ///
+    /// The position is only recorded if the index records positions.
+    pub fn insert_term_at(&mut self, term: String, position: u32) {
+        let index = self.index();
+        self.writer.insert_counter(term.clone());
+        self.writer.insert_term_at(term, index, position);
+    }
+
     pub fn reset_counter(&mut self) {
         self.writer.reset_count()
     }
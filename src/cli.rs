@@ -71,6 +71,15 @@ pub struct Cli {
     // pub normalizer: PathBuf,
     #[arg(long, value_name = "FILE")]
     pub config: String,
+
+    /// Prints the explanation of the score of `--resource` for the query,
+    /// as JSON, each time an index thread publishes its documents.
+    #[arg(long, value_name = "QUERY")]
+    pub explain: Option<String>,
+
+    /// Index of the explained resource, in each index thread.
+    #[arg(long, value_name = "INDEX", default_value_t = 0, requires = "explain")]
+    pub resource: usize,
 }
 
 impl Cli {
@@ -112,6 +121,12 @@ pub struct Config {
     pub thread: ThreadConfig,
     pub tokenizer: TokenizerConfig,
     pub normalizer: Vec<NormalizerConfig>,
+    #[serde(default)]
+    pub filter: Vec<FilterConfig>,
+    #[serde(default)]
+    pub storage: Option<StorageConfig>,
+    #[serde(default)]
+    pub index: IndexConfig,
 }
 
 #[derive(Debug, Deserialize)]
@@ -119,6 +134,7 @@ pub struct Config {
 pub struct ThreadConfig {
     pub read: NonZeroUsize,
     pub index: NonZeroUsize,
+    #[allow(dead_code)]
     pub write: NonZeroUsize,
 }
 
@@ -132,10 +148,46 @@ impl Default for ThreadConfig {
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct StorageConfig {
+    /// Directory holding one segment per index thread.
+    pub directory: String,
+
+    /// Size in bytes of the write-ahead log that triggers a checkpoint.
+    #[serde(default = "StorageConfig::default_checkpoint_size")]
+    pub checkpoint_size: u64,
+
+    /// Seconds after which pending log records are checkpointed, whatever
+    /// the size of the log.
+    #[serde(default = "StorageConfig::default_checkpoint_interval")]
+    pub checkpoint_interval: u64,
+}
+
+impl StorageConfig {
+    fn default_checkpoint_size() -> u64 {
+        64 * 1024 * 1024
+    }
+
+    fn default_checkpoint_interval() -> u64 {
+        300
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct IndexConfig {
+    /// Records term positions, required by phrase queries.
+    #[serde(default)]
+    pub positions: bool,
+}
+
 #[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct TokenizerConfig {
     pub mode: TokenizerMode,
+    #[serde(default)]
+    pub html: HtmlConfig,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -144,17 +196,50 @@ pub enum TokenizerMode {
     #[default]
     Standard,
     Whitespace,
+    Html,
+}
+
+/// Where the parts of the HTML documents go, with the `html` mode.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct HtmlConfig {
+    pub title: PartConfig,
+    pub headings: PartConfig,
+    pub alt: PartConfig,
+}
+
+impl Default for HtmlConfig {
+    fn default() -> Self {
+        Self {
+            title: PartConfig::Skip,
+            headings: PartConfig::Body,
+            alt: PartConfig::Body,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartConfig {
+    /// In the text of the document.
+    Body,
+    /// In a field of its own, with the boost of the field.
+    Field(f32),
+    Skip,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum NormalizerConfig {
     Case(CaseConfig),
+    Diacritics(bool),
+    Lemmatizer(LemmatizerConfig),
     #[serde(rename = "replacements")]
     Replacer(ReplacerConfig),
     Punctuation(bool),
-    // Stemmer(StemmerConfig),
+    Stemmer(StemmerConfig),
     Stopwords(StopwordsConfig),
+    Unicode(UnicodeConfig),
 }
 
 #[derive(Debug, Deserialize)]
@@ -162,6 +247,15 @@ pub enum NormalizerConfig {
 pub enum CaseConfig {
     Lowercase,
     Uppercase,
+    Fold,
+}
+
+/// Unicode normalization form of the tokens.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnicodeConfig {
+    Nfc,
+    Nfkc,
 }
 
 #[derive(Debug, Deserialize)]
@@ -178,8 +272,52 @@ pub struct ReplacerConfig {
     pub pairs: Option<HashMap<String, String>>,
 }
 
+/// Token filter, run after the normalizers.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
-pub struct StemmerConfig {}
+pub enum FilterConfig {
+    Length(LengthConfig),
+    Numeric(bool),
+    /// Regular expression of the tokens to drop.
+    Pattern(String),
+    #[serde(rename = "keep_words")]
+    Keep(KeepWordsConfig),
+}
+
+/// Bounds of the length of the tokens kept, in characters.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LengthConfig {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct KeepWordsConfig {
+    pub file: Option<String>,
+    pub words: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LemmatizerConfig {
+    /// Directory of the exceptions, index and rules of each part of speech.
+    pub directory: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct StemmerConfig {
+    #[serde(default)]
+    pub language: StemmerLanguage,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StemmerLanguage {
+    #[default]
+    English,
+}
 
 // // TODO: Tokenizer Config
@@ -1,17 +1,60 @@
 use std::fmt::Debug;
 
-use idx::{document::Document, token::Tokens, tokenizer::Tokenizer};
+use idx::{document::Document, facet::Facets, token::Tokens, tokenizer::Tokenizer};
 
 #[derive(Debug)]
 pub struct Descriptor<R: Clone + Debug> {
     document: Document,
     resource: R,
+    facets: Facets,
+
+    /// Named fields, indexed next to the document.
+    fields: Vec<(String, Document)>,
+
+    /// Tokens of the document, when already tokenized.
+    tokens: Option<Tokens>,
 }
 
 impl<R: Clone + Debug> Descriptor<R> {
     #[inline]
     pub fn new(resource: R, document: Document) -> Self {
-        Self { resource, document }
+        Self {
+            resource,
+            document,
+            facets: Facets::new(),
+            fields: Vec::new(),
+            tokens: None,
+        }
+    }
+
+    /// Tokens of the document, tokenized as the index tokenizes documents,
+    /// e.g. extracted along with the fields of an HTML document.
+    #[inline]
+    pub fn with_tokens(mut self, tokens: Tokens) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    #[inline]
+    pub fn with_field(mut self, name: impl Into<String>, document: Document) -> Self {
+        self.fields.push((name.into(), document));
+        self
+    }
+
+    #[inline]
+    pub fn fields(&self) -> &[(String, Document)] {
+        &self.fields
+    }
+
+    #[inline]
+    pub fn with_facets(mut self, facets: Facets) -> Self {
+        self.facets = facets;
+        self
+    }
+
+    #[inline]
+    pub fn facets(&self) -> Facets {
+        self.facets.clone()
     }
 
     #[inline]
@@ -24,8 +67,11 @@ impl<R: Clone + Debug> Descriptor<R> {
         self.resource.clone()
     }
 
+    /// Tokens of the document, tokenized unless already tokenized.
     #[inline]
-    pub fn tokenize(&self, tokenizer: &mut Tokenizer) -> Tokens {
-        self.document.tokenize(tokenizer)
+    pub fn tokenize(&mut self, tokenizer: &mut Tokenizer) -> Tokens {
+        self.tokens
+            .take()
+            .unwrap_or_else(|| self.document.tokenize(tokenizer))
     }
 }
@@ -1,24 +1,61 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, hash::Hash};
 
 use crate::descriptor::Descriptor;
 
 use idx::{
-    aggregate::{Aggregator, HashAggregator, Order},
+    aggregate::{FacetCounts, Page, TopK},
+    error::Error,
+    explain::Explanation,
+    facet::{self, FacetFilter},
+    field::{self, Field, FieldTokens},
+    filter::FilterPipeline,
+    highlight::{Fragment, Highlighter},
     index::Indexer,
     normalizer::NormalizerPipeline,
-    query::Query,
-    score::{Score, Scorer, TfIdfScorer},
+    query::{
+        eval::DEFAULT_MAX_EXPANSIONS,
+        wand::{self, Wand},
+        Expr, Query,
+    },
+    reader::ReaderContext,
+    score::{Bm25fScorer, Score, Scorer},
     tokenizer::Tokenizer,
 };
 
+/// Ranked hits of a query within the requested page, with the number of
+/// hits and the facet counts over every hit, empty when the facade does
+/// not count facets.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct SearchResults<R> {
+    pub hits: Vec<R>,
+    pub total: usize,
+    pub facets: FacetCounts,
+}
+
 #[derive(Debug)]
 pub struct IdxFacade<I: Indexer> {
     pub index: I,
     pub tokenizer: Tokenizer,
     pub pipeline: NormalizerPipeline,
+
+    /// Filters of the normalized tokens, of the documents, their fields
+    /// and the queries.
+    pub filters: FilterPipeline,
+
+    /// Named fields with their own analysis and boost, fields of a
+    /// descriptor missing here are analyzed like the document.
+    pub fields: Vec<Field>,
+
+    /// Number of indexed terms a fuzzy or wildcard query term expands to.
+    pub max_expansions: usize,
+
+    /// Whether searches count the facets of the hits.
+    pub facet_counts: bool,
 }
 
 impl<I: Indexer> IdxFacade<I> {
+    #[allow(dead_code)]
     pub fn new(
         capacity: usize,
         threshold: usize,
@@ -29,66 +66,332 @@ impl<I: Indexer> IdxFacade<I> {
             index: Indexer::new(capacity, threshold),
             tokenizer,
             pipeline,
+            filters: FilterPipeline::new(),
+            fields: Vec::new(),
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+            facet_counts: true,
         }
     }
 
-    pub fn insert(&mut self, descriptor: Descriptor<<I as Indexer>::R>) {
-        let mut tokens = descriptor.tokenize(&mut self.tokenizer);
+    /// Creates a facade over an existing index.
+    pub fn with_index(index: I, tokenizer: Tokenizer, pipeline: NormalizerPipeline) -> Self {
+        Self {
+            index,
+            tokenizer,
+            pipeline,
+            filters: FilterPipeline::new(),
+            fields: Vec::new(),
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+            facet_counts: true,
+        }
+    }
+
+    /// Filters the normalized tokens, at index and query time.
+    pub fn with_filters(mut self, filters: FilterPipeline) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Counts the facets of the hits, enabled by default.
+    #[allow(dead_code)]
+    pub fn with_facet_counts(mut self, facet_counts: bool) -> Self {
+        self.facet_counts = facet_counts;
+        self
+    }
+
+    /// Declares a named field.
+    #[allow(dead_code)]
+    pub fn with_field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn insert(
+        &mut self,
+        mut descriptor: Descriptor<<I as Indexer>::R>,
+    ) -> Result<usize, Error> {
+        let fields = self.analyze(&mut descriptor);
+        let resource = descriptor.resource();
+        let facets = descriptor.facets();
+
+        self.index.insert_fields(resource, fields, facets)
+    }
+
+    #[allow(dead_code)]
+    pub fn delete(&mut self, index: usize) -> Result<bool, Error> {
+        self.index.delete(index)
+    }
+
+    /// Re-indexes the resource at `index`, returning its new index.
+    #[allow(dead_code)]
+    pub fn update(
+        &mut self,
+        index: usize,
+        mut descriptor: Descriptor<<I as Indexer>::R>,
+    ) -> Result<usize, Error> {
+        let fields = self.analyze(&mut descriptor);
         let resource = descriptor.resource();
+        let facets = descriptor.facets();
+
+        self.index.update_fields(index, resource, fields, facets)
+    }
+
+    /// Tokenizes, normalizes and filters the document and each of its
+    /// fields.
+    fn analyze(&mut self, descriptor: &mut Descriptor<<I as Indexer>::R>) -> Vec<FieldTokens> {
+        let mut tokens = descriptor.tokenize(&mut self.tokenizer);
         let word_count = tokens.count();
 
         if !self.pipeline.is_empty() {
             self.pipeline.run(&mut tokens);
         }
 
-        self.index.insert(resource, word_count, &mut tokens);
+        let mut fields = vec![FieldTokens::unnamed(word_count, tokens)];
+
+        for (name, document) in descriptor.fields() {
+            let tokens = match self.fields.iter_mut().find(|field| field.name() == name) {
+                Some(field) => field.analyze(document),
+                None => {
+                    let mut tokens = document.tokenize(&mut self.tokenizer);
+                    let word_count = tokens.count();
+
+                    if !self.pipeline.is_empty() {
+                        self.pipeline.run(&mut tokens);
+                    }
+
+                    FieldTokens::new(name.as_str(), word_count, tokens)
+                }
+            };
+            fields.push(tokens);
+        }
+
+        if !self.filters.is_empty() {
+            for field in fields.iter_mut() {
+                self.filters.run(field.tokens_mut());
+            }
+        }
+
+        fields
     }
 
-    pub fn get(&self, query: Query) -> Vec<I::R> {
+    /// Searches the index, see [`idx::query::parser`] for the query syntax.
+    #[allow(dead_code)]
+    pub fn get(&self, query: Query) -> Result<Vec<I::R>, Error> {
+        self.search(query, &[], Page::default())
+            .map(|results| results.hits)
+    }
+
+    /// Searches the index, keeping the hits accepted by every filter,
+    /// and returns the hits within the page.
+    #[allow(dead_code)]
+    pub fn search(
+        &self,
+        query: Query,
+        filters: &[FacetFilter],
+        page: Page,
+    ) -> Result<SearchResults<I::R>, Error> {
         let reader = self.index.reader();
 
-        let mut tokenizer = self.tokenizer.clone();
-        let mut pipeline = self.pipeline.clone();
+        let Some(expr) = self.parse(&query)? else {
+            return Ok(SearchResults {
+                hits: Vec::new(),
+                total: 0,
+                facets: FacetCounts::new(),
+            });
+        };
+
+        // Without filters, only the best hits of a disjunction are scored,
+        // the other hits are only counted.
+        let (total, facets, hits) = match wand::terms(&expr).filter(|_| filters.is_empty()) {
+            Some(terms) => {
+                let (total, facets) = count_matches(&reader, &terms, self.facet_counts);
+
+                let bm25f_scorer = self.bm25f_scorer(&reader);
+                let mut hits = Wand::new(&bm25f_scorer).top_k(&terms, page.end());
+                hits.drain(..page.offset.min(hits.len()));
+
+                (total, facets, hits)
+            }
+            None => {
+                let mut matches = self.scorer(&reader).evaluate(&expr);
+                let mut facets = facet::filter(&reader, &mut matches, filters);
+                if !self.facet_counts {
+                    facets = FacetCounts::new();
+                }
 
-        let mut tokens = query.tokenize(&mut tokenizer);
+                // Only the hits up to the end of the page are ranked.
+                let mut top = TopK::for_page(page);
+                top.extend(matches);
 
-        if !self.pipeline.is_empty() {
-            pipeline.run(&mut tokens);
-        }
+                (top.total(), facets, top.into_page(page))
+            }
+        };
+
+        let hits = hits
+            .into_iter()
+            .filter_map(|(index, _)| self.index.get(index))
+            .collect::<Vec<_>>();
+
+        Ok(SearchResults {
+            hits,
+            total,
+            facets,
+        })
+    }
+
+    /// Explains the score of the resource at `index` for the query,
+    /// `None` if the resource does not match.
+    pub fn explain(&self, query: Query, index: usize) -> Result<Option<Explanation>, Error> {
+        let reader = self.index.reader();
+
+        let explanation = self
+            .parse(&query)?
+            .and_then(|expr| self.scorer(&reader).explain(&expr, index));
+        Ok(explanation)
+    }
 
-        let hash_aggregator = HashAggregator::new();
-        let mut aggregator = Aggregator::new(hash_aggregator);
+    /// Highlighter of the text of the documents, analyzed like the
+    /// indexed documents.
+    #[allow(dead_code)]
+    pub fn highlighter(&self) -> Highlighter {
+        Highlighter::new(self.tokenizer.clone(), self.pipeline.clone())
+    }
 
-        let tfidf_scorer = TfIdfScorer::new(&reader);
-        let mut scorer = Scorer::new(tfidf_scorer);
+    /// Best fragments of the text of a hit for the query, see
+    /// [`Highlighter::highlight`].
+    ///
+    /// Terms restricted to a named field are not highlighted.
+    #[allow(dead_code)]
+    pub fn highlight(
+        &self,
+        query: Query,
+        text: &str,
+        highlighter: &mut Highlighter,
+    ) -> Result<Vec<Fragment>, Error> {
+        let Some(expr) = self.parse(&query)? else {
+            return Ok(Vec::new());
+        };
+
+        let terms = expr
+            .terms()
+            .into_iter()
+            .filter(|term| !field::is_qualified(term))
+            .collect::<Vec<_>>();
+        Ok(highlighter.highlight(text, &terms))
+    }
 
-        // Vec<(index, score)>
-        scorer.score_and_apply(
-            |scores| {
-                for score in scores {
-                    aggregator.insert(score.0, score.1);
+    /// Parses the query, normalizing and filtering its terms like the
+    /// indexed terms of their field.
+    ///
+    /// Returns `None` if no term is left after normalization.
+    fn parse(&self, query: &Query) -> Result<Option<Expr>, Error> {
+        let mut tokenizer = self.tokenizer.clone();
+        let mut pipeline = self.pipeline.clone();
+        let mut fields = self.fields.clone();
+        let mut filters = self.filters.clone();
+
+        let expr = query.parse()?.analyze_fields(&mut |name, term| {
+            let field = name.and_then(|name| fields.iter_mut().find(|field| field.name() == name));
+            let mut tokens = match field {
+                Some(field) => field.analyze(term).into_tokens(),
+                None => {
+                    let mut tokens = tokenizer.tokenize(term);
+
+                    if !pipeline.is_empty() {
+                        pipeline.run(&mut tokens);
+                    }
+                    tokens
                 }
-            },
-            tokens,
-        );
+            };
+
+            if !filters.is_empty() {
+                filters.run(&mut tokens);
+            }
 
-        // temporary
-        // FIXME: create unified interface for aggregator
-        let sorted = aggregator.sort_by(Order::Descending);
+            tokens
+        });
 
-        sorted
+        Ok(expr)
+    }
+
+    /// BM25F scorer, weighting the fields with their boost.
+    fn scorer<'r>(&self, reader: &'r ReaderContext<'r, I::R>) -> Scorer<'r, Bm25fScorer<'r, I::R>> {
+        Scorer::new(self.bm25f_scorer(reader)).with_max_expansions(self.max_expansions)
+    }
+
+    fn bm25f_scorer<'r>(&self, reader: &'r ReaderContext<'r, I::R>) -> Bm25fScorer<'r, I::R> {
+        self.fields
             .iter()
-            .filter_map(|(index, _)| self.index.get(*index))
-            .collect::<Vec<_>>()
+            .fold(Bm25fScorer::new(reader), |scorer, field| {
+                scorer.with_boost(field.name(), field.boost())
+            })
+    }
+}
+
+/// Number of live resources containing any of the terms, and their facet
+/// counts when `facets` is set, walking the union of the posting cursors.
+fn count_matches<R: Clone + Debug + Hash + Eq>(
+    reader: &ReaderContext<'_, R>,
+    terms: &[String],
+    facets: bool,
+) -> (usize, FacetCounts) {
+    let mut cursors = Vec::new();
+    for term in terms {
+        for (field, term) in reader.fields_of(term) {
+            let key = field::qualify(field.unwrap_or_default(), term);
+            if let Some(mut cursor) = reader.cursor(&key) {
+                cursor.next();
+                cursors.push(cursor);
+            }
+        }
     }
+
+    let mut total = 0;
+    let mut counts = FacetCounts::new();
+
+    while let Some(index) = cursors
+        .iter()
+        .filter_map(|cursor| cursor.current())
+        .map(|entry| entry.get_index())
+        .min()
+    {
+        for cursor in cursors.iter_mut() {
+            cursor.advance_to(index + 1);
+        }
+
+        // Deleted resources have no facets.
+        let Some(resource_facets) = reader.facets(index) else {
+            continue;
+        };
+
+        total += 1;
+        if facets {
+            counts.insert(resource_facets.iter());
+        }
+    }
+
+    (total, counts)
 }
 
 #[cfg(test)]
 mod tests {
     use idx::{
-        index::Index,
-        normalizer::{case::Lowercase, punctuation::Punctuation, NormalizerPipeline, Stopwords},
-        tokenizer::{Standard, Tokenizer},
+        aggregate::{FacetCounts, Page},
+        facet::{FacetFilter, Facets},
+        field::Field,
+        filter::{FilterPipeline, Length, Numeric},
+        index::{Index, Indexer},
+        normalizer::{
+            case::{CaseFold, Lowercase},
+            punctuation::Punctuation,
+            stemmer::{Language, Stemmer},
+            unicode::{Diacritics, Form, UnicodeNormalizer},
+            NormalizerPipeline, Stopwords,
+        },
+        tokenizer::{
+            html::{Part, Route},
+            Html, Standard, Tokenizer, Whitespace,
+        },
     };
 
     use crate::{
@@ -125,17 +428,412 @@ mod tests {
 
         for document in corpus {
             let descriptor = Descriptor::new(document.clone(), document.into());
-            engine.insert(descriptor);
+            engine.insert(descriptor).unwrap();
         }
 
         let target = "cat sat";
 
         let query = Query::new(target);
-        let collection = engine.get(query);
+        let collection = engine.get(query).unwrap();
         println!("collection: {collection:?}");
     }
 
-    #[derive(Clone, Debug)]
+    #[test]
+    fn test_engine_boolean_query() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+        pipeline.insert(Box::new(
+            Stopwords::load("assets/stopwords/en.txt").unwrap(),
+        ));
+
+        let mut engine: IdxFacade<Index<String>> = IdxFacade::new(10, 30, tokenizer, pipeline);
+
+        for document in tiny_test_corpus() {
+            let descriptor = Descriptor::new(document.clone(), document.into());
+            engine.insert(descriptor).unwrap();
+        }
+
+        let collection = engine.get(Query::new("Cat AND NOT Mat")).unwrap();
+        assert_eq!(collection, vec!["the cat sat".to_string()]);
+
+        let collection = engine.get(Query::new("+the (dog OR penguin)")).unwrap();
+        assert_eq!(collection.len(), 2);
+
+        let collection = engine.get(Query::new("Pengu* OR d?g")).unwrap();
+        assert_eq!(collection.len(), 2);
+
+        assert!(engine.get(Query::new("cat AND")).is_err());
+    }
+
+    #[test]
+    fn test_engine_phrase_query() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+
+        let index = Index::new(10, 30).with_positions();
+        let mut engine: IdxFacade<Index<String>> =
+            IdxFacade::with_index(index, tokenizer, pipeline);
+
+        for document in [
+            "connection refused by peer",
+            "peer refused connection",
+            "connection was refused by peer",
+        ] {
+            let descriptor = Descriptor::new(document.to_string(), document.into());
+            engine.insert(descriptor).unwrap();
+        }
+
+        let collection = engine.get(Query::new("\"Connection Refused\"")).unwrap();
+        assert_eq!(collection, vec!["connection refused by peer".to_string()]);
+
+        let collection = engine.get(Query::new("\"connection refused\"~1")).unwrap();
+        assert_eq!(
+            collection,
+            vec![
+                "connection refused by peer".to_string(),
+                "connection was refused by peer".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_engine_facets() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+
+        let mut engine: IdxFacade<Index<String>> = IdxFacade::new(10, 30, tokenizer, pipeline);
+
+        let documents = [
+            ("src/parser.rs", "rs", "parser error handling"),
+            ("src/lexer.rs", "rs", "lexer error"),
+            ("docs/errors.md", "md", "error codes"),
+            ("docs/install.md", "md", "install guide"),
+        ];
+
+        for (path, extension, text) in documents {
+            let facets = Facets::new().with("extension", extension);
+            let descriptor = Descriptor::new(path.to_string(), text.into()).with_facets(facets);
+            engine.insert(descriptor).unwrap();
+        }
+
+        let results = engine
+            .search(Query::new("error"), &[], Page::default())
+            .unwrap();
+        assert_eq!(results.hits.len(), 3);
+        assert_eq!(results.total, 3);
+        assert_eq!(results.facets.get("extension"), [("rs", 2), ("md", 1)]);
+
+        let filters = [FacetFilter::new("extension", "md")];
+        let results = engine
+            .search(Query::new("error"), &filters, Page::default())
+            .unwrap();
+        assert_eq!(results.hits, vec!["docs/errors.md".to_string()]);
+        assert_eq!(results.total, 1);
+        assert_eq!(results.facets.get("extension"), [("rs", 2), ("md", 1)]);
+    }
+
+    #[test]
+    fn test_engine_disjunction() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+        let mut engine: IdxFacade<Index<String>> =
+            IdxFacade::new(10, 30, tokenizer, NormalizerPipeline::new());
+
+        let documents = [
+            ("a", "rs", "error error handling"),
+            ("b", "rs", "lexer error"),
+            ("c", "md", "error codes and error guide"),
+            ("d", "md", "install guide"),
+            ("e", "md", "guide to error handling"),
+            ("f", "txt", "nothing here"),
+        ];
+
+        for (path, extension, text) in documents {
+            let facets = Facets::new().with("extension", extension);
+            let descriptor = Descriptor::new(path.to_string(), text.into()).with_facets(facets);
+            engine.insert(descriptor).unwrap();
+        }
+        engine.delete(1).unwrap();
+
+        // Only the best hits of a disjunction are scored, the excluded
+        // missing term makes the same query evaluated exhaustively.
+        for page in [Page::first(2), Page::new(1, 2), Page::default()] {
+            let pruned = engine
+                .search(Query::new("error guide handling"), &[], page)
+                .unwrap();
+            let exhaustive = engine
+                .search(Query::new("error guide handling -missing"), &[], page)
+                .unwrap();
+
+            assert_eq!(pruned.hits, exhaustive.hits);
+            assert_eq!(pruned.total, 4);
+            assert_eq!(exhaustive.total, 4);
+            assert_eq!(pruned.facets, exhaustive.facets);
+        }
+
+        // Without facet counts, the hits are still counted.
+        let engine = engine.with_facet_counts(false);
+        let results = engine
+            .search(Query::new("error guide handling"), &[], Page::default())
+            .unwrap();
+
+        assert_eq!(results.total, 4);
+        assert_eq!(results.facets, FacetCounts::new());
+    }
+
+    #[test]
+    fn test_engine_pagination() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+        let mut engine: IdxFacade<Index<String>> =
+            IdxFacade::new(10, 30, tokenizer, NormalizerPipeline::new());
+
+        // Documents repeating the term more rank first.
+        let documents = (1..=7)
+            .rev()
+            .map(|words| vec!["fox"; words].join(" "))
+            .collect::<Vec<_>>();
+
+        for document in &documents {
+            engine
+                .insert(Descriptor::new(document.clone(), document.as_str().into()))
+                .unwrap();
+        }
+        engine
+            .insert(Descriptor::new("dog".to_string(), "dog".into()))
+            .unwrap();
+
+        let page = |offset, limit| {
+            engine
+                .search(Query::new("fox"), &[], Page::new(offset, limit))
+                .unwrap()
+        };
+
+        let first = page(0, 3);
+        assert_eq!(first.total, 7);
+        assert_eq!(first.hits, documents[..3]);
+
+        let second = page(3, 3);
+        assert_eq!(second.total, 7);
+        assert_eq!(second.hits, documents[3..6]);
+
+        let last = page(6, 3);
+        assert_eq!(last.hits, documents[6..]);
+        assert!(page(9, 3).hits.is_empty());
+        assert_eq!(page(9, 3).total, 7);
+    }
+
+    #[test]
+    fn test_engine_explain() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+
+        let mut engine: IdxFacade<Index<String>> = IdxFacade::new(10, 30, tokenizer, pipeline)
+            .with_field(Field::new(
+                "title",
+                Tokenizer::Whitespace(Whitespace::new()),
+                NormalizerPipeline::new(),
+            ));
+
+        for (title, document) in [("cats", "The cat sat"), ("dogs", "The dog barked")] {
+            let descriptor = Descriptor::new(title.to_string(), document.into())
+                .with_field("title", title.into());
+            engine.insert(descriptor).unwrap();
+        }
+
+        let explanation = engine
+            .explain(Query::new("cat title:cats"), 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(explanation.description, "or, sum of matching clauses:");
+        assert_eq!(explanation.details.len(), 2);
+        assert!(engine.explain(Query::new("cat"), 1).unwrap().is_none());
+
+        // Leaves are printed without details.
+        let json = serde_json::to_value(&explanation).unwrap();
+        let weight = &json["details"][1];
+        assert_eq!(weight["description"], "weight(title:cats), idf * tf of:");
+        assert_eq!(
+            weight["details"][0]["details"][0]["description"],
+            "documents"
+        );
+        assert_eq!(weight["details"][0]["details"][0]["value"], 2.0);
+        assert!(weight["details"][0]["details"][0].get("details").is_none());
+    }
+
+    #[test]
+    fn test_engine_highlight() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+        pipeline.insert(Box::new(Punctuation::new()));
+
+        let mut engine: IdxFacade<Index<String>> = IdxFacade::new(10, 30, tokenizer, pipeline);
+        let text = "Connection REFUSED by peer: the server refused a connection.";
+        engine
+            .insert(Descriptor::new("log".to_string(), text.into()))
+            .unwrap();
+
+        let hits = engine.get(Query::new("refused")).unwrap();
+        assert_eq!(hits, ["log"]);
+
+        let mut highlighter = engine.highlighter().with_markers("**", "**");
+        let fragments = engine
+            .highlight(
+                Query::new("Refused title:peer -server"),
+                text,
+                &mut highlighter,
+            )
+            .unwrap();
+
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(
+            fragments[0].text,
+            "Connection **REFUSED** by peer: the server **refused** a connection"
+        );
+    }
+
+    #[test]
+    fn test_engine_stemming() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+        pipeline.insert(Box::new(Stemmer::new(Language::English)));
+
+        let mut engine: IdxFacade<Index<String>> = IdxFacade::new(10, 30, tokenizer, pipeline);
+        for document in [
+            "The quick brown fox jumps.",
+            "Foxes jump over lazy dogs.",
+            "Jumping foxes and sleeping dogs.",
+            "The dog is lazy.",
+        ] {
+            let descriptor = Descriptor::new(document.to_string(), document.into());
+            engine.insert(descriptor).unwrap();
+        }
+
+        let mut collection = engine.get(Query::new("jumping AND fox")).unwrap();
+        collection.sort();
+        assert_eq!(
+            collection,
+            [
+                "Foxes jump over lazy dogs.",
+                "Jumping foxes and sleeping dogs.",
+                "The quick brown fox jumps.",
+            ]
+        );
+
+        let mut highlighter = engine.highlighter().with_markers("[", "]");
+        let fragments = engine
+            .highlight(Query::new("foxes"), "A fox and two foxes", &mut highlighter)
+            .unwrap();
+        assert_eq!(fragments[0].text, "A [fox] and two [foxes]");
+    }
+
+    #[test]
+    fn test_engine_unicode_folding() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(UnicodeNormalizer::new(Form::Nfkc)));
+        pipeline.insert(Box::new(CaseFold::new()));
+        pipeline.insert(Box::new(Diacritics::new()));
+
+        let mut engine: IdxFacade<Index<String>> = IdxFacade::new(10, 30, tokenizer, pipeline);
+        for document in ["ÉCOLE normale", "cafe\u{301} crème", "Straße"] {
+            let descriptor = Descriptor::new(document.to_string(), document.into());
+            engine.insert(descriptor).unwrap();
+        }
+
+        assert_eq!(engine.get(Query::new("école")).unwrap(), ["ÉCOLE normale"]);
+        assert_eq!(
+            engine.get(Query::new("CAFÉ AND creme")).unwrap(),
+            ["cafe\u{301} crème"]
+        );
+        assert_eq!(engine.get(Query::new("strasse")).unwrap(), ["Straße"]);
+    }
+
+    #[test]
+    fn test_engine_filters() {
+        let tokenizer = Tokenizer::Standard(Standard::new());
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+
+        let mut filters = FilterPipeline::new();
+        filters.insert(Box::new(Length::new(3, 20)));
+        filters.insert(Box::new(Numeric::new()));
+
+        let mut engine: IdxFacade<Index<String>> =
+            IdxFacade::new(10, 30, tokenizer, pipeline).with_filters(filters);
+        for document in ["Rust 1.80 release notes", "Go 1.22 release"] {
+            let descriptor = Descriptor::new(document.to_string(), document.into());
+            engine.insert(descriptor).unwrap();
+        }
+
+        let reader = engine.index.reader();
+        assert_eq!(reader.document_frequency("80"), None);
+        assert_eq!(reader.document_frequency("go"), None);
+        assert_eq!(reader.document_frequency("release"), Some(2));
+
+        // Filtered query terms are dropped too.
+        assert_eq!(engine.get(Query::new("release AND 80")).unwrap().len(), 2);
+        assert!(engine.get(Query::new("go")).unwrap().is_empty());
+        assert_eq!(
+            engine.get(Query::new("rust AND notes")).unwrap(),
+            ["Rust 1.80 release notes"]
+        );
+    }
+
+    #[test]
+    fn test_engine_html() {
+        let html = Html::new().with_route(Part::Title, Route::Field);
+        let mut pipeline = NormalizerPipeline::new();
+        pipeline.insert(Box::new(Lowercase::new()));
+
+        let title = Field::new(
+            Part::Title.name(),
+            Tokenizer::Standard(Standard::new()),
+            pipeline.clone(),
+        )
+        .with_boost(2.0);
+        let mut engine: IdxFacade<Index<String>> =
+            IdxFacade::new(10, 30, Tokenizer::Html(html.clone()), pipeline).with_field(title);
+
+        let documents = [
+            ("go.html", "<title>Go</title><h1>Coming from Rust</h1><img alt=\"gopher\"><script>rust()</script>"),
+            ("rust.html", "<html><head><title>Rust</title></head><body><p>A guide</p></body></html>"),
+        ];
+        for (path, document) in documents {
+            let (tokens, fields) = html.extract(document);
+            let descriptor = fields.into_iter().fold(
+                Descriptor::new(path.to_string(), document.into()).with_tokens(tokens),
+                |descriptor, (name, text)| descriptor.with_field(name, text.into()),
+            );
+            engine.insert(descriptor).unwrap();
+        }
+
+        // The title is only in its field, headings and alt text in the body.
+        assert_eq!(engine.get(Query::new("title:rust")).unwrap(), ["rust.html"]);
+        assert_eq!(
+            engine.get(Query::new("coming AND gopher")).unwrap(),
+            ["go.html"]
+        );
+        assert!(engine
+            .get(Query::new("html OR head OR script"))
+            .unwrap()
+            .is_empty());
+
+        // The boosted title ranks first.
+        assert_eq!(
+            engine.get(Query::new("rust")).unwrap(),
+            ["rust.html", "go.html"]
+        );
+    }
+
+    #[derive(Clone, Debug, Hash, PartialEq, Eq)]
     struct Webpage {
         url: String,
         title: String,
@@ -179,19 +877,45 @@ mod tests {
             Stopwords::load("assets/stopwords/en.txt").unwrap(),
         ));
 
+        let mut url_pipeline = NormalizerPipeline::new();
+        url_pipeline.insert(Box::new(Lowercase::new()));
+
+        let title = Field::new("title", tokenizer.clone(), pipeline.clone()).with_boost(2.0);
+        let url = Field::new(
+            "url",
+            Tokenizer::Whitespace(Whitespace::new()),
+            url_pipeline,
+        );
+
         let mut engine: IdxFacade<Index<Webpage>> =
-            IdxFacade::new(10, 30, tokenizer.clone(), pipeline);
+            IdxFacade::new(10, 30, tokenizer.clone(), pipeline)
+                .with_field(title)
+                .with_field(url);
 
         for document in corpus {
-            let doc = document.excerpt.clone();
-            let descriptor = Descriptor::new(document.clone(), doc.into());
-            engine.insert(descriptor);
+            let descriptor = Descriptor::new(document.clone(), "".into())
+                .with_field("title", document.title.as_str().into())
+                .with_field("url", document.url.as_str().into())
+                .with_field("excerpt", document.excerpt.as_str().into());
+            engine.insert(descriptor).unwrap();
         }
 
-        let target = "AI";
-
-        let query = Query::new(target);
-        let collection = engine.get(query);
-        println!("collection: {collection:?}");
+        let titles = |query: &str| {
+            engine
+                .get(Query::new(query))
+                .unwrap()
+                .into_iter()
+                .map(|webpage| webpage.title)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles("AI"), ["The Future of AI"]);
+        assert_eq!(titles("title:rust"), ["Rust Programming Guide"]);
+        assert_eq!(
+            titles("url:https://example.com/ai-future"),
+            ["The Future of AI"]
+        );
+        assert!(titles("title:learn").is_empty());
+        assert_eq!(titles("excerpt:learn"), ["Cybersecurity Essentials"]);
     }
 }
@@ -6,23 +6,42 @@ extern crate clap;
 extern crate crossbeam_channel;
 extern crate tokio;
 
-use std::time::Duration;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use idx::{
+    codec::StringCodec,
     document::Document,
-    index::Index,
+    error::Error,
+    facet::Facets,
+    field::Field,
+    filter::{FilterPipeline, KeepWords, Length, Numeric, Pattern},
+    index::{CoreIndex, Index, Indexer},
     normalizer::{
-        case::{Lowercase, Uppercase},
+        case::{CaseFold, Lowercase, Uppercase},
+        lemmatize::Lemmatizer,
         punctuation::Punctuation,
         replace::TokenReplacer,
+        stemmer::{Language, Stemmer},
+        unicode::{Diacritics, Form, UnicodeNormalizer},
         NormalizerPipeline, Stopwords,
     },
     query::Query,
-    tokenizer::{Standard, Tokenizer, Whitespace},
+    tokenizer::{
+        html::{Part, Route},
+        Html, Standard, Tokenizer, Whitespace,
+    },
+    wal::WriteAheadLog,
 };
 
 use crate::{
-    cli::{CaseConfig, Cli, NormalizerConfig, TokenizerMode},
+    cli::{
+        CaseConfig, Cli, FilterConfig, NormalizerConfig, PartConfig, StemmerLanguage,
+        TokenizerMode, UnicodeConfig,
+    },
     descriptor::Descriptor,
     engine::IdxFacade,
 };
@@ -35,16 +54,56 @@ const INDEX_CAPACITY: usize = 100;
 const THRESHOLD_CAPACITY: usize = 80;
 
 #[derive(Clone, Debug)]
-struct Engine {}
+struct Engine {
+    /// HTML tokenizer, extracting the parts of the documents routed to
+    /// fields.
+    html: Option<Html>,
+}
 
 impl Engine {
     pub fn new() -> Self {
-        Self {}
+        Self { html: None }
+    }
+
+    pub fn with_html(mut self, html: Html) -> Self {
+        self.html = Some(html);
+        self
     }
 
     pub fn read(&self, path: String, buffer: &mut Vec<u8>) -> Descriptor<String> {
         let document = self.document(buffer);
-        Descriptor::new(path, document)
+        let facets = self.facets(&path);
+
+        let descriptor = Descriptor::new(path, document).with_facets(facets);
+        let Some(html) = self.html.as_ref() else {
+            return descriptor;
+        };
+
+        // The markup is parsed once, for the tokens and the fields.
+        let (tokens, fields) = html.extract(descriptor.inner());
+        fields.into_iter().fold(
+            descriptor.with_tokens(tokens),
+            |descriptor, (name, text)| descriptor.with_field(name, text.into()),
+        )
+    }
+
+    /// Extension and parent directory of the file.
+    fn facets(&self, path: &str) -> Facets {
+        let path = Path::new(path);
+        let mut facets = Facets::new();
+
+        if let Some(extension) = path.extension() {
+            facets.insert("extension", extension.to_string_lossy());
+        }
+
+        if let Some(directory) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            facets.insert("directory", directory.to_string_lossy());
+        }
+
+        facets
     }
 
     fn document(&self, buffer: &mut Vec<u8>) -> Document {
@@ -59,14 +118,41 @@ async fn main() {
     let cli = Cli::parse();
 
     let config = cli.init().unwrap();
+    let explain = cli.explain.clone().map(|query| (query, cli.resource));
     let thread_config = config.thread;
 
-    let engine = Engine::new();
+    let mut engine = Engine::new();
     let mut pipeline = NormalizerPipeline::new();
 
+    // Parts of the HTML documents indexed as fields, with their boost.
+    let mut boosts = Vec::new();
+
     let tokenizer = match config.tokenizer.mode {
         TokenizerMode::Standard => Tokenizer::Standard(Standard::new()),
         TokenizerMode::Whitespace => Tokenizer::Whitespace(Whitespace::new()),
+        TokenizerMode::Html => {
+            let html_config = &config.tokenizer.html;
+            let parts = [
+                (Part::Title, &html_config.title),
+                (Part::Headings, &html_config.headings),
+                (Part::Alt, &html_config.alt),
+            ];
+
+            let html = parts.into_iter().fold(Html::new(), |html, (part, config)| {
+                let route = match config {
+                    PartConfig::Body => Route::Body,
+                    PartConfig::Field(boost) => {
+                        boosts.push((part.name(), *boost));
+                        Route::Field
+                    }
+                    PartConfig::Skip => Route::Skip,
+                };
+                html.with_route(part, route)
+            });
+
+            engine = engine.with_html(html.clone());
+            Tokenizer::Html(html)
+        }
     };
 
     for field in config.normalizer {
@@ -78,8 +164,25 @@ async fn main() {
                 CaseConfig::Uppercase => {
                     pipeline.insert(Box::new(Uppercase::new()));
                 }
+                CaseConfig::Fold => {
+                    pipeline.insert(Box::new(CaseFold::new()));
+                }
             },
 
+            NormalizerConfig::Unicode(form) => {
+                let form = match form {
+                    UnicodeConfig::Nfc => Form::Nfc,
+                    UnicodeConfig::Nfkc => Form::Nfkc,
+                };
+                pipeline.insert(Box::new(UnicodeNormalizer::new(form)));
+            }
+
+            NormalizerConfig::Diacritics(status) => {
+                if status {
+                    pipeline.insert(Box::new(Diacritics::new()));
+                }
+            }
+
             NormalizerConfig::Stopwords(config) => {
                 if let Some(path) = config.file {
                     match Stopwords::load(&path) {
@@ -100,7 +203,7 @@ async fn main() {
             }
 
             NormalizerConfig::Replacer(config) => {
-                if let Some(file) = config.file {
+                if let Some(_file) = config.file {
                     // decide!("txt file", "json file", "support both");
                     // read file line by line
 
@@ -122,16 +225,83 @@ async fn main() {
                     pipeline.insert(Box::new(Punctuation::new()));
                 }
             }
+
+            NormalizerConfig::Lemmatizer(config) => match Lemmatizer::load(&config.directory) {
+                Ok(lemmatizer) => {
+                    pipeline.insert(Box::new(lemmatizer));
+                }
+                Err(error) => panic!("{error:?}"),
+            },
+
+            NormalizerConfig::Stemmer(config) => {
+                let language = match config.language {
+                    StemmerLanguage::English => Language::English,
+                };
+                pipeline.insert(Box::new(Stemmer::new(language)));
+            }
         };
     }
 
+    let mut filters = FilterPipeline::new();
+
+    for filter in config.filter {
+        match filter {
+            FilterConfig::Length(config) => {
+                let length = Length::new(config.min.unwrap_or(0), config.max.unwrap_or(usize::MAX));
+                filters.insert(Box::new(length));
+            }
+
+            FilterConfig::Numeric(status) => {
+                if status {
+                    filters.insert(Box::new(Numeric::new()));
+                }
+            }
+
+            FilterConfig::Pattern(pattern) => match Pattern::new(&pattern) {
+                Ok(pattern) => {
+                    filters.insert(Box::new(pattern));
+                }
+                Err(error) => panic!("{error:?}"),
+            },
+
+            FilterConfig::Keep(config) => {
+                if let Some(path) = config.file {
+                    match KeepWords::load(&path) {
+                        Ok(words) => filters.insert(Box::new(words)),
+                        Err(error) => panic!("{error:?}"),
+                    };
+
+                    continue;
+                }
+
+                if let Some(words) = config.words {
+                    filters.insert(Box::new(KeepWords::new(words)));
+                    continue;
+                }
+
+                eprintln!("Error: No valid keep words file or words provided");
+                return;
+            }
+        }
+    }
+
     println!("{pipeline:#?}");
 
-    let (read_tx, read_rx) = unbounded();
-    let (index_tx, index_rx) = unbounded();
+    if let Some(storage) = config.storage.as_ref() {
+        if let Err(error) = std::fs::create_dir_all(&storage.directory) {
+            eprintln!("Error: Failed to create storage directory: {error}");
+            return;
+        }
+    }
+
+    let (read_tx, read_rx) = unbounded::<String>();
+
+    // One channel per index thread, see `shard`.
+    let (index_txs, index_rxs): (Vec<_>, Vec<_>) =
+        (0..thread_config.index.get()).map(|_| unbounded()).unzip();
 
     (0..thread_config.read.get()).for_each(|_| {
-        let index_tx = index_tx.clone();
+        let index_txs = index_txs.clone();
         let read_rx = read_rx.clone();
         let context = engine.clone();
 
@@ -145,9 +315,10 @@ async fn main() {
                         match File::open(&path).await {
                             Ok(mut file) => match file.read_to_end(&mut buffer).await {
                                 Ok(_) => {
+                                    let thread = shard(&path, index_txs.len());
                                     let descriptor = context.read(path, &mut buffer);
                                     // println!("{descriptor:?}");
-                                    index_tx.send(descriptor).unwrap();
+                                    index_txs[thread].send(descriptor).unwrap();
                                 }
                                 Err(_) => todo!(),
                             },
@@ -159,27 +330,133 @@ async fn main() {
         });
     });
 
-    (0..thread_config.index.get()).for_each(|_| {
-        let rx = index_rx.clone();
+    let snapshots = (0..thread_config.index.get()).map(|id| {
+        let rx = index_rxs[id].clone();
+
+        // Each index thread owns its own segment and write-ahead log.
+        let storage = config.storage.as_ref().map(|storage| {
+            let directory = Path::new(&storage.directory);
+            (
+                directory.join(format!("segment-{id}.idx")),
+                directory.join(format!("wal-{id}.log")),
+            )
+        });
+
+        let index = match storage.as_ref() {
+            Some((segment, _)) => {
+                Index::load(segment, &StringCodec, INDEX_CAPACITY, THRESHOLD_CAPACITY)
+            }
+            None => Ok(Index::new(INDEX_CAPACITY, THRESHOLD_CAPACITY)),
+        };
+
+        // Positions are enabled before replaying the log, so replayed inserts record them.
+        let index = index
+            .map(|index| match config.index.positions {
+                true => index.with_positions(),
+                false => index,
+            })
+            .and_then(|index| match storage.as_ref() {
+                Some((_, wal)) => {
+                    WriteAheadLog::open(wal, StringCodec).and_then(|wal| index.with_wal(wal))
+                }
+                None => Ok(index),
+            });
+
+        let index = index?;
+
+        // Checkpoints rewrite the whole segment, the log keeps the inserts
+        // durable until it grows past the size or interval threshold.
+        let checkpoint = config
+            .storage
+            .as_ref()
+            .zip(storage)
+            .map(|(config, (segment, _))| {
+                let interval = Duration::from_secs(config.checkpoint_interval);
+                (segment, config.checkpoint_size, interval)
+            });
+        let mut facade = IdxFacade::with_index(index, tokenizer.clone(), pipeline.clone())
+            .with_filters(filters.clone());
+
+        // Fields of the HTML parts are plain text, once extracted.
+        for &(name, boost) in boosts.iter() {
+            let tokenizer = Tokenizer::Standard(Standard::new());
+            let field = Field::new(name, tokenizer, pipeline.clone()).with_boost(boost);
+            facade = facade.with_field(field);
+        }
 
-        let mut facade: IdxFacade<Index<String>> = IdxFacade::new(
-            INDEX_CAPACITY,
-            THRESHOLD_CAPACITY,
-            tokenizer.clone(),
-            pipeline.clone(),
-        );
+        // Searches run on published snapshots, while the thread keeps indexing.
+        facade.index.publish();
+        let snapshots = facade.index.snapshots();
 
-        let value = pipeline.clone();
+        let mut checkpointed = Instant::now();
+        let explain = explain.clone();
 
         std::thread::spawn(move || loop {
             while let Ok(descriptor) = rx.recv() {
-                facade.insert(descriptor);
+                match facade.insert(descriptor) {
+                    Ok(_) => {}
+                    // Already indexed, re-reads are not re-indexed.
+                    Err(Error::Duplicate(_)) => continue,
+                    Err(error) => {
+                        eprintln!("Error: Failed to index document: {error}");
+                        continue;
+                    }
+                }
 
                 println!("{facade:#?}");
+
+                // Publish once the pending documents are drained.
+                if !rx.is_empty() {
+                    continue;
+                }
+
+                facade.index.publish();
+
+                if let Some((query, resource)) = explain.as_ref() {
+                    match facade.explain(Query::new(query), *resource) {
+                        Ok(Some(explanation)) => match serde_json::to_string_pretty(&explanation) {
+                            Ok(json) => println!("{json}"),
+                            Err(error) => {
+                                eprintln!("Error: Failed to serialize explanation: {error}")
+                            }
+                        },
+                        Ok(None) => println!("Resource {resource} does not match: {query}"),
+                        Err(error) => eprintln!("Error: Failed to explain query: {error}"),
+                    }
+                }
+
+                let Some((path, size, interval)) = checkpoint.as_ref() else {
+                    continue;
+                };
+
+                let pending = facade.index.wal_size();
+                if pending == 0 || (pending < *size && checkpointed.elapsed() < *interval) {
+                    continue;
+                }
+
+                match facade.index.checkpoint(path, &StringCodec) {
+                    Ok(_) => checkpointed = Instant::now(),
+                    Err(error) => eprintln!("Error: Failed to checkpoint index: {error}"),
+                }
             }
         });
+
+        Ok(snapshots)
     });
 
+    // A corrupted, unreadable or older segment stops the engine, instead of
+    // indexing on top of a partial index.
+    let snapshots = match snapshots.collect::<Result<Vec<_>, Error>>() {
+        Ok(snapshots) => snapshots,
+        Err(error) => {
+            eprintln!("Error: Failed to open index: {error}");
+            return;
+        }
+    };
+
+    let mut generations = Vec::new();
+    let mut global = CoreIndex::merge([]);
+
     // // Main thread will handle server.
     // // Simulating server.
     loop {
@@ -189,8 +466,34 @@ async fn main() {
             .unwrap();
 
         tokio::time::sleep(Duration::from_secs(2)).await;
+
+        // Global view over the latest snapshots of the thread-local indexes,
+        // index threads are never blocked. It is only merged again once a
+        // thread published new documents.
+        let latest = snapshots
+            .iter()
+            .map(|snapshots| snapshots.load())
+            .collect::<Vec<_>>();
+        let current = latest
+            .iter()
+            .map(|snapshot| snapshot.generation())
+            .collect::<Vec<_>>();
+
+        if current != generations {
+            global = CoreIndex::merge(latest.iter().map(|snapshot| snapshot.core()));
+            generations = current;
+        }
+        println!("global: {} documents", global.reader().total_documents());
     }
 }
 
+/// Index thread of the path, so a path is only indexed by one thread and
+/// the duplicate check of its index holds.
+fn shard(path: &str, threads: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    (hasher.finish() % threads as u64) as usize
+}
+
 // TODO: Conditional Variable
 // TODO: Use shared memory, instead of channels.